@@ -1,4 +1,4 @@
-use clap::{ArgAction, Parser, ValueEnum, ValueHint};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum, ValueHint};
 use org_exporter::{ConfigOptions, Exporter};
 use serde::Deserialize;
 
@@ -7,7 +7,12 @@ use serde::Deserialize;
 #[command(author = "Laith Bahodi <laithbahodi@gmail.com>")]
 #[command(about = "Exporter for Org Mode Content")]
 #[command(author, version, about, long_about=None)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+
     /// Default is html
     #[arg(short, long, value_enum)]
     pub backend: Option<Backend>,
@@ -16,15 +21,19 @@ pub struct Cli {
     ///
     /// If the input is a directory, `org-rust` will walk and export every file
     /// to the output directory maintaining the directory structure.
+    ///
+    /// Required unless set in the config file.
     #[arg(value_hint = ValueHint::FilePath)]
-    pub input: String,
+    pub input: Option<String>,
 
     /// Output path
     ///
     /// The output type corresponds to the type of the input. I.e. if the input path is a file
     /// then the output path will be a file, same for a directory.
+    ///
+    /// Required unless set in the config file.
     #[arg(short, long, value_hint = ValueHint::AnyPath)]
-    pub output: String,
+    pub output: Option<String>,
 
     /// Path to config file
     ///
@@ -39,6 +48,20 @@ pub struct Cli {
     pub verbose: bool,
 }
 
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Write the contents of source blocks to the files named by their `:tangle` header argument
+    Tangle {
+        /// Input file
+        #[arg(value_hint = ValueHint::FilePath)]
+        input: String,
+
+        /// Display the files that are written
+        #[arg(short, long, action = ArgAction::SetTrue)]
+        verbose: bool,
+    },
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Deserialize)]
 pub enum Backend {
     Html,
@@ -1,5 +1,5 @@
 use anyhow::bail;
-use org_exporter::ConfigOptions;
+use org_exporter::{ConfigOptions, ExportOverrides, ExportSettings};
 use std::fs::{self, read_to_string, OpenOptions};
 use std::io::{stdout, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
@@ -12,6 +12,7 @@ use clap::Parser;
 mod template;
 use crate::cli::Backend;
 mod cli;
+mod tangle;
 mod types;
 mod utils;
 
@@ -25,6 +26,9 @@ fn main() {
 /// Function that works through the entire pipeline
 fn run() -> anyhow::Result<()> {
     let cli_params = cli::Cli::parse();
+    if let Some(cli::Command::Tangle { input, verbose }) = cli_params.command {
+        return tangle::tangle_file(Path::new(&input), verbose);
+    }
     let config_params: cli::Cli;
 
     if let Some(config_path) = cli_params.config {
@@ -45,15 +49,12 @@ fn run() -> anyhow::Result<()> {
         None => config_params.backend,
         r => r,
     };
-    let output_path = if cli_params.output == "" {
-        config_params.output
-    } else {
-        cli_params.output
+    // only required when no subcommand is given, so clap can't check them
+    let Some(input_path) = cli_params.input.or(config_params.input) else {
+        bail!("No input path given, pass one or set it in the config file");
     };
-    let input_path = if cli_params.input == "" {
-        config_params.input
-    } else {
-        cli_params.input
+    let Some(output_path) = cli_params.output.or(config_params.output) else {
+        bail!("No output path given, pass one with --output or set it in the config file");
     };
 
     let verbose = if cli_params.verbose {
@@ -160,7 +161,22 @@ fn run() -> anyhow::Result<()> {
                 }
             }
 
-            let conf = ConfigOptions::new(Some(file_path.to_path_buf()));
+            let mut conf = ConfigOptions::new(Some(file_path.to_path_buf()));
+            let settings = ExportSettings::resolve(
+                &parser_output,
+                conf.export_settings(),
+                conf.export_overrides(),
+            );
+            let template_path = parser_output.keywords.get("template_path");
+            if template_path.is_some() {
+                // the template places the document's metadata itself
+                conf.set_export_overrides(ExportOverrides {
+                    title: Some(false),
+                    author: Some(false),
+                    date: Some(false),
+                    ..Default::default()
+                });
+            }
             if let Err(err_vec) = backend.export(&parser_output, &mut exported_content, conf) {
                 let mut build_str = String::new();
                 for e in err_vec {
@@ -171,7 +187,7 @@ fn run() -> anyhow::Result<()> {
             }
 
             // handle a template (if needed)
-            if let Some(template_path) = parser_output.keywords.get("template_path") {
+            if let Some(template_path) = template_path {
                 // evaluate relative paths if needed
                 let template_path = relative_path_from(file_path, Path::new(template_path))?;
                 let template_contents = std::fs::read_to_string(&template_path).map_err(|e| {
@@ -185,6 +201,7 @@ fn run() -> anyhow::Result<()> {
                     &template_path,
                     &template_contents,
                     &exported_content,
+                    settings,
                 );
                 exported_content = t.process()?;
             }
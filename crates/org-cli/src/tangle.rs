@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::io::{stdout, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use org_parser::element::{Block, BlockSwitches};
+use org_parser::{Expr, HeaderArgs, NodeID, NowebContext, Parser, Tangle};
+
+use crate::types::CliError;
+use crate::utils::{mkdir_recursively, path_relative_to, target_path_from};
+
+/// The blocks tangled into a single file, in document order.
+#[derive(Debug)]
+struct TangleTarget {
+    path: PathBuf,
+    contents: String,
+    /// `:shebang`, taken from the first block that has one
+    shebang: Option<String>,
+    /// `:mkdirp yes`, creates missing parent directories
+    mkdirp: bool,
+    blocks: usize,
+}
+
+/// Tangles every source block of an org file, writing each `:tangle` target.
+pub fn tangle_file(path: &Path, verbose: bool) -> anyhow::Result<()> {
+    let file_contents = read_to_string(path).map_err(|e| {
+        CliError::from(e)
+            .with_path(path)
+            .with_cause("failed to read input file")
+    })?;
+    let parsed = org_parser::parse_org(&file_contents);
+
+    let mut stdout = BufWriter::new(stdout());
+    let targets = collect_targets(&parsed, path)?;
+    let mut num_blocks = 0;
+    for target in &targets {
+        write_target(target)?;
+        num_blocks += target.blocks;
+        if verbose {
+            writeln!(
+                stdout,
+                " -- tangled: {} ({} blocks)",
+                target.path.display(),
+                target.blocks
+            )?;
+        }
+    }
+    writeln!(
+        stdout,
+        "Tangled {num_blocks} code blocks from {}",
+        path.display()
+    )?;
+    Ok(())
+}
+
+fn write_target(target: &TangleTarget) -> Result<(), CliError> {
+    if let Some(parent) = target.path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            if target.mkdirp {
+                mkdir_recursively(parent)?;
+            } else {
+                return Err(CliError::new()
+                    .with_path(parent)
+                    .with_cause("directory does not exist, set `:mkdirp yes` to create it"));
+            }
+        }
+    }
+
+    let mut contents = String::new();
+    if let Some(shebang) = &target.shebang {
+        contents.push_str(shebang);
+        contents.push('\n');
+    }
+    contents.push_str(&target.contents);
+    std::fs::write(&target.path, contents).map_err(|e| {
+        CliError::from(e)
+            .with_path(&target.path)
+            .with_cause("error in writing tangled file")
+    })?;
+
+    if target.shebang.is_some() {
+        make_executable(&target.path)?;
+    }
+    Ok(())
+}
+
+/// Tangled files with a shebang are made executable, as in Emacs.
+#[cfg(unix)]
+fn make_executable(path: &Path) -> Result<(), CliError> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+        .map_err(|e| CliError::from(e).with_path(path))
+}
+
+/// Files can't be marked executable outside of unix, so there is nothing to do.
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> Result<(), CliError> {
+    Ok(())
+}
+
+/// Walks the document, grouping blocks by the file they are tangled to.
+fn collect_targets(parsed: &Parser, org_path: &Path) -> Result<Vec<TangleTarget>, CliError> {
+    let mut targets: Vec<TangleTarget> = Vec::new();
+    let mut indices: HashMap<PathBuf, usize> = HashMap::new();
+    let mut stack = vec![(parsed.pool.root_id(), None)];
+    // blocks seen under each heading, used to describe :comments links
+    let mut counters: HashMap<Option<NodeID>, usize> = HashMap::new();
+
+    // depth first, in document order
+    while let Some((node_id, heading)) = stack.pop() {
+        let node = &parsed.pool[node_id];
+        let Expr::Block(Block::Src {
+            language, switches, ..
+        }) = &node.obj
+        else {
+            let heading = if let Expr::Heading(_) = node.obj {
+                Some(node_id)
+            } else {
+                heading
+            };
+            if let Some(children) = node.obj.children() {
+                stack.extend(children.iter().rev().map(|&id| (id, heading)));
+            }
+            continue;
+        };
+
+        let counter = counters.entry(heading).or_default();
+        *counter += 1;
+        let block_num = *counter;
+
+        let args = HeaderArgs::resolve(parsed, node_id);
+        let path = match args.tangle {
+            Tangle::No => continue,
+            Tangle::Yes => {
+                let Some(lang) = language else {
+                    continue;
+                };
+                org_path.with_extension(lang_extension(lang))
+            }
+            Tangle::File(file) => target_path_from(org_path, Path::new(HeaderArgs::unquote(file)))?,
+        };
+
+        let index = *indices.entry(path.clone()).or_insert_with(|| {
+            targets.push(TangleTarget {
+                path: path.clone(),
+                contents: String::new(),
+                shebang: None,
+                mkdirp: false,
+                blocks: 0,
+            });
+            targets.len() - 1
+        });
+        let target = &mut targets[index];
+
+        if target.shebang.is_none() {
+            target.shebang = args
+                .other
+                .get("shebang")
+                .map(|val| HeaderArgs::unquote(val).to_owned());
+        }
+        target.mkdirp |= args
+            .other
+            .get("mkdirp")
+            .is_some_and(|val| matches!(*val, "yes" | "t"));
+
+        if target.blocks > 0 {
+            target.contents.push('\n');
+        }
+        let expanded = parsed
+            .expand_noweb(node_id, NowebContext::Tangle)
+            .map_err(|e| {
+                CliError::new()
+                    .with_path(org_path)
+                    .with_cause(&e.to_string())
+            })?;
+        let body = block_body(&expanded, switches);
+        match args.other.get("comments").copied() {
+            Some("link" | "yes" | "both") => {
+                let comment = comment_start(language.unwrap_or_default());
+                let title = heading.and_then(|id| match &parsed.pool[id].obj {
+                    Expr::Heading(heading) => heading.title.as_ref().map(|(raw, _)| raw.trim()),
+                    _ => None,
+                });
+                let org_link = path_relative_to(org_path, path.parent().unwrap_or(&path));
+                let (link, desc) = if let Some(title) = title {
+                    (
+                        format!("file:{}::*{title}", org_link.display()),
+                        format!("{title}:{block_num}"),
+                    )
+                } else {
+                    (
+                        format!("file:{}", org_link.display()),
+                        format!("No heading:{block_num}"),
+                    )
+                };
+                target
+                    .contents
+                    .push_str(&format!("{comment} [[{link}][{desc}]]\n"));
+                target.contents.push_str(&body);
+                target
+                    .contents
+                    .push_str(&format!("{comment} {desc} ends here\n"));
+            }
+            _ => target.contents.push_str(&body),
+        }
+        target.blocks += 1;
+    }
+
+    Ok(targets)
+}
+
+/// The code of a block as it should be tangled: without coderef labels,
+/// comma escapes or the indentation of the block itself.
+fn block_body(contents: &str, switches: &BlockSwitches) -> String {
+    let indentation = if switches.preserve_indent {
+        0
+    } else {
+        contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0)
+    };
+
+    let mut ret = String::new();
+    for line in contents.lines() {
+        let (line, _label) = switches.split_label(line);
+        let line = line.get(indentation..).unwrap_or(line.trim_start());
+        let trimmed = line.trim_start();
+        // lines that look like org syntax are escaped with a comma
+        if let Some(unescaped) = trimmed.strip_prefix(',') {
+            if unescaped.starts_with('*') || unescaped.starts_with("#+") {
+                ret.push_str(&line[..line.len() - trimmed.len()]);
+                ret.push_str(unescaped);
+                ret.push('\n');
+                continue;
+            }
+        }
+        ret.push_str(line);
+        ret.push('\n');
+    }
+    ret
+}
+
+/// The extension of files tangled with `:tangle yes`, which defaults to the language.
+fn lang_extension(lang: &str) -> &str {
+    match lang {
+        "emacs-lisp" | "elisp" => "el",
+        "python" => "py",
+        "rust" => "rs",
+        "shell" | "sh" | "bash" | "zsh" => "sh",
+        "C" => "c",
+        "C++" | "cpp" => "cpp",
+        "javascript" | "js" => "js",
+        "typescript" => "ts",
+        "haskell" => "hs",
+        "ruby" => "rb",
+        "perl" => "pl",
+        "ocaml" => "ml",
+        "scheme" => "scm",
+        "clojure" => "clj",
+        "latex" => "tex",
+        "julia" => "jl",
+        _ => lang,
+    }
+}
+
+/// The line comment syntax of a language, used by `:comments link`.
+fn comment_start(lang: &str) -> &'static str {
+    match lang {
+        "emacs-lisp" | "elisp" | "lisp" | "scheme" | "clojure" => ";;",
+        "C" | "C++" | "cpp" | "rust" | "javascript" | "js" | "typescript" | "java" | "go" => "//",
+        "haskell" | "lua" | "sql" => "--",
+        "latex" => "%",
+        _ => "#",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use org_parser::parse_org;
+
+    #[test]
+    fn tangle_targets() {
+        let input = r##"#+PROPERTY: header-args:python :tangle yes
+* Setup
+:PROPERTIES:
+:header-args:sh: :tangle bin/run.sh :shebang "#!/bin/sh" :mkdirp yes
+:END:
+#+begin_src python
+import os
+#+end_src
+
+#+begin_src sh
+echo hi
+#+end_src
+** Later
+#+begin_src python :tangle no
+skipped()
+#+end_src
+
+#+begin_src python
+  ,* not a heading
+  print(1) (ref:print)
+#+end_src
+"##;
+        let parsed = parse_org(input);
+        let targets = collect_targets(&parsed, Path::new("/notes/config.org")).unwrap();
+
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].path, Path::new("/notes/config.py"));
+        assert_eq!(
+            targets[0].contents,
+            "import os\n\n* not a heading\nprint(1)\n"
+        );
+        assert_eq!(targets[0].blocks, 2);
+        assert_eq!(targets[1].path, Path::new("/notes/bin/run.sh"));
+        assert_eq!(targets[1].shebang.as_deref(), Some("#!/bin/sh"));
+        assert!(targets[1].mkdirp);
+    }
+
+    #[test]
+    fn comment_links() {
+        let input = r"* Editor
+#+begin_src emacs-lisp :tangle init.el :comments link
+(setq x 1)
+#+end_src
+";
+        let parsed = parse_org(input);
+        let targets = collect_targets(&parsed, Path::new("/notes/config.org")).unwrap();
+
+        assert_eq!(
+            targets[0].contents,
+            ";; [[file:config.org::*Editor][Editor:1]]\n(setq x 1)\n;; Editor:1 ends here\n"
+        );
+    }
+
+    #[test]
+    fn tangle_noweb() {
+        let input = r"#+begin_src python :noweb-ref body
+print(1)
+#+end_src
+
+#+begin_src python :tangle out.py :noweb tangle
+def main():
+    <<body>>
+#+end_src
+";
+        let parsed = parse_org(input);
+        let targets = collect_targets(&parsed, Path::new("/notes/config.org")).unwrap();
+
+        assert_eq!(targets[0].contents, "def main():\n    print(1)\n");
+    }
+}
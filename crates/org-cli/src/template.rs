@@ -1,4 +1,5 @@
 use crate::{types::CliError, utils::relative_path_from};
+use org_exporter::ExportSettings;
 use org_parser::Parser;
 use std::{fs::read_to_string, path::Path};
 
@@ -43,6 +44,7 @@ enum LogicItem {
 
 pub struct Template<'a, 'template> {
     p: &'a Parser<'a>,
+    settings: ExportSettings,
     template_path: &'a Path,
     template_contents: &'template str,
     exported_content: &'a str,
@@ -55,9 +57,11 @@ impl<'a, 'template> Template<'a, 'template> {
         template_path: &'a Path,
         template_contents: &'template str,
         exported_content: &'a str,
+        settings: ExportSettings,
     ) -> Self {
         Self {
             p,
+            settings,
             template_path,
             template_contents,
             exported_content,
@@ -78,6 +82,21 @@ impl<'a, 'template> Template<'a, 'template> {
         self.process_captures(&mut captures, 0, LogicItem::None)
     }
 
+    /// The value of a keyword, unless `#+OPTIONS:` leaves it out of the export.
+    fn keyword(&self, key: &str) -> Option<&'a str> {
+        let hidden = match key {
+            "title" => !self.settings.title,
+            "author" => !self.settings.author,
+            "date" => !self.settings.date,
+            _ => false,
+        };
+        if hidden {
+            None
+        } else {
+            self.p.keywords.get(key)
+        }
+    }
+
     fn process_captures(
         &mut self,
         // begin, end, extract
@@ -97,7 +116,7 @@ impl<'a, 'template> Template<'a, 'template> {
             } else if let Some(command) = Command::check(extract) {
                 match command {
                     Command::If(cond) => {
-                        if let Some(_) = self.p.keywords.get(&*cond) {
+                        if let Some(_) = self.keyword(cond) {
                             local_items.push_str(&self.process_captures(
                                 captures,
                                 self.end,
@@ -209,20 +228,21 @@ impl<'a, 'template> Template<'a, 'template> {
                             &include_path,
                             &included_template,
                             self.exported_content,
+                            self.settings.clone(),
                         );
                         local_items.push_str(&t.process()?);
                     }
                 }
             } else if let Some(ind) = extract.find("|") {
                 let (l, r) = extract.split_at(ind);
-                local_items.push_str(if let Some(val) = self.p.keywords.get(l) {
+                local_items.push_str(if let Some(val) = self.keyword(l) {
                     val
                 } else {
                     //  the split resutls in "abc|123" being split like l = abc, r = |123
                     //  so we toss the first byte :>
                     &r[1..]
                 })
-            } else if let Some(kw) = self.p.keywords.get(extract) {
+            } else if let Some(kw) = self.keyword(extract) {
                 local_items.push_str(kw);
             }
 
@@ -8,7 +8,7 @@ use crate::types::CliError;
 // a fs::canonicalize that doesnt care for existince. used for error handling
 // yanked straight from:
 // https://github.com/rust-lang/cargo/blob/fede83ccf973457de319ba6fa0e36ead454d2e20/src/cargo/util/paths.rs#L61
-pub fn _normalize_path(path: &Path) -> PathBuf {
+pub fn normalize_path(path: &Path) -> PathBuf {
     let mut components = path.components().peekable();
     let mut ret = if let Some(c @ Component::Prefix(..)) = components.peek().cloned() {
         components.next();
@@ -70,3 +70,49 @@ pub fn relative_path_from<'a, 'b>(
         Ok(added.into())
     }
 }
+
+/// Like [`relative_path_from`], but for paths that don't need to exist yet,
+/// such as the destination of a tangled file.
+pub fn target_path_from(src: &Path, added: &Path) -> Result<PathBuf, CliError> {
+    if added.is_relative() {
+        let parent = src.parent().ok_or(
+            CliError::new()
+                .with_path(src)
+                .with_cause("no parent directory found"),
+        )?;
+        Ok(normalize_path(&parent.join(added)))
+    } else {
+        Ok(normalize_path(added))
+    }
+}
+
+/// Expresses `path` relative to the directory `base`, e.g. `../notes.org`.
+///
+/// Relative inputs are interpreted from the current directory.
+pub fn path_relative_to(path: &Path, base: &Path) -> PathBuf {
+    let absolute = |path: &Path| {
+        if path.is_relative() {
+            std::env::current_dir()
+                .map(|cwd| normalize_path(&cwd.join(path)))
+                .unwrap_or_else(|_| normalize_path(path))
+        } else {
+            normalize_path(path)
+        }
+    };
+    let path = absolute(path);
+    let base = absolute(base);
+
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut ret = PathBuf::new();
+    for _ in base.components().skip(common) {
+        ret.push(Component::ParentDir);
+    }
+    for component in path.components().skip(common) {
+        ret.push(component);
+    }
+    ret
+}
@@ -0,0 +1,661 @@
+//! Citation processing
+//!
+//! Citations are rendered through a [`CitationProcessor`], which can be provided through
+//! [`ConfigOptions`]. When none is given, a [`BasicProcessor`] is created from the document:
+//!
+//! ```example
+//! #+bibliography: refs.bib
+//! #+bibliography: more.json
+//! #+cite_export: basic numeric
+//! ```
+//!
+//! Bibliographies can be written in BibTeX (`.bib`) or CSL-JSON (`.json`).
+//! `#+cite_export: basic author-year` (the default) and `basic numeric` are supported.
+//!
+//! The bibliography is only exported where `#+print_bibliography:` requests it.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::fs::read_to_string;
+use std::path::{Path, PathBuf};
+
+use org_parser::object::{Citation, CitationReference};
+use org_parser::{Expr, Parser};
+use thiserror::Error;
+
+use crate::html::HtmlEscape;
+use crate::types::{ConfigOptions, ExportError, FileError, LogicErrorKind};
+
+/// Formats [`Citation`]s and the bibliography for the [`Html`](crate::Html) backend.
+pub trait CitationProcessor: fmt::Debug {
+    /// Renders a citation as HTML.
+    ///
+    /// `cited` holds every key cited so far in the document, in order of first appearance.
+    /// It includes the keys of `citation`.
+    fn cite(&self, citation: &Citation, cited: &[String]) -> String;
+    /// Renders the bibliography of every cited key as HTML.
+    ///
+    /// Empty when none of the keys have an entry.
+    fn bibliography(&self, cited: &[String]) -> String;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BasicStyle {
+    /// `(Doe, 2020)`
+    #[default]
+    AuthorYear,
+    /// `[1]`
+    Numeric,
+}
+
+/// A single bibliography entry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BibEntry {
+    pub authors: Vec<Name>,
+    pub year: Option<String>,
+    pub title: Option<String>,
+    /// The journal, book, or publisher the work appeared in
+    pub container: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Name {
+    pub family: String,
+    pub given: Option<String>,
+}
+
+/// The default [`CitationProcessor`], backed by local BibTeX or CSL-JSON files.
+#[derive(Debug, Clone, Default)]
+pub struct BasicProcessor {
+    entries: HashMap<String, BibEntry>,
+    style: BasicStyle,
+}
+
+#[derive(Debug, Error)]
+pub enum CiteError {
+    #[error("{0}")]
+    IoError(#[from] FileError),
+    #[error("bibliography `{0}` is not one of [.bib, .json]")]
+    UnsupportedFormat(PathBuf),
+    #[error("invalid BibTeX: {0}")]
+    BibTex(&'static str),
+    #[error("invalid CSL-JSON: {0}")]
+    CslJson(#[from] serde_json::Error),
+}
+
+impl BasicProcessor {
+    pub fn new(style: BasicStyle) -> Self {
+        Self {
+            entries: HashMap::new(),
+            style,
+        }
+    }
+
+    pub fn style(&self) -> BasicStyle {
+        self.style
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, entry: BibEntry) {
+        self.entries.insert(key.into(), entry);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&BibEntry> {
+        self.entries.get(key)
+    }
+
+    /// Loads a bibliography file, determining its format from the extension.
+    pub fn load_file(&mut self, path: &Path) -> Result<(), CiteError> {
+        let contents = read_to_string(path).map_err(|e| FileError {
+            context: "failed to read bibliography: ".into(),
+            path: path.into(),
+            source: e,
+        })?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("bib") => self.load_bibtex(&contents),
+            Some("json") => self.load_csl_json(&contents),
+            _ => Err(CiteError::UnsupportedFormat(path.into())),
+        }
+    }
+
+    pub fn load_bibtex(&mut self, src: &str) -> Result<(), CiteError> {
+        let mut rest = src;
+        while let Some(at) = rest.find('@') {
+            rest = &rest[at + 1..];
+            let open = rest
+                .find(['{', '('])
+                .ok_or(CiteError::BibTex("expected `{` after entry type"))?;
+            let kind = rest[..open].trim().to_ascii_lowercase();
+            let close = closing_delim(rest, open).ok_or(CiteError::BibTex("unclosed entry"))?;
+            let body = &rest[open + 1..close];
+            rest = &rest[close + 1..];
+
+            if matches!(kind.as_str(), "comment" | "string" | "preamble") {
+                continue;
+            }
+
+            let (key, fields) = body.split_once(',').unwrap_or((body, ""));
+            let fields = parse_bibtex_fields(fields)?;
+            let field = |name: &str| fields.get(name).cloned();
+
+            let entry = BibEntry {
+                authors: field("author")
+                    .or_else(|| field("editor"))
+                    .map(|names| names.split(" and ").map(Name::from_bibtex).collect())
+                    .unwrap_or_default(),
+                year: field("year").or_else(|| field("date").map(|d| d.chars().take(4).collect())),
+                title: field("title"),
+                container: field("journal")
+                    .or_else(|| field("booktitle"))
+                    .or_else(|| field("publisher")),
+            };
+            self.entries.insert(key.trim().into(), entry);
+        }
+
+        Ok(())
+    }
+
+    pub fn load_csl_json(&mut self, src: &str) -> Result<(), CiteError> {
+        let items: Vec<serde_json::Value> = serde_json::from_str(src)?;
+        for item in items {
+            let Some(key) = item["id"].as_str() else {
+                continue;
+            };
+            let string = |name: &str| item[name].as_str().map(String::from);
+
+            let authors = item["author"]
+                .as_array()
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|name| {
+                            let family = name["family"].as_str().or(name["literal"].as_str())?;
+                            Some(Name {
+                                family: family.into(),
+                                given: name["given"].as_str().map(String::from),
+                            })
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            let year = match &item["issued"]["date-parts"][0][0] {
+                serde_json::Value::Number(year) => Some(year.to_string()),
+                serde_json::Value::String(year) => Some(year.clone()),
+                _ => None,
+            };
+
+            self.entries.insert(
+                key.into(),
+                BibEntry {
+                    authors,
+                    year,
+                    title: string("title"),
+                    container: string("container-title").or_else(|| string("publisher")),
+                },
+            );
+        }
+
+        Ok(())
+    }
+
+    fn cite_reference(
+        &self,
+        buf: &mut String,
+        reference: &CitationReference,
+        style: &str,
+        cited: &[String],
+    ) {
+        let key = reference.key;
+        if let Some(prefix) = reference.prefix {
+            let _ = write!(buf, "{} ", HtmlEscape(prefix));
+        }
+
+        let entry = self.entries.get(key);
+        let suffix = reference.suffix.map(HtmlEscape);
+        let _ = match (self.style, entry) {
+            (BasicStyle::Numeric, Some(_)) => {
+                // numbered as in the bibliography, which leaves out keys without an entry
+                let num = cited
+                    .iter()
+                    .filter(|c| self.entries.contains_key(c.as_str()))
+                    .position(|c| c == key)
+                    .map_or(0, |i| i + 1);
+                write!(buf, r##"<a href="#bib-{}">{num}</a>"##, HtmlEscape(key))
+            }
+            (BasicStyle::AuthorYear, Some(entry)) => {
+                let author = HtmlEscape(entry.short_author(key));
+                let year = HtmlEscape(entry.year.as_deref().unwrap_or("n.d."));
+                match style {
+                    "t" | "text" => write!(
+                        buf,
+                        r##"<a href="#bib-{}">{author}</a> ({year}"##,
+                        HtmlEscape(key)
+                    ),
+                    "a" | "author" => {
+                        write!(buf, r##"<a href="#bib-{}">{author}</a>"##, HtmlEscape(key))
+                    }
+                    "na" | "noauthor" => {
+                        write!(buf, r##"<a href="#bib-{}">{year}</a>"##, HtmlEscape(key))
+                    }
+                    _ => write!(
+                        buf,
+                        r##"<a href="#bib-{}">{author}, {year}</a>"##,
+                        HtmlEscape(key)
+                    ),
+                }
+            }
+            (_, None) => write!(buf, "{}", HtmlEscape(key)),
+        };
+
+        if let Some(suffix) = suffix {
+            let _ = write!(buf, ", {suffix}");
+        }
+        if self.style == BasicStyle::AuthorYear && entry.is_some() && matches!(style, "t" | "text")
+        {
+            buf.push(')');
+        }
+    }
+}
+
+impl CitationProcessor for BasicProcessor {
+    fn cite(&self, citation: &Citation, cited: &[String]) -> String {
+        let style = citation
+            .style
+            .and_then(|s| s.split('/').next())
+            .unwrap_or("");
+        if matches!(style, "n" | "nocite") {
+            return String::new();
+        }
+        let (open, close) = match self.style {
+            // the author is written outside of the parentheses
+            BasicStyle::AuthorYear if matches!(style, "t" | "text" | "a" | "author") => ("", ""),
+            BasicStyle::AuthorYear => ("(", ")"),
+            BasicStyle::Numeric => ("[", "]"),
+        };
+        let sep = if self.style == BasicStyle::Numeric
+            && citation.references.iter().all(|r| r.suffix.is_none())
+        {
+            ", "
+        } else {
+            "; "
+        };
+
+        let mut buf = String::from(open);
+        if let Some(prefix) = citation.prefix {
+            let _ = write!(buf, "{} ", HtmlEscape(prefix));
+        }
+        for (i, reference) in citation.references.iter().enumerate() {
+            if i != 0 {
+                buf.push_str(sep);
+            }
+            self.cite_reference(&mut buf, reference, style, cited);
+        }
+        if let Some(suffix) = citation.suffix {
+            let _ = write!(buf, " {}", HtmlEscape(suffix));
+        }
+        buf.push_str(close);
+        buf
+    }
+
+    fn bibliography(&self, cited: &[String]) -> String {
+        let mut entries: Vec<(&String, &BibEntry)> = cited
+            .iter()
+            .filter_map(|key| Some((key, self.entries.get(key)?)))
+            .collect();
+        let list = match self.style {
+            BasicStyle::AuthorYear => {
+                entries.sort_by(|(_, a), (_, b)| {
+                    let family = |e: &BibEntry| e.authors.first().map(|n| n.family.clone());
+                    family(a).cmp(&family(b)).then(a.year.cmp(&b.year))
+                });
+                "ul"
+            }
+            BasicStyle::Numeric => "ol",
+        };
+        if entries.is_empty() {
+            return String::new();
+        }
+
+        let mut buf = format!("<div id=\"bibliography\">\n<h2>Bibliography</h2>\n<{list}>\n");
+        for (key, entry) in entries {
+            let _ = write!(buf, r#"<li id="bib-{}">"#, HtmlEscape(key));
+            if !entry.authors.is_empty() {
+                let _ = write!(buf, "{} ", HtmlEscape(entry.long_author()));
+            }
+            if let Some(year) = &entry.year {
+                let _ = write!(buf, "({}). ", HtmlEscape(year));
+            }
+            if let Some(title) = &entry.title {
+                let _ = write!(buf, "<i>{}</i>. ", HtmlEscape(title));
+            }
+            if let Some(container) = &entry.container {
+                let _ = write!(buf, "{}.", HtmlEscape(container));
+            }
+            let trimmed = buf.trim_end().len();
+            buf.truncate(trimmed);
+            buf.push_str("</li>\n");
+        }
+        let _ = write!(buf, "</{list}>\n</div>\n");
+        buf
+    }
+}
+
+impl BibEntry {
+    /// The author as used in in-text citations, e.g. `Doe & Smith`.
+    fn short_author<'a>(&'a self, key: &'a str) -> Cow<'a, str> {
+        match self.authors.as_slice() {
+            [] => key.into(),
+            [one] => one.family.as_str().into(),
+            [one, two] => format!("{} & {}", one.family, two.family).into(),
+            [one, ..] => format!("{} et al.", one.family).into(),
+        }
+    }
+
+    /// The authors as used in the bibliography, e.g. `Doe, J., & Smith, A.`
+    fn long_author(&self) -> String {
+        let names: Vec<String> = self
+            .authors
+            .iter()
+            .map(|name| match &name.given {
+                Some(given) => {
+                    let initials: Vec<String> = given
+                        .split_whitespace()
+                        .filter_map(|part| part.chars().next())
+                        .map(|c| format!("{c}."))
+                        .collect();
+                    format!("{}, {}", name.family, initials.join(" "))
+                }
+                None => format!("{}.", name.family),
+            })
+            .collect();
+
+        match names.as_slice() {
+            [] => String::new(),
+            [one] => one.clone(),
+            [rest @ .., last] => format!("{}, & {last}", rest.join(", ")),
+        }
+    }
+}
+
+impl Name {
+    /// Parses `Family, Given` or `Given Family`.
+    fn from_bibtex(name: &str) -> Self {
+        let name = name.trim();
+        if let Some((family, given)) = name.split_once(',') {
+            Self {
+                family: family.trim().into(),
+                given: Some(given.trim().into()).filter(|g: &String| !g.is_empty()),
+            }
+        } else if let Some((given, family)) = name.rsplit_once(' ') {
+            Self {
+                family: family.into(),
+                given: Some(given.trim().into()),
+            }
+        } else {
+            Self {
+                family: name.into(),
+                given: None,
+            }
+        }
+    }
+}
+
+/// Finds the delimiter closing the one at `open`, skipping over nested braces.
+fn closing_delim(src: &str, open: usize) -> Option<usize> {
+    let closer = if src.as_bytes()[open] == b'(' {
+        b')'
+    } else {
+        b'}'
+    };
+    let mut depth = 0;
+    for (i, &chr) in src.as_bytes().iter().enumerate().skip(open + 1) {
+        match chr {
+            b'{' => depth += 1,
+            b'}' if depth > 0 => depth -= 1,
+            _ if chr == closer && depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parses `name = {value}, name = "value", name = 2020`, lowercasing the names.
+fn parse_bibtex_fields(src: &str) -> Result<HashMap<String, String>, CiteError> {
+    let bytes = src.as_bytes();
+    let mut fields = HashMap::new();
+    let mut i = 0;
+
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b',') {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            break;
+        }
+
+        let eq = src[i..]
+            .find('=')
+            .ok_or(CiteError::BibTex("expected `=` after field name"))?
+            + i;
+        let name = src[i..eq].trim().to_ascii_lowercase();
+        i = eq + 1;
+
+        let mut value = String::new();
+        loop {
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            match bytes.get(i) {
+                Some(b'{') => {
+                    let close =
+                        closing_delim(src, i).ok_or(CiteError::BibTex("unclosed field value"))?;
+                    value.push_str(&src[i + 1..close]);
+                    i = close + 1;
+                }
+                Some(b'"') => {
+                    let close = src[i + 1..]
+                        .find('"')
+                        .ok_or(CiteError::BibTex("unclosed field value"))?
+                        + i
+                        + 1;
+                    value.push_str(&src[i + 1..close]);
+                    i = close + 1;
+                }
+                Some(_) => {
+                    let end = src[i..].find([',', '#']).map_or(src.len(), |end| end + i);
+                    value.push_str(src[i..end].trim());
+                    i = end;
+                }
+                None => break,
+            }
+
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if bytes.get(i) == Some(&b'#') {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+
+        let value: String = value.chars().filter(|&c| c != '{' && c != '}').collect();
+        fields.insert(name, value.split_whitespace().collect::<Vec<_>>().join(" "));
+    }
+
+    Ok(fields)
+}
+
+/// Builds a [`BasicProcessor`] from the document's `#+bibliography:` and `#+cite_export:`
+/// keywords.
+///
+/// Bibliography paths are relative to the file being exported.
+pub(crate) fn document_processor(
+    parser: &Parser,
+    conf: &ConfigOptions,
+) -> (BasicProcessor, Vec<ExportError>) {
+    let mut processor = BasicProcessor::default();
+    let mut errors = Vec::new();
+
+    for node in parser.pool.iter() {
+        let Expr::Keyword(keyword) = &node.obj else {
+            continue;
+        };
+
+        if keyword.key.eq_ignore_ascii_case("cite_export") {
+            let mut params = keyword.val.split_whitespace();
+            if params.next() == Some("basic") && params.next() == Some("numeric") {
+                processor.style = BasicStyle::Numeric;
+            }
+        } else if keyword.key.eq_ignore_ascii_case("bibliography") {
+            let file = Path::new(keyword.val.trim().trim_matches('"'));
+            let path: Cow<Path> = match conf.file_path() {
+                Some(v) => v.parent().unwrap_or(Path::new("")).join(file).into(),
+                None => file.into(),
+            };
+
+            if let Err(e) = processor.load_file(&path) {
+                errors.push(ExportError::LogicError {
+                    span: node.start..node.end,
+                    source: LogicErrorKind::Citation(e),
+                });
+            }
+        }
+    }
+
+    (processor, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use org_parser::parse_org;
+    use pretty_assertions::assert_eq;
+
+    const BIBTEX: &str = r#"
+@comment{ignored}
+@article{doe2020,
+  author = {Doe, John and Smith, Alice},
+  title = {A {Great} Title},
+  journal = "Journal of " # {Things},
+  year = 2020,
+}
+@book(roe2019,
+  author = {Richard Roe},
+  title = {Book},
+  publisher = {Press},
+  date = {2019-04-01}
+)
+"#;
+
+    fn cite(input: &str, processor: &BasicProcessor) -> String {
+        let parsed = parse_org(input);
+        let Some(Expr::Citation(citation)) = parsed
+            .pool
+            .iter()
+            .map(|n| &n.obj)
+            .find(|obj| matches!(obj, Expr::Citation(_)))
+        else {
+            panic!("no citation in {input}");
+        };
+        let cited: Vec<String> = citation.references.iter().map(|r| r.key.into()).collect();
+        processor.cite(citation, &cited)
+    }
+
+    #[test]
+    fn bibtex() {
+        let mut processor = BasicProcessor::default();
+        processor.load_bibtex(BIBTEX).unwrap();
+
+        assert_eq!(
+            processor.get("doe2020"),
+            Some(&BibEntry {
+                authors: vec![
+                    Name {
+                        family: "Doe".into(),
+                        given: Some("John".into())
+                    },
+                    Name {
+                        family: "Smith".into(),
+                        given: Some("Alice".into())
+                    }
+                ],
+                year: Some("2020".into()),
+                title: Some("A Great Title".into()),
+                container: Some("Journal of Things".into()),
+            })
+        );
+        let roe = processor.get("roe2019").unwrap();
+        assert_eq!(roe.authors[0].family, "Roe");
+        assert_eq!(roe.year.as_deref(), Some("2019"));
+        assert_eq!(processor.entries.len(), 2);
+    }
+
+    #[test]
+    fn csl_json() {
+        let mut processor = BasicProcessor::default();
+        processor
+            .load_csl_json(
+                r#"[{"id": "doe2020", "type": "article-journal", "title": "Title",
+                     "author": [{"family": "Doe", "given": "John"}],
+                     "container-title": "Journal",
+                     "issued": {"date-parts": [[2020, 3]]}}]"#,
+            )
+            .unwrap();
+
+        let doe = processor.get("doe2020").unwrap();
+        assert_eq!(doe.authors[0].family, "Doe");
+        assert_eq!(doe.year.as_deref(), Some("2020"));
+        assert_eq!(doe.container.as_deref(), Some("Journal"));
+    }
+
+    #[test]
+    fn author_year() {
+        let mut processor = BasicProcessor::default();
+        processor.load_bibtex(BIBTEX).unwrap();
+
+        assert_eq!(
+            cite("[cite:see @doe2020 p. 3; @roe2019]", &processor),
+            r##"(see <a href="#bib-doe2020">Doe &amp; Smith, 2020</a>, p. 3; <a href="#bib-roe2019">Roe, 2019</a>)"##
+        );
+        assert_eq!(
+            cite("[cite/t:@roe2019 ch. 2]", &processor),
+            r##"<a href="#bib-roe2019">Roe</a> (2019, ch. 2)"##
+        );
+        assert_eq!(cite("[cite:@missing]", &processor), "(missing)");
+    }
+
+    #[test]
+    fn numeric() {
+        let mut processor = BasicProcessor::new(BasicStyle::Numeric);
+        processor.load_bibtex(BIBTEX).unwrap();
+
+        assert_eq!(
+            cite("[cite:@roe2019;@doe2020]", &processor),
+            r##"[<a href="#bib-roe2019">1</a>, <a href="#bib-doe2020">2</a>]"##
+        );
+        assert_eq!(
+            processor.bibliography(&["roe2019".into(), "doe2020".into()]),
+            r#"<div id="bibliography">
+<h2>Bibliography</h2>
+<ol>
+<li id="bib-roe2019">Roe, R. (2019). <i>Book</i>. Press.</li>
+<li id="bib-doe2020">Doe, J., &amp; Smith, A. (2020). <i>A Great Title</i>. Journal of Things.</li>
+</ol>
+</div>
+"#
+        );
+        assert_eq!(processor.bibliography(&["missing".into()]), "");
+
+        // keys without an entry aren't numbered, as they aren't in the bibliography
+        assert_eq!(
+            cite("[cite/t:@missing;@doe2020]", &processor),
+            r##"[missing, <a href="#bib-doe2020">1</a>]"##
+        );
+        assert_eq!(
+            cite("[cite/a:@doe2020]", &processor),
+            r##"[<a href="#bib-doe2020">1</a>]"##
+        );
+    }
+}
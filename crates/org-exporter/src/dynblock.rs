@@ -0,0 +1,459 @@
+//! Dynamic block generation
+//!
+//! The contents of a dynamic block are produced by a generator registered under the block's name:
+//!
+//! ```example
+//! #+BEGIN: clocktable :scope file :maxlevel 2
+//! #+END:
+//! ```
+//!
+//! [`DynamicBlockRegistry::regenerate`] rewrites the contents of every block with a registered
+//! generator. `clocktable` and `columnview` are available by default.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use org_parser::element::{DynamicBlock, Heading, Priority, Tag};
+use org_parser::{parse_org, Expr, NodeID, Parser};
+
+use crate::utils::collect_nodes;
+
+/// Produces the contents of a [`DynamicBlock`].
+///
+/// Implemented for any `Fn(&Parser, NodeID) -> String`.
+pub trait DynamicBlockGenerator {
+    /// Generates the new contents of the block at `block_id`, as Org source.
+    fn generate(&self, parser: &Parser, block_id: NodeID) -> String;
+}
+
+impl<F: Fn(&Parser, NodeID) -> String> DynamicBlockGenerator for F {
+    fn generate(&self, parser: &Parser, block_id: NodeID) -> String {
+        self(parser, block_id)
+    }
+}
+
+/// Maps dynamic block names to their generators.
+///
+/// The default registry contains [`ClockTable`] and [`ColumnView`].
+pub struct DynamicBlockRegistry {
+    generators: HashMap<String, Box<dyn DynamicBlockGenerator>>,
+}
+
+impl Default for DynamicBlockRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("clocktable", ClockTable);
+        registry.register("columnview", ColumnView);
+        registry
+    }
+}
+
+impl DynamicBlockRegistry {
+    /// A registry without any generators.
+    pub fn empty() -> Self {
+        Self {
+            generators: HashMap::new(),
+        }
+    }
+
+    /// Registers a generator, replacing any previous generator for `name`.
+    ///
+    /// Names are case-insensitive.
+    pub fn register(&mut self, name: &str, generator: impl DynamicBlockGenerator + 'static) {
+        self.generators
+            .insert(name.to_ascii_lowercase(), Box::new(generator));
+    }
+
+    /// Generates the contents of the block at `block_id`.
+    ///
+    /// Returns `None` if the node isn't a [`DynamicBlock`] or if no generator is registered for it.
+    pub fn generate(&self, parser: &Parser, block_id: NodeID) -> Option<String> {
+        let Expr::DynamicBlock(block) = &parser.pool[block_id].obj else {
+            return None;
+        };
+        let generator = self.generators.get(&block.name.to_ascii_lowercase())?;
+        Some(generator.generate(parser, block_id))
+    }
+
+    /// Rewrites the contents of every dynamic block in `input` that has a registered generator.
+    pub fn regenerate(&self, input: &str) -> String {
+        let parser = parse_org(input);
+        let mut blocks = Vec::new();
+        collect_nodes(
+            &parser,
+            parser.pool.root_id(),
+            &|obj| matches!(obj, Expr::DynamicBlock(_)),
+            &mut blocks,
+        );
+
+        let mut ret = String::with_capacity(input.len());
+        let mut prev = 0;
+        for block_id in blocks {
+            let node = &parser.pool[block_id];
+            // nested within a block that was already replaced
+            if node.start < prev {
+                continue;
+            }
+            let Some(mut contents) = self.generate(&parser, block_id) else {
+                continue;
+            };
+            if !contents.is_empty() && !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+
+            let block_src = &input[node.start..node.end];
+            // after the #+BEGIN: line
+            let body_start = node.start + block_src.find('\n').map_or(block_src.len(), |i| i + 1);
+            // the start of the #+END: line
+            let body_end = node.start + block_src.trim_end().rfind('\n').map_or(0, |i| i + 1);
+
+            ret.push_str(&input[prev..body_start]);
+            ret.push_str(&contents);
+            prev = body_end.max(body_start);
+        }
+        ret.push_str(&input[prev..]);
+        ret
+    }
+}
+
+/// Generates a table of the time clocked in each headline.
+///
+/// Parameters:
+///
+/// - `:scope file` (default) or `:scope subtree`, the headline containing the block
+/// - `:maxlevel N`, the deepest headline level listed, defaults to 3
+#[derive(Debug, Clone, Copy)]
+pub struct ClockTable;
+
+impl DynamicBlockGenerator for ClockTable {
+    fn generate(&self, parser: &Parser, block_id: NodeID) -> String {
+        let Expr::DynamicBlock(block) = &parser.pool[block_id].obj else {
+            return String::new();
+        };
+        let max_level = max_level(block, 3);
+        let scope = match block.parameter("scope") {
+            Some("subtree") => subtree_scope(parser, block_id),
+            _ => file_scope(parser),
+        };
+
+        let mut headings = Vec::new();
+        for id in &scope {
+            collect_headings(parser, *id, max_level, &mut headings);
+        }
+
+        let total: u32 = scope.iter().map(|id| parser.clocked_minutes(*id)).sum();
+        let mut rows = vec![
+            Some(vec!["Headline".into(), "Time".into()]),
+            None,
+            Some(vec!["*Total time*".into(), format!("*{}*", hours(total))]),
+            None,
+        ];
+        for (id, heading) in headings {
+            let minutes = parser.clocked_minutes(id);
+            if minutes == 0 {
+                continue;
+            }
+            let level = heading.heading_level.get();
+            let indent = if level > 1 {
+                format!("\\_{}", "  ".repeat(level - 1))
+            } else {
+                String::new()
+            };
+            rows.push(Some(vec![
+                format!("{indent}{}", title(heading)),
+                hours(minutes),
+            ]));
+        }
+
+        format_table(&rows)
+    }
+}
+
+/// Generates a table of the properties of each headline.
+///
+/// Parameters:
+///
+/// - `:id global` for every headline, otherwise only the headline containing the block
+/// - `:maxlevel N`, the deepest headline level listed, all levels by default
+/// - `:format "%ITEM %TODO %EFFORT"`, the columns, defaults to `#+COLUMNS:`
+///
+/// Supported columns are `ITEM`, `TODO`, `PRIORITY`, `TAGS`, and any property.
+/// Columns can be given a title: `%EFFORT(Estimate)`.
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnView;
+
+impl DynamicBlockGenerator for ColumnView {
+    fn generate(&self, parser: &Parser, block_id: NodeID) -> String {
+        let Expr::DynamicBlock(block) = &parser.pool[block_id].obj else {
+            return String::new();
+        };
+        let max_level = max_level(block, usize::MAX);
+        let scope = match block.parameter("id") {
+            Some("global") => file_scope(parser),
+            _ => subtree_scope(parser, block_id),
+        };
+        let format = block
+            .parameter("format")
+            .map(|format| format.trim_matches('"'))
+            .or_else(|| parser.keywords.get("columns"))
+            .unwrap_or("%ITEM");
+        let columns = parse_columns(format);
+
+        let mut headings = Vec::new();
+        for id in &scope {
+            collect_headings(parser, *id, max_level, &mut headings);
+        }
+
+        let mut rows = vec![
+            Some(columns.iter().map(|(_, title)| title.to_string()).collect()),
+            None,
+        ];
+        for (id, heading) in headings {
+            rows.push(Some(
+                columns
+                    .iter()
+                    .map(|(name, _)| column_value(parser, id, heading, name))
+                    .collect(),
+            ));
+        }
+
+        format_table(&rows)
+    }
+}
+
+fn max_level(block: &DynamicBlock, default: usize) -> usize {
+    block
+        .parameter("maxlevel")
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Every top level node of the document.
+fn file_scope(parser: &Parser) -> Vec<NodeID> {
+    parser.pool[parser.pool.root_id()]
+        .obj
+        .children()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// The headline containing `node_id`, or the whole document if there is none.
+fn subtree_scope(parser: &Parser, node_id: NodeID) -> Vec<NodeID> {
+    let mut curr = parser.pool[node_id].parent;
+    while let Some(id) = curr {
+        if let Expr::Heading(_) = parser.pool[id].obj {
+            return vec![id];
+        }
+        curr = parser.pool[id].parent;
+    }
+    file_scope(parser)
+}
+
+fn collect_headings<'a>(
+    parser: &'a Parser,
+    node_id: NodeID,
+    max_level: usize,
+    headings: &mut Vec<(NodeID, &'a Heading<'a>)>,
+) {
+    if let Expr::Heading(heading) = &parser.pool[node_id].obj {
+        if heading.heading_level.get() > max_level {
+            return;
+        }
+        headings.push((node_id, heading));
+        for id in heading.children.iter().flatten() {
+            collect_headings(parser, *id, max_level, headings);
+        }
+    }
+}
+
+fn title<'a>(heading: &Heading<'a>) -> &'a str {
+    heading.title.as_ref().map_or("", |title| title.0.trim())
+}
+
+fn hours(minutes: u32) -> String {
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Splits a column format such as `%25ITEM %TODO %EFFORT(Estimate){:}`
+/// into the property and title of each column.
+fn parse_columns(format: &str) -> Vec<(&str, &str)> {
+    format
+        .split_whitespace()
+        .filter_map(|column| {
+            let column = column.strip_prefix('%')?;
+            let column = column.trim_start_matches(|chr: char| chr.is_ascii_digit());
+            // drop any summary type
+            let column = column.split('{').next()?;
+            let (name, title) = match column.split_once('(') {
+                Some((name, title)) => (name, title.trim_end_matches(')')),
+                None => (column, column),
+            };
+            (!name.is_empty()).then_some((name, title))
+        })
+        .collect()
+}
+
+fn column_value(parser: &Parser, id: NodeID, heading: &Heading, name: &str) -> String {
+    match name.to_ascii_uppercase().as_str() {
+        "ITEM" => title(heading).into(),
+        "TODO" => heading
+            .keyword
+            .map(|keyword| keyword.name.into())
+            .unwrap_or_default(),
+        "PRIORITY" => match &heading.priority {
+            Some(Priority::A) => "A".into(),
+            Some(Priority::B) => "B".into(),
+            Some(Priority::C) => "C".into(),
+            Some(Priority::Num(num)) => num.to_string(),
+            None => String::new(),
+        },
+        "TAGS" | "ALLTAGS" => {
+            let tags: Vec<&str> = if name.eq_ignore_ascii_case("alltags") {
+                parser.tags(id)
+            } else {
+                heading
+                    .tags
+                    .iter()
+                    .flatten()
+                    .rev()
+                    .filter_map(|tag| match tag {
+                        Tag::Raw(tag) => Some(*tag),
+                        Tag::Loc(_) => None,
+                    })
+                    .collect()
+            };
+            if tags.is_empty() {
+                String::new()
+            } else {
+                format!(":{}:", tags.join(":"))
+            }
+        }
+        _ => parser
+            .property(id, name)
+            .map(|val| val.into_owned())
+            .unwrap_or_default(),
+    }
+}
+
+/// Writes an aligned Org table, where `None` is a rule.
+pub(crate) fn format_table(rows: &[Option<Vec<String>>]) -> String {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows.iter().flatten() {
+        for (i, cell) in row.iter().enumerate() {
+            let len = cell.chars().count();
+            if i < widths.len() {
+                widths[i] = widths[i].max(len);
+            } else {
+                widths.push(len);
+            }
+        }
+    }
+
+    let mut ret = String::new();
+    for row in rows {
+        if let Some(row) = row {
+            ret.push('|');
+            for (i, width) in widths.iter().enumerate() {
+                let cell = row.get(i).map_or("", |cell| cell.as_str());
+                let _ = write!(ret, " {cell:width$} |");
+            }
+        } else {
+            let rule: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+            let _ = write!(ret, "|{}|", rule.join("+"));
+        }
+        ret.push('\n');
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn clocktable() {
+        let input = r"* one
+:LOGBOOK:
+CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:00] =>  1:00
+:END:
+** two
+:LOGBOOK:
+CLOCK: [2023-08-16 Wed 11:00]--[2023-08-16 Wed 11:30] =>  0:30
+:END:
+* three
+#+BEGIN: clocktable :scope file
+old contents
+#+END:
+";
+        assert_eq!(
+            DynamicBlockRegistry::default().regenerate(input),
+            r"* one
+:LOGBOOK:
+CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:00] =>  1:00
+:END:
+** two
+:LOGBOOK:
+CLOCK: [2023-08-16 Wed 11:00]--[2023-08-16 Wed 11:30] =>  0:30
+:END:
+* three
+#+BEGIN: clocktable :scope file
+| Headline     | Time   |
+|--------------+--------|
+| *Total time* | *1:30* |
+|--------------+--------|
+| one          | 1:30   |
+| \_  two      | 0:30   |
+#+END:
+"
+        );
+    }
+
+    #[test]
+    fn columnview() {
+        let input = r#"#+COLUMNS: %ITEM %TODO %EFFORT(Estimate)
+* TODO parent
+:PROPERTIES:
+:EFFORT: 2:00
+:END:
+#+BEGIN: columnview :maxlevel 2
+#+END:
+** DONE child :tag:
+*** too deep
+"#;
+        assert_eq!(
+            DynamicBlockRegistry::default().regenerate(input),
+            r#"#+COLUMNS: %ITEM %TODO %EFFORT(Estimate)
+* TODO parent
+:PROPERTIES:
+:EFFORT: 2:00
+:END:
+#+BEGIN: columnview :maxlevel 2
+| ITEM   | TODO | Estimate |
+|--------+------+----------|
+| parent | TODO | 2:00     |
+| child  | DONE |          |
+#+END:
+** DONE child :tag:
+*** too deep
+"#
+        );
+    }
+
+    #[test]
+    fn custom_generator() {
+        let mut registry = DynamicBlockRegistry::empty();
+        registry.register("Greeting", |parser: &Parser, id: NodeID| {
+            let Expr::DynamicBlock(block) = &parser.pool[id].obj else {
+                unreachable!()
+            };
+            format!("hello {}", block.parameter("name").unwrap())
+        });
+
+        let input = "#+begin: greeting :name world\n#+end:\n#+begin: clocktable\n#+end:\n";
+        assert_eq!(
+            registry.regenerate(input),
+            "#+begin: greeting :name world\nhello world\n#+end:\n#+begin: clocktable\n#+end:\n"
+        );
+    }
+}
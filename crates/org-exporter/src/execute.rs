@@ -0,0 +1,942 @@
+//! Source block execution
+//!
+//! Source blocks are run by a [`LanguageExecutor`] registered under the block's language:
+//!
+//! ```example
+//! #+begin_src python :results value table
+//! return [[1, 2], [3, 4]]
+//! #+end_src
+//! ```
+//!
+//! [`SourceExecutor::execute`] runs every block that has a registered executor, following
+//! the `:results` header argument, and sets its results in the AST with
+//! [`Parser::set_results`]. The document is then written back through the [`Org`] exporter,
+//! which places them after a `#+RESULTS:` line.
+//! Blocks with `:cache yes` are only run again when their code or arguments change.
+//! `sh`, `bash` and `python` are available by default, and run the local interpreters.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+
+use org_parser::element::{Block, TableRow};
+use org_parser::{
+    parse_org, Expr, HeaderArgs, NodeID, NowebContext, NowebError, Parser, ResultsCollection,
+    ResultsFormat, ResultsHandling, ResultsType,
+};
+use thiserror::Error;
+
+use crate::dynblock::format_table;
+use crate::settings::{BrokenLinks, ExportOverrides, LatexExport, Selection};
+use crate::types::{ConfigOptions, Exporter};
+use crate::utils::{collect_nodes, node_name};
+use crate::{ExportError, Org};
+
+/// Scalar output longer than this many lines is wrapped in an example block
+/// rather than a fixed-width area.
+const MIN_LINES_FOR_BLOCK: usize = 10;
+
+/// The values of a block's `:var`s, by name.
+type Vars<'a> = Vec<(&'a str, Value)>;
+
+/// The result of running a block, or the value of a `:var`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A string or number
+    Scalar(String),
+    /// Rows of cells, such as a table or a list of lists
+    Table(Vec<Vec<String>>),
+}
+
+#[derive(Debug, Error)]
+pub enum ExecuteError {
+    #[error("failed to run `{command}`: {source}")]
+    Spawn {
+        command: String,
+        source: std::io::Error,
+    },
+    #[error("`{command}` exited with {status}: {stderr}")]
+    Failed {
+        command: String,
+        status: ExitStatus,
+        stderr: String,
+    },
+    #[error("invalid output from `{command}`: {reason}")]
+    InvalidOutput { command: String, reason: String },
+    #[error("no executor for language `{0}`")]
+    UnknownLanguage(String),
+    #[error("block `{0}` depends on itself")]
+    Cycle(String),
+    #[error("failed to write results to `{path}`: {source}")]
+    File {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("{0}")]
+    Noweb(#[from] NowebError),
+    #[error("failed to write the document: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    Export(Vec<ExportError>),
+}
+
+/// The new results of a block, as Org source.
+struct BlockResults {
+    block_id: NodeID,
+    text: String,
+    /// Written as `#+RESULTS[hash]:` for blocks with `:cache yes`
+    hash: Option<String>,
+}
+
+/// Runs the code of source blocks written in a language.
+///
+/// Implemented for any `Fn(&str, &[(&str, Value)], &HeaderArgs) -> Result<Value, ExecuteError>`.
+pub trait LanguageExecutor {
+    /// Runs `code` with each of `vars` defined, returning either its output or its value
+    /// depending on `args.results.collection`.
+    fn execute(
+        &self,
+        code: &str,
+        vars: &[(&str, Value)],
+        args: &HeaderArgs,
+    ) -> Result<Value, ExecuteError>;
+}
+
+impl<F> LanguageExecutor for F
+where
+    F: Fn(&str, &[(&str, Value)], &HeaderArgs) -> Result<Value, ExecuteError>,
+{
+    fn execute(
+        &self,
+        code: &str,
+        vars: &[(&str, Value)],
+        args: &HeaderArgs,
+    ) -> Result<Value, ExecuteError> {
+        self(code, vars, args)
+    }
+}
+
+/// Maps languages to their executors.
+///
+/// The default registry runs `sh`, `shell` and `bash` with [`Shell`], and `python` with [`Python`].
+pub struct SourceExecutor {
+    executors: HashMap<String, Box<dyn LanguageExecutor>>,
+}
+
+impl Default for SourceExecutor {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("sh", Shell::new("sh"));
+        registry.register("shell", Shell::new("sh"));
+        registry.register("bash", Shell::new("bash"));
+        registry.register("python", Python::default());
+        registry
+    }
+}
+
+impl SourceExecutor {
+    /// A registry without any executors.
+    pub fn empty() -> Self {
+        Self {
+            executors: HashMap::new(),
+        }
+    }
+
+    /// Registers an executor, replacing any previous executor for `lang`.
+    pub fn register(&mut self, lang: &str, executor: impl LanguageExecutor + 'static) {
+        self.executors.insert(lang.to_owned(), Box::new(executor));
+    }
+
+    /// Runs the source block at `block_id`.
+    ///
+    /// Noweb references are expanded first, and `:var` values that name a table or
+    /// another block are resolved, running that block.
+    ///
+    /// Returns `None` if the node isn't a source block or if no executor is registered for it.
+    pub fn evaluate(
+        &self,
+        parser: &Parser,
+        block_id: NodeID,
+    ) -> Option<Result<Value, ExecuteError>> {
+        let args = HeaderArgs::resolve(parser, block_id);
+        self.executor(parser, block_id)?;
+        Some(self.evaluate_with(parser, block_id, args, &mut Vec::new()))
+    }
+
+    /// Runs every block in `input` that has a registered executor, replacing
+    /// their previous results, and writes the document back with the [`Org`] exporter.
+    ///
+    /// Blocks with `:eval no` are skipped, as are blocks with `:cache yes` whose
+    /// results were produced by the same code and arguments.
+    pub fn execute(&self, input: &str) -> Result<String, ExecuteError> {
+        let parser = parse_org(input);
+        let results = self.run(&parser)?;
+
+        // the results are parsed after the end of the document, which keeps the spans
+        // of its nodes valid
+        let mut source = input.to_owned();
+        let mut ranges = Vec::with_capacity(results.len());
+        for result in &results {
+            source.push('\n');
+            let hash = result.hash.as_ref().map(|hash| {
+                let start = source.len();
+                source.push_str(hash);
+                source.push('\n');
+                start..start + hash.len()
+            });
+            let start = source.len();
+            source.push_str(&result.text);
+            ranges.push((hash, start..source.len()));
+        }
+        let mut parser = parser;
+        parser.extend_source(&source);
+        for (result, (hash, range)) in results.iter().zip(ranges) {
+            parser.set_results(result.block_id, range, hash.map(|hash| &source[hash]));
+        }
+
+        let mut conf = ConfigOptions::default();
+        conf.set_export_overrides(write_back_overrides());
+        let mut ret = String::with_capacity(input.len());
+        Org::export_tree(&parser, &mut ret, conf).map_err(ExecuteError::Export)?;
+        Ok(ret)
+    }
+
+    /// Runs every block that has a registered executor and formats its new results.
+    fn run(&self, parser: &Parser) -> Result<Vec<BlockResults>, ExecuteError> {
+        let mut blocks = Vec::new();
+        collect_nodes(
+            parser,
+            parser.pool.root_id(),
+            &|obj| matches!(obj, Expr::Block(Block::Src { .. })),
+            &mut blocks,
+        );
+
+        let mut ret = Vec::new();
+        for block_id in blocks {
+            let Expr::Block(Block::Src {
+                language,
+                results,
+                results_hash,
+                ..
+            }) = &parser.pool[block_id].obj
+            else {
+                continue;
+            };
+            if self.executor(parser, block_id).is_none() {
+                continue;
+            }
+            let args = HeaderArgs::resolve(parser, block_id);
+            if matches!(args.other.get("eval"), Some(&("no" | "never"))) {
+                continue;
+            }
+
+            let hash = if args.other.get("cache") == Some(&"yes") {
+                let hash = cache_hash(parser, block_id, &args);
+                if results.is_some() && *results_hash == Some(hash.as_str()) {
+                    continue;
+                }
+                Some(hash)
+            } else {
+                None
+            };
+
+            let value = self.evaluate_with(parser, block_id, args.clone(), &mut Vec::new())?;
+            let mut text = format_results(&value, &args, language.unwrap_or_default());
+            if let Some(file) = args.other.get("file") {
+                let file = HeaderArgs::unquote(file);
+                let path = Path::new(args.dir.unwrap_or_default()).join(file);
+                std::fs::write(&path, value_text(&value)).map_err(|source| ExecuteError::File {
+                    path: path.display().to_string(),
+                    source,
+                })?;
+                text = format!("[[file:{file}]]\n");
+            }
+
+            let existing = results.map_or("", |id| {
+                let node = &parser.pool[id];
+                parser.source[node.start..node.end].trim_end()
+            });
+            let text = match args.results.handling {
+                Some(ResultsHandling::Silent | ResultsHandling::None) => continue,
+                Some(ResultsHandling::Append) if !existing.is_empty() => {
+                    format!("{existing}\n{text}")
+                }
+                Some(ResultsHandling::Prepend) if !existing.is_empty() => {
+                    format!("{text}{existing}\n")
+                }
+                _ => text,
+            };
+            ret.push(BlockResults {
+                block_id,
+                text,
+                hash,
+            });
+        }
+        Ok(ret)
+    }
+
+    fn executor(&self, parser: &Parser, block_id: NodeID) -> Option<&dyn LanguageExecutor> {
+        let Expr::Block(Block::Src {
+            language: Some(lang),
+            ..
+        }) = &parser.pool[block_id].obj
+        else {
+            return None;
+        };
+        self.executors.get(*lang).map(|executor| executor.as_ref())
+    }
+
+    /// `stack` holds the blocks being evaluated, to catch blocks that depend on themselves.
+    fn evaluate_with(
+        &self,
+        parser: &Parser,
+        block_id: NodeID,
+        args: HeaderArgs,
+        stack: &mut Vec<NodeID>,
+    ) -> Result<Value, ExecuteError> {
+        let name = || {
+            node_name(parser, block_id)
+                .unwrap_or("anonymous")
+                .to_owned()
+        };
+        let Some(executor) = self.executor(parser, block_id) else {
+            let lang = match &parser.pool[block_id].obj {
+                Expr::Block(Block::Src { language, .. }) => language.unwrap_or_default(),
+                _ => "",
+            };
+            return Err(ExecuteError::UnknownLanguage(lang.to_owned()));
+        };
+        if stack.contains(&block_id) {
+            return Err(ExecuteError::Cycle(name()));
+        }
+        stack.push(block_id);
+
+        let (code, vars) = self.prepare(parser, block_id, &args, stack)?;
+        let ret = executor.execute(&code, &vars, &args);
+
+        stack.pop();
+        ret
+    }
+
+    /// The expanded code of a block and the values of its vars.
+    fn prepare<'a>(
+        &self,
+        parser: &'a Parser,
+        block_id: NodeID,
+        args: &HeaderArgs<'a>,
+        stack: &mut Vec<NodeID>,
+    ) -> Result<(String, Vars<'a>), ExecuteError> {
+        // errors from calls within noweb references, which only report that they failed
+        let mut call_error = None;
+        let code =
+            parser.expand_noweb_with(
+                block_id,
+                NowebContext::Eval,
+                &mut |id, call_args| match self.call(parser, id, call_args, stack) {
+                    Ok(value) => Some(value_text(&value)),
+                    Err(e) => {
+                        call_error.get_or_insert(e);
+                        None
+                    }
+                },
+            );
+        let code = match (code, call_error) {
+            (Ok(code), _) => code,
+            (Err(_), Some(e)) => return Err(e),
+            (Err(e), None) => return Err(e.into()),
+        };
+
+        let mut vars = Vec::new();
+        for var in &args.vars {
+            vars.push((var.name, self.resolve_var(parser, var.value, stack)?));
+        }
+        Ok((code, vars))
+    }
+
+    /// Evaluates a named block with additional arguments, as in `name(x=1, y=2)`.
+    fn call(
+        &self,
+        parser: &Parser,
+        block_id: NodeID,
+        call_args: &str,
+        stack: &mut Vec<NodeID>,
+    ) -> Result<Value, ExecuteError> {
+        let mut args = HeaderArgs::resolve(parser, block_id);
+        let headers = format!(":var {call_args}");
+        if !call_args.trim().is_empty() {
+            args.apply(&headers);
+        }
+        self.evaluate_with(parser, block_id, args, stack)
+    }
+
+    fn resolve_var(
+        &self,
+        parser: &Parser,
+        value: &str,
+        stack: &mut Vec<NodeID>,
+    ) -> Result<Value, ExecuteError> {
+        let value = value.trim();
+        if value.starts_with('"') {
+            return Ok(Value::Scalar(HeaderArgs::unquote(value).to_owned()));
+        }
+
+        let (name, call_args) = match value.strip_suffix(')').and_then(|v| v.split_once('(')) {
+            Some((name, call_args)) => (name, Some(call_args)),
+            None => (value, None),
+        };
+        let Some(&id) = parser.names.get(name) else {
+            return Ok(Value::Scalar(value.to_owned()));
+        };
+        match &parser.pool[id].obj {
+            Expr::Block(Block::Src { .. }) => {
+                self.call(parser, id, call_args.unwrap_or_default(), stack)
+            }
+            Expr::Table(_) => Ok(Value::Table(table_rows(parser, id))),
+            Expr::Block(Block::Example { contents, .. }) => {
+                Ok(Value::Scalar((*contents).to_owned()))
+            }
+            _ => Ok(Value::Scalar(value.to_owned())),
+        }
+    }
+}
+
+/// Identifies the code and arguments that produced a block's results.
+///
+/// Vars and noweb references are hashed as written, so checking the cache doesn't run
+/// the blocks they refer to.
+fn cache_hash(parser: &Parser, block_id: NodeID, args: &HeaderArgs) -> String {
+    let (lang, contents) = match &parser.pool[block_id].obj {
+        Expr::Block(Block::Src {
+            language, contents, ..
+        }) => (language.unwrap_or_default(), *contents),
+        _ => ("", ""),
+    };
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(format!("{lang}\n{:?}\n{:?}\n{contents}", args.results, args.vars).as_bytes());
+    hasher.digest().to_string()
+}
+
+/// Settings for writing a document back, keeping it as written where the [`Org`]
+/// exporter allows.
+fn write_back_overrides() -> ExportOverrides {
+    ExportOverrides {
+        footnotes: Some(true),
+        todo_keywords: Some(true),
+        priority: Some(true),
+        tags: Some(true),
+        planning: Some(true),
+        drawers: Some(Selection::All),
+        properties: Some(Selection::All),
+        latex: Some(LatexExport::Yes),
+        broken_links: Some(BrokenLinks::Ignore),
+        entities: Some(false),
+        preserve_breaks: Some(false),
+        timestamp: Some(false),
+        write_back: Some(true),
+        ..Default::default()
+    }
+}
+
+/// Runs code with a POSIX shell, such as `sh` or `bash`.
+///
+/// Vars are defined as shell variables, with tables written as lines of tab separated cells.
+/// The value of a block is its output.
+#[derive(Debug, Clone)]
+pub struct Shell {
+    command: String,
+}
+
+impl Shell {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_owned(),
+        }
+    }
+}
+
+impl LanguageExecutor for Shell {
+    fn execute(
+        &self,
+        code: &str,
+        vars: &[(&str, Value)],
+        args: &HeaderArgs,
+    ) -> Result<Value, ExecuteError> {
+        let mut script = String::new();
+        for (name, value) in vars {
+            let value = value_text(value);
+            script.push_str(&format!("{name}='{}'\n", value.replace('\'', r"'\''")));
+        }
+        script.push_str(code);
+        let output = run(&self.command, &[], &script, args.dir)?;
+        Ok(Value::Scalar(output))
+    }
+}
+
+/// Runs code with a Python interpreter, `python3` by default.
+///
+/// With `:results value`, the default, the code is the body of a function whose
+/// return value is the result. Lists of lists become tables.
+#[derive(Debug, Clone)]
+pub struct Python {
+    command: String,
+}
+
+impl Default for Python {
+    fn default() -> Self {
+        Self::new("python3")
+    }
+}
+
+impl Python {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_owned(),
+        }
+    }
+}
+
+impl LanguageExecutor for Python {
+    fn execute(
+        &self,
+        code: &str,
+        vars: &[(&str, Value)],
+        args: &HeaderArgs,
+    ) -> Result<Value, ExecuteError> {
+        let mut script = String::new();
+        for (name, value) in vars {
+            script.push_str(&format!("{name} = {}\n", python_literal(value)));
+        }
+
+        if args.results.collection == Some(ResultsCollection::Output) {
+            script.push_str(code);
+            let output = run(&self.command, &["-"], &script, args.dir)?;
+            return Ok(Value::Scalar(output));
+        }
+
+        script.push_str("def __org_main():\n");
+        for line in code.lines() {
+            script.push_str(&format!("    {line}\n"));
+        }
+        script.push_str(
+            r"    pass
+import io as __org_io, json as __org_json, sys as __org_sys
+__org_stdout = __org_sys.stdout
+__org_sys.stdout = __org_io.StringIO()
+__org_value = __org_main()
+__org_sys.stdout = __org_stdout
+print(__org_json.dumps(__org_value, default=str))
+",
+        );
+        let output = run(&self.command, &["-"], &script, args.dir)?;
+        let value: serde_json::Value =
+            serde_json::from_str(&output).map_err(|e| ExecuteError::InvalidOutput {
+                command: self.command.clone(),
+                reason: e.to_string(),
+            })?;
+        Ok(json_value(value))
+    }
+}
+
+fn run(
+    command: &str,
+    cmd_args: &[&str],
+    script: &str,
+    dir: Option<&str>,
+) -> Result<String, ExecuteError> {
+    let spawn_error = |source| ExecuteError::Spawn {
+        command: command.to_owned(),
+        source,
+    };
+    let mut cmd = Command::new(command);
+    cmd.args(cmd_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = dir {
+        cmd.current_dir(HeaderArgs::unquote(dir));
+    }
+    let mut child = cmd.spawn().map_err(spawn_error)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(script.as_bytes()).map_err(spawn_error)?;
+    }
+    let output = child.wait_with_output().map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(ExecuteError::Failed {
+            command: command.to_owned(),
+            status: output.status,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn json_value(value: serde_json::Value) -> Value {
+    let cell = |value: serde_json::Value| match value {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    match value {
+        serde_json::Value::Array(items) => {
+            if items.iter().all(|item| item.is_array()) && !items.is_empty() {
+                Value::Table(
+                    items
+                        .into_iter()
+                        .map(|row| match row {
+                            serde_json::Value::Array(cells) => {
+                                cells.into_iter().map(cell).collect()
+                            }
+                            _ => unreachable!(),
+                        })
+                        .collect(),
+                )
+            } else {
+                Value::Table(vec![items.into_iter().map(cell).collect()])
+            }
+        }
+        other => Value::Scalar(cell(other)),
+    }
+}
+
+fn python_literal(value: &Value) -> String {
+    let cell = |cell: &str| {
+        if cell.parse::<f64>().is_ok() {
+            cell.to_owned()
+        } else {
+            serde_json::Value::String(cell.to_owned()).to_string()
+        }
+    };
+    match value {
+        Value::Scalar(s) => cell(s.trim_end_matches('\n')),
+        Value::Table(rows) => {
+            let rows: Vec<String> = rows
+                .iter()
+                .map(|row| {
+                    let cells: Vec<String> = row.iter().map(|c| cell(c)).collect();
+                    format!("[{}]", cells.join(", "))
+                })
+                .collect();
+            format!("[{}]", rows.join(", "))
+        }
+    }
+}
+
+/// A value as plain text, with tables written as lines of tab separated cells.
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Scalar(s) => s.clone(),
+        Value::Table(rows) => rows.iter().map(|row| row.join("\t") + "\n").collect(),
+    }
+}
+
+/// Formats a value as the element inserted after `#+RESULTS:`.
+fn format_results(value: &Value, args: &HeaderArgs, lang: &str) -> String {
+    let text = value_text(value);
+    let text = if text.is_empty() || text.ends_with('\n') {
+        text
+    } else {
+        text + "\n"
+    };
+    let wrap = |begin: &str, end: &str| format!("{begin}\n{}{end}\n", escape_lines(&text));
+
+    match args.results.format {
+        Some(ResultsFormat::Raw) => return table_or(value, text),
+        Some(ResultsFormat::Drawer) => {
+            return format!(":results:\n{}:end:\n", table_or(value, text));
+        }
+        Some(ResultsFormat::Org) => return wrap("#+begin_src org", "#+end_src"),
+        Some(ResultsFormat::Html) => return wrap("#+begin_export html", "#+end_export"),
+        Some(ResultsFormat::Latex) => return wrap("#+begin_export latex", "#+end_export"),
+        Some(ResultsFormat::Code) => return wrap(&format!("#+begin_src {lang}"), "#+end_src"),
+        _ => {}
+    }
+
+    match (args.results.kind, value) {
+        (Some(ResultsType::Table), Value::Scalar(s)) => {
+            let rows: Vec<Option<Vec<String>>> = s
+                .lines()
+                .map(|line| {
+                    let cells: Vec<&str> = if line.contains('\t') {
+                        line.split('\t').collect()
+                    } else {
+                        line.split_whitespace().collect()
+                    };
+                    Some(cells.into_iter().map(str::to_owned).collect())
+                })
+                .collect();
+            format_table(&rows)
+        }
+        (Some(ResultsType::List), _) => text.lines().map(|line| format!("- {line}\n")).collect(),
+        (Some(ResultsType::Table) | None, Value::Table(_)) => table_or(value, text),
+        _ => {
+            if text.lines().count() > MIN_LINES_FOR_BLOCK {
+                wrap("#+begin_example", "#+end_example")
+            } else {
+                text.lines()
+                    .map(|line| {
+                        if line.is_empty() {
+                            ":\n".to_owned()
+                        } else {
+                            format!(": {line}\n")
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn table_or(value: &Value, text: String) -> String {
+    match value {
+        Value::Table(rows) => {
+            let rows: Vec<Option<Vec<String>>> = rows.iter().cloned().map(Some).collect();
+            format_table(&rows)
+        }
+        Value::Scalar(_) => text,
+    }
+}
+
+/// Escapes lines that would otherwise end a block or be read as a heading.
+fn escape_lines(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('*') || trimmed.starts_with("#+") || trimmed.starts_with(",*") {
+            ret.push_str(&line[..line.len() - trimmed.len()]);
+            ret.push(',');
+            ret.push_str(trimmed);
+        } else {
+            ret.push_str(line);
+        }
+    }
+    ret
+}
+
+/// The cells of a table as plain text, without rules.
+fn table_rows(parser: &Parser, table_id: NodeID) -> Vec<Vec<String>> {
+    let Expr::Table(table) = &parser.pool[table_id].obj else {
+        return Vec::new();
+    };
+    table
+        .children
+        .iter()
+        .filter_map(|row_id| match &parser.pool[*row_id].obj {
+            Expr::TableRow(TableRow::Standard(cells)) => Some(
+                cells
+                    .iter()
+                    .map(|cell_id| {
+                        let cell = &parser.pool[*cell_id];
+                        parser.source[cell.start..cell.end]
+                            .trim_end_matches(['|', '\n'])
+                            .trim()
+                            .to_owned()
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn shell_results() {
+        let input = r"* Data
+#+NAME: count
+#+begin_src sh :results output
+echo 1
+echo 2
+#+end_src
+
+#+RESULTS: count
+: old
+
+#+begin_src sh :results output table
+printf 'a\tb\nc\td\n'
+#+end_src
+- after
+";
+        let executed = SourceExecutor::default().execute(input).unwrap();
+        assert_eq!(
+            executed,
+            r"* Data
+#+NAME: count
+#+begin_src sh :results output
+echo 1
+echo 2
+#+end_src
+
+#+RESULTS: count
+: 1
+: 2
+
+#+begin_src sh :results output table
+printf 'a\tb\nc\td\n'
+#+end_src
+
+#+RESULTS:
+| a | b |
+| c | d |
+- after
+"
+        );
+        // running again leaves the document as it is
+        assert_eq!(
+            SourceExecutor::default().execute(&executed).unwrap(),
+            executed
+        );
+    }
+
+    #[test]
+    fn python_value_and_vars() {
+        let input = r"#+NAME: data
+| 1 | x |
+| 2 | y |
+
+#+begin_src python :var rows=data :var n=3
+return [[r[0] * n, r[1]] for r in rows]
+#+end_src
+";
+        assert_eq!(
+            SourceExecutor::default().execute(input).unwrap(),
+            r"#+NAME: data
+| 1 | x |
+| 2 | y |
+
+#+begin_src python :var rows=data :var n=3
+return [[r[0] * n, r[1]] for r in rows]
+#+end_src
+
+#+RESULTS:
+| 3 | x |
+| 6 | y |
+"
+        );
+    }
+
+    #[test]
+    fn only_results_change() {
+        let document = |results: &str| {
+            format!(
+                r"#+MACRO: greet hello $1
+#+INCLUDE: ./missing.org
+* Tasks [0/2]
+:PROPERTIES:
+:A: first
+:Z: last
+:END:
+{{{{{{greet(world)}}}}}}
+** DONE one
+** TODO two
+
+|a|b|
+|-
+| 1 |   |
+#+TBLFM: $2=$1/0
+
+#+begin_src sh :results output
+echo new
+#+end_src
+
+#+RESULTS:
+{results}
+
+Trailing text
+"
+            )
+        };
+        let executed = SourceExecutor::default()
+            .execute(&document(": old"))
+            .unwrap();
+        assert_eq!(executed, document(": new"));
+        assert_eq!(
+            SourceExecutor::default().execute(&executed).unwrap(),
+            executed
+        );
+    }
+
+    #[test]
+    fn noweb_calls() {
+        let input = r#"#+NAME: square
+#+begin_src python :var x=0
+return x * x
+#+end_src
+
+#+begin_src sh :noweb yes :results output drawer
+echo "<<square(x=4)>>"
+#+end_src
+"#;
+        let executed = SourceExecutor::default().execute(input).unwrap();
+        assert!(executed.ends_with("#+RESULTS:\n:results:\n16\n:end:\n"));
+    }
+
+    #[test]
+    fn cached_results() {
+        let runs = Rc::new(Cell::new(0));
+        let mut executor = SourceExecutor::empty();
+        let counter = runs.clone();
+        executor.register(
+            "fake",
+            move |code: &str, _: &[(&str, Value)], _: &HeaderArgs| {
+                counter.set(counter.get() + 1);
+                Ok(Value::Scalar(code.to_uppercase()))
+            },
+        );
+
+        let input = "#+begin_src fake :cache yes\nhello\n#+end_src\n";
+        let executed = executor.execute(input).unwrap();
+        assert!(executed.contains("#+RESULTS["));
+        assert!(executed.ends_with("]:\n: HELLO\n"));
+        assert_eq!(executor.execute(&executed).unwrap(), executed);
+        assert_eq!(runs.get(), 1);
+
+        let changed = executed.replace("hello", "bye");
+        assert!(executor.execute(&changed).unwrap().ends_with("]:\n: BYE\n"));
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn cached_dependencies() {
+        let runs = Rc::new(Cell::new(0));
+        let mut executor = SourceExecutor::empty();
+        let counter = runs.clone();
+        executor.register(
+            "fake",
+            move |code: &str, _: &[(&str, Value)], _: &HeaderArgs| {
+                counter.set(counter.get() + 1);
+                Ok(Value::Scalar(code.to_uppercase()))
+            },
+        );
+
+        let input = r"#+NAME: dep
+#+begin_src fake :eval no
+dep
+#+end_src
+
+#+begin_src fake :cache yes :var x=dep
+hello
+#+end_src
+";
+        // the dependency runs once along with the block
+        let executed = executor.execute(input).unwrap();
+        assert_eq!(runs.get(), 2);
+        // and not at all when the results are cached
+        assert_eq!(executor.execute(&executed).unwrap(), executed);
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn failing_block() {
+        let input = "#+begin_src sh\nexit 3\n#+end_src\n";
+        assert!(matches!(
+            SourceExecutor::default().execute(input),
+            Err(ExecuteError::Failed { .. })
+        ));
+    }
+}
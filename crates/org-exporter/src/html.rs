@@ -6,17 +6,25 @@ use core::fmt;
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
+use std::sync::Arc;
 
 use latex2mathml::{latex_to_mathml, DisplayStyle};
 use memchr::memchr3_iter;
-use org_parser::element::{Affiliated, Block, CheckBox, ListKind, TableRow};
-use org_parser::object::{LatexFragment, PathReg, PlainOrRec};
-use org_parser::{parse_macro_call, parse_org, Expr, Node, NodeID, Parser};
+use org_parser::element::{
+    Affiliated, Block, BlockSwitches, CheckBox, ColumnAlign, LineNumbers, ListKind, Priority,
+    TableRow, Tag, TodoKeyword,
+};
+use org_parser::object::{DateTime, LatexFragment, PathReg, PlainOrRec, Timestamp};
+use org_parser::{
+    parse_macro_call, parse_org, Expr, HeaderArgs, Node, NodeID, NowebContext, Parser, TodoState,
+};
 
+use crate::cite::{document_processor, CitationProcessor};
 use crate::include::include_handle;
 use crate::org_macros::macro_handle;
+use crate::settings::{BrokenLinks, ExportSettings, LatexExport, SubSuperscripts};
 use crate::types::{ConfigOptions, Exporter, ExporterInner, LogicErrorKind};
-use crate::utils::{process_toc, Options, TocItem};
+use crate::utils::{export_time, is_broken_link, process_toc, TocItem};
 use crate::ExportError;
 use phf::phf_set;
 
@@ -74,6 +82,18 @@ pub struct Html<'buf> {
     // used footnotes
     footnotes: Vec<NodeID>,
     footnote_ids: HashMap<NodeID, usize>,
+    // cited keys, in order of first appearance
+    cited: Vec<String>,
+    cite_processor: Option<Arc<dyn CitationProcessor + Send + Sync>>,
+    settings: ExportSettings,
+    // section number of the last numbered headline, one counter per level
+    section_numbers: Vec<usize>,
+    // first line number of each numbered src/example block
+    line_numbers: HashMap<NodeID, usize>,
+    // coderef labels, mapped to the text links to them show
+    coderefs: HashMap<String, String>,
+    // within a verse block, where line breaks and indentation are kept
+    in_verse: bool,
     conf: ConfigOptions,
     errors: Vec<ExportError>,
 }
@@ -138,24 +158,48 @@ impl<'buf> Exporter<'buf> for Html<'buf> {
     fn export_tree<'inp, T: fmt::Write>(
         parsed: &Parser,
         buf: &'buf mut T,
-        conf: ConfigOptions,
+        mut conf: ConfigOptions,
     ) -> core::result::Result<(), Vec<ExportError>> {
+        // macros and includes are exported with the document's settings
+        let settings =
+            ExportSettings::resolve(parsed, conf.export_settings(), conf.export_overrides());
+        conf.set_export_settings(settings.clone());
         let mut obj = Html {
             buf,
             nox: HashSet::new(),
             footnotes: Vec::new(),
             footnote_ids: HashMap::new(),
+            cited: Vec::new(),
+            cite_processor: conf.citation_processor().cloned(),
+            settings,
+            section_numbers: Vec::new(),
+            line_numbers: HashMap::new(),
+            coderefs: HashMap::new(),
+            in_verse: false,
             conf,
             errors: Vec::new(),
         };
 
-        if let Ok(opts) = Options::handle_opts(parsed) {
-            if let Ok(tocs) = process_toc(parsed, &opts) {
-                handle_toc(parsed, &mut obj, &tocs);
-            }
+        let has_citations = parsed
+            .pool
+            .iter()
+            .any(|node| matches!(node.obj, Expr::Citation(_)));
+        if obj.cite_processor.is_none() && has_citations {
+            let (processor, errors) = document_processor(parsed, &obj.conf);
+            obj.cite_processor = Some(Arc::new(processor));
+            obj.errors.extend(errors);
+        }
+
+        resolve_code_lines(parsed, parsed.pool.root_id(), &mut 0, &mut obj);
+
+        obj.exp_title(parsed);
+        if let Some(toc_level) = obj.settings.toc.max_level(obj.settings.headline_levels) {
+            let tocs = process_toc(parsed, toc_level);
+            handle_toc(parsed, &mut obj, &tocs);
         }
         obj.export_rec(&parsed.pool.root_id(), &parsed);
         obj.exp_footnotes(&parsed);
+        obj.exp_postamble(parsed);
 
         if obj.errors().is_empty() {
             Ok(())
@@ -165,6 +209,49 @@ impl<'buf> Exporter<'buf> for Html<'buf> {
     }
 }
 
+/// Numbers the lines of src and example blocks in document order, and
+/// resolves what links to each coderef label display.
+fn resolve_code_lines(parsed: &Parser, node_id: NodeID, last_line: &mut usize, obj: &mut Html) {
+    let node = &parsed.pool[node_id];
+    if let Expr::Block(
+        Block::Src {
+            switches,
+            contents,
+            coderefs,
+            ..
+        }
+        | Block::Example {
+            switches,
+            contents,
+            coderefs,
+            ..
+        },
+    ) = &node.obj
+    {
+        let start = switches.number_lines.map(|numbering| {
+            let start = match numbering {
+                LineNumbers::New(start) => start,
+                LineNumbers::Continued(skip) => *last_line + skip + 1,
+            };
+            *last_line = (start + contents.lines().count()).saturating_sub(1);
+            obj.line_numbers.insert(node_id, start);
+            start
+        });
+        for coderef in coderefs {
+            let text = if switches.use_labels() {
+                coderef.label.to_string()
+            } else {
+                (start.unwrap_or(1) + coderef.line).to_string()
+            };
+            obj.coderefs.insert(coderef.label.to_string(), text);
+        }
+    } else if let Some(children) = node.obj.children() {
+        for &child in children {
+            resolve_code_lines(parsed, child, last_line, obj);
+        }
+    }
+}
+
 fn handle_toc<'a, T: fmt::Write + ExporterInner<'a>>(
     parsed: &Parser,
     writer: &mut T,
@@ -189,7 +276,7 @@ fn toc_rec<'a, T: fmt::Write + ExporterInner<'a>>(
     parser: &Parser,
     writer: &mut T,
     parent: &TocItem,
-    curr_level: u8,
+    curr_level: usize,
 ) {
     w!(writer, "<li>");
     if curr_level < parent.level {
@@ -213,6 +300,54 @@ fn toc_rec<'a, T: fmt::Write + ExporterInner<'a>>(
     w!(writer, "</li>");
 }
 
+/// Whether a heading is the first and the last of a run of sibling headings.
+fn heading_sibling_bounds(parser: &Parser, node_id: NodeID) -> (bool, bool) {
+    let is_heading =
+        |id: Option<&NodeID>| id.is_some_and(|id| matches!(parser.pool[*id].obj, Expr::Heading(_)));
+
+    parser.pool[node_id]
+        .parent
+        .and_then(|parent_id| parser.pool[parent_id].obj.children())
+        .and_then(|siblings| {
+            let pos = siblings.iter().position(|id| *id == node_id)?;
+            let prev = pos.checked_sub(1).and_then(|pos| siblings.get(pos));
+            Some((!is_heading(prev), !is_heading(siblings.get(pos + 1))))
+        })
+        .unwrap_or((true, true))
+}
+
+fn align_class(align: ColumnAlign) -> &'static str {
+    match align {
+        ColumnAlign::Left => "org-left",
+        ColumnAlign::Center => "org-center",
+        ColumnAlign::Right => "org-right",
+    }
+}
+
+/// Applies smart quotes and special strings to plain text, `prev` is the character before it.
+fn typographic(text: &str, mut prev: Option<char>, settings: &ExportSettings) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(chr) = rest.chars().next() {
+        let opening = prev.map_or(true, |prev| {
+            prev.is_whitespace() || matches!(prev, '(' | '[' | '{' | '\u{2014}' | '\u{2013}')
+        });
+        let (replacement, len) = match chr {
+            '"' if settings.smart_quotes => (if opening { '\u{201c}' } else { '\u{201d}' }, 1),
+            '\'' if settings.smart_quotes => (if opening { '\u{2018}' } else { '\u{2019}' }, 1),
+            '\\' if settings.special_strings && rest.starts_with("\\-") => ('\u{ad}', 2),
+            '-' if settings.special_strings && rest.starts_with("---") => ('\u{2014}', 3),
+            '-' if settings.special_strings && rest.starts_with("--") => ('\u{2013}', 2),
+            '.' if settings.special_strings && rest.starts_with("...") => ('\u{2026}', 3),
+            _ => (chr, chr.len_utf8()),
+        };
+        ret.push(replacement);
+        prev = Some(replacement);
+        rest = &rest[len..];
+    }
+    ret
+}
+
 impl<'buf> ExporterInner<'buf> for Html<'buf> {
     fn export_macro_buf<'inp, T: fmt::Write>(
         input: &'inp str,
@@ -225,6 +360,13 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
             nox: HashSet::new(),
             footnotes: Vec::new(),
             footnote_ids: HashMap::new(),
+            cited: Vec::new(),
+            cite_processor: conf.citation_processor().cloned(),
+            settings: conf.export_settings().clone(),
+            section_numbers: Vec::new(),
+            line_numbers: HashMap::new(),
+            coderefs: HashMap::new(),
+            in_verse: false,
             conf,
             errors: Vec::new(),
         };
@@ -250,25 +392,136 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                 }
             }
             Expr::Heading(inner) => {
-                let heading_number: u8 = inner.heading_level.into();
+                let level = inner.heading_level.get();
+                // low level headlines are exported as a list, where consecutive
+                // siblings share the same list
+                let low_level = level > self.settings.headline_levels;
+                let (first, last) = if low_level {
+                    heading_sibling_bounds(parser, *node_id)
+                } else {
+                    (false, false)
+                };
 
-                w!(self, "<h{heading_number}");
-                self.prop(node);
-                w!(self, ">");
+                if low_level {
+                    if first {
+                        w!(self, "<ul");
+                        self.class("org-ul");
+                        w!(self, ">\n");
+                    }
+                    w!(self, "<li");
+                    self.prop(node);
+                    w!(self, ">");
+                    self.headline_title(
+                        parser,
+                        inner.keyword,
+                        &inner.priority,
+                        &inner.title,
+                        *node_id,
+                    );
+                    w!(self, "<br>\n");
+                } else if level > 6 {
+                    // there is no <h7>
+                    w!(self, "<div");
+                    self.class(&format!("outline-{level}"));
+                    self.prop(node);
+                    w!(self, r#" role="heading" aria-level="{level}">"#);
+                    self.section_number(parser, *node_id, level);
+                    self.headline_title(
+                        parser,
+                        inner.keyword,
+                        &inner.priority,
+                        &inner.title,
+                        *node_id,
+                    );
+                    w!(self, "</div>\n");
+                } else {
+                    w!(self, "<h{level}");
+                    self.prop(node);
+                    w!(self, ">");
+                    self.section_number(parser, *node_id, level);
+                    self.headline_title(
+                        parser,
+                        inner.keyword,
+                        &inner.priority,
+                        &inner.title,
+                        *node_id,
+                    );
+                    w!(self, "</h{level}>\n");
+                }
+
+                if let Some(planning) = inner.planning.as_ref().filter(|_| self.settings.planning) {
+                    w!(self, r#"<p class="planning">"#);
+                    let mut first = true;
+                    for (kwd, stamp) in [
+                        ("DEADLINE", &planning.deadline),
+                        ("SCHEDULED", &planning.scheduled),
+                        ("CLOSED", &planning.closed),
+                    ] {
+                        if let Some(stamp) = stamp {
+                            if !first {
+                                w!(self, " ");
+                            }
+                            first = false;
+                            w!(self, r#"<span class="timestamp-kwd">{kwd}:</span> "#);
+                            self.timestamp(stamp);
+                        }
+                    }
+                    w!(self, "</p>\n");
+                }
+
+                if let Some(properties) = &inner.properties {
+                    let mut exported = properties
+                        .iter()
+                        .filter(|(key, _)| self.settings.properties.includes(key))
+                        .collect::<Vec<_>>();
+                    if !exported.is_empty() {
+                        exported.sort_by_key(|(key, _)| *key);
+                        w!(self, "<pre");
+                        self.class("example");
+                        w!(self, ">\n");
+                        for (key, val) in exported {
+                            w!(self, "{}: {}\n", HtmlEscape(key), HtmlEscape(val));
+                        }
+                        w!(self, "</pre>\n");
+                    }
+                }
 
-                if let Some(title) = &inner.title {
-                    for id in &title.1 {
+                if let Some(children) = &inner.children {
+                    for id in children {
                         self.export_rec(id, parser);
                     }
                 }
 
-                w!(self, "</h{heading_number}>\n");
+                if low_level {
+                    w!(self, "</li>\n");
+                    if last {
+                        w!(self, "</ul>\n");
+                    }
+                }
+            }
+            Expr::InlineTask(inner) => {
+                w!(self, "<div");
+                self.class("inlinetask");
+                self.prop(node);
+                w!(self, ">\n<b>");
+                self.headline_title(
+                    parser,
+                    inner.keyword,
+                    &inner.priority,
+                    &inner.title,
+                    *node_id,
+                );
+                w!(self, "</b>");
 
                 if let Some(children) = &inner.children {
+                    w!(self, "<br>\n");
                     for id in children {
                         self.export_rec(id, parser);
                     }
+                } else {
+                    w!(self, "\n");
                 }
+                w!(self, "</div>\n");
             }
             Expr::Block(inner) => {
                 match inner {
@@ -344,8 +597,10 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                         w!(self, "<!--{contents}-->\n");
                     }
                     Block::Example {
+                        switches,
                         parameters,
                         contents,
+                        ..
                     } => {
                         if parameters.get("exports").is_some_and(|&x| x == "none") {
                             return;
@@ -353,7 +608,9 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                         w!(self, "<pre");
                         self.class("example");
                         self.prop(node);
-                        w!(self, ">\n{}</pre>\n", HtmlEscape(contents));
+                        w!(self, ">\n");
+                        self.code_lines(*node_id, switches, contents);
+                        w!(self, "</pre>\n");
                     }
                     Block::Export {
                         backend,
@@ -369,20 +626,36 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                     }
                     Block::Src {
                         language,
-                        parameters,
+                        switches,
                         contents,
+                        results,
+                        ..
                     } => {
-                        if parameters.get("exports").is_some_and(|&x| x == "none") {
-                            return;
+                        let exports = HeaderArgs::resolve(parser, *node_id).exports;
+                        if exports.code() {
+                            w!(self, "<pre>");
+                            w!(self, "<code");
+                            self.class("src");
+                            if let Some(lang) = language {
+                                self.class(&format!("src-{}", lang));
+                            }
+                            self.prop(node);
+                            w!(self, ">\n");
+                            match parser.expand_noweb(*node_id, NowebContext::Export) {
+                                Ok(expanded) => self.code_lines(*node_id, switches, &expanded),
+                                Err(e) => {
+                                    self.errors().push(ExportError::LogicError {
+                                        span: node.start..node.end,
+                                        source: LogicErrorKind::Noweb(e),
+                                    });
+                                    self.code_lines(*node_id, switches, contents);
+                                }
+                            }
+                            w!(self, "</pre></code>\n");
                         }
-                        w!(self, "<pre>");
-                        w!(self, "<code");
-                        self.class("src");
-                        if let Some(lang) = language {
-                            self.class(&format!("src-{}", lang));
+                        if let Some(results) = results.filter(|_| exports.results()) {
+                            self.export_rec(&results, parser);
                         }
-                        self.prop(node);
-                        w!(self, ">\n{}</pre></code>\n", HtmlEscape(contents));
                     }
                     Block::Verse {
                         parameters,
@@ -391,20 +664,38 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                         if parameters.get("exports").is_some_and(|&x| x == "none") {
                             return;
                         }
-                        // FIXME: apparently verse blocks contain objects...
                         w!(self, "<p");
                         self.class("verse");
                         self.prop(node);
-                        w!(self, ">\n{}</p>\n", HtmlEscape(contents));
+                        w!(self, ">\n");
+                        self.in_verse = true;
+                        for id in contents {
+                            self.export_rec(id, parser);
+                        }
+                        self.in_verse = false;
+                        w!(self, "</p>\n");
                     }
                 }
             }
             Expr::RegularLink(inner) => {
+                if self.settings.broken_links != BrokenLinks::Ignore
+                    && is_broken_link(parser, &inner.path.obj)
+                {
+                    let path = inner.path.to_str(parser.source);
+                    if self.settings.broken_links == BrokenLinks::Mark {
+                        w!(self, "[BROKEN LINK: {}]", HtmlEscape(path));
+                        return;
+                    }
+                    self.errors.push(ExportError::LogicError {
+                        span: node.start..node.end,
+                        source: LogicErrorKind::BrokenLink(path.into()),
+                    });
+                }
                 let path_link: String = match &inner.path.obj {
                     PathReg::PlainLink(a) => a.into(),
                     PathReg::Id(a) => format!("#{a}"),
                     PathReg::CustomId(a) => format!("#{a}"),
-                    PathReg::Coderef(_) => todo!(),
+                    PathReg::Coderef(label) => format!("#coderef-{label}"),
                     PathReg::Unspecified(a) => {
                         let mut rita = String::new();
                         // see if the link is present in someone's target
@@ -426,11 +717,21 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                     }
                     PathReg::File(a) => format!("{a}"),
                 };
-                w!(self, r#"<a href="{}">"#, HtmlEscape(&path_link));
+                w!(self, r#"<a href="{}""#, HtmlEscape(&path_link));
+                if let PathReg::Coderef(_) = inner.path.obj {
+                    self.class("coderef");
+                }
+                w!(self, ">");
                 if let Some(children) = &inner.description {
                     for id in children {
                         self.export_rec(id, parser);
                     }
+                } else if let PathReg::Coderef(label) = inner.path.obj {
+                    let text = self
+                        .coderefs
+                        .get(label)
+                        .map_or_else(|| label.to_string(), Clone::clone);
+                    w!(self, "{}", HtmlEscape(text));
                 } else {
                     w!(self, "{}", HtmlEscape(inner.path.to_str(parser.source)));
                 }
@@ -490,32 +791,16 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
             }
 
             Expr::Italic(inner) => {
-                w!(self, "<em>");
-                for id in &inner.0 {
-                    self.export_rec(id, parser);
-                }
-                w!(self, "</em>");
+                self.emphasis(parser, &inner.0, "em", '/');
             }
             Expr::Bold(inner) => {
-                w!(self, "<b>");
-                for id in &inner.0 {
-                    self.export_rec(id, parser);
-                }
-                w!(self, "</b>");
+                self.emphasis(parser, &inner.0, "b", '*');
             }
             Expr::StrikeThrough(inner) => {
-                w!(self, "<del>");
-                for id in &inner.0 {
-                    self.export_rec(id, parser);
-                }
-                w!(self, "</del>");
+                self.emphasis(parser, &inner.0, "del", '+');
             }
             Expr::Underline(inner) => {
-                w!(self, "<u>");
-                for id in &inner.0 {
-                    self.export_rec(id, parser);
-                }
-                w!(self, "</u>");
+                self.emphasis(parser, &inner.0, "u", '_');
                 // w!(self, "<span class=underline>")?;
                 // for id in &inner.0 {
                 //     self.export_rec(id, parser);
@@ -526,16 +811,42 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                 // w!(self, "\n")?;
             }
             Expr::SoftBreak => {
-                w!(self, " ");
+                if self.in_verse || self.settings.preserve_breaks {
+                    w!(self, "<br>\n");
+                } else {
+                    w!(self, " ");
+                }
             }
             Expr::LineBreak => {
-                w!(self, "\n<br>\n");
+                if self.in_verse {
+                    w!(self, "<br>\n");
+                } else {
+                    w!(self, "\n<br>\n");
+                }
             }
             Expr::HorizontalRule => {
                 w!(self, "\n<hr>\n");
             }
             Expr::Plain(inner) => {
-                w!(self, "{}", HtmlEscape(inner));
+                let inner: Cow<str> = if self.settings.smart_quotes || self.settings.special_strings
+                {
+                    let prev = parser.source[..node.start].chars().next_back();
+                    typographic(inner, prev, &self.settings).into()
+                } else {
+                    (*inner).into()
+                };
+                let line_start =
+                    || node.start == 0 || parser.source.as_bytes()[node.start - 1] == b'\n';
+                if self.in_verse && line_start() {
+                    // indentation is kept with non-breaking spaces
+                    let text = inner.trim_start_matches(' ');
+                    for _ in 0..inner.len() - text.len() {
+                        w!(self, "&#xa0;");
+                    }
+                    w!(self, "{}", HtmlEscape(text));
+                } else {
+                    w!(self, "{}", HtmlEscape(&inner));
+                }
             }
             Expr::Verbatim(inner) => {
                 w!(self, "<code>{}</code>", HtmlEscape(inner.0));
@@ -543,6 +854,34 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
             Expr::Code(inner) => {
                 w!(self, "<code>{}</code>", HtmlEscape(inner.0));
             }
+            Expr::FixedWidth(inner) => {
+                w!(self, "<pre");
+                self.class("example");
+                self.prop(node);
+                w!(self, ">\n");
+                for line in &inner.0 {
+                    w!(self, "{}\n", HtmlEscape(line));
+                }
+                w!(self, "</pre>\n");
+            }
+            Expr::BabelCall(inner) => {
+                let exports = HeaderArgs::resolve(parser, *node_id).exports;
+                if exports.code() {
+                    w!(self, "<pre");
+                    self.class("example");
+                    self.prop(node);
+                    w!(self, ">\n{}\n</pre>\n", HtmlEscape(inner.to_string()));
+                }
+                if let Some(results) = inner.results.filter(|_| exports.results()) {
+                    self.export_rec(&results, parser);
+                }
+            }
+            Expr::InlineBabelCall(inner) => {
+                // results are written after the call, in a {{{results(...)}}} macro
+                if HeaderArgs::resolve(parser, *node_id).exports.code() {
+                    w!(self, "<code>{}</code>", HtmlEscape(inner.to_string()));
+                }
+            }
             Expr::Comment(inner) => {
                 w!(self, "<!--{}-->", inner.0);
             }
@@ -577,9 +916,25 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                     //     source: LogicErrorKind::Include(e),
                     // })?;
                     w!(self, "</div>");
+                } else if inner.key.eq_ignore_ascii_case("print_bibliography") {
+                    self.exp_bibliography();
                 }
             }
             Expr::LatexEnv(inner) => {
+                match self.settings.latex {
+                    LatexExport::Yes => {}
+                    LatexExport::Verbatim => {
+                        w!(self, "<pre");
+                        self.class("example");
+                        w!(
+                            self,
+                            ">\n{}</pre>\n",
+                            HtmlEscape(&parser.source[node.start..node.end])
+                        );
+                        return;
+                    }
+                    LatexExport::No => return,
+                }
                 let formatted = &format!(
                     r"\begin{{{0}}}
 {1}
@@ -595,6 +950,11 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                     if let Ok(val) = &ret { val } else { formatted }
                 );
             }
+            Expr::LatexFragment(_) if self.settings.latex != LatexExport::Yes => {
+                if self.settings.latex == LatexExport::Verbatim {
+                    w!(self, "{}", HtmlEscape(&parser.source[node.start..node.end]));
+                }
+            }
             Expr::LatexFragment(inner) => match inner {
                 LatexFragment::Command { name, contents } => {
                     let mut pot_cont = String::new();
@@ -690,15 +1050,65 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                 );
             }
             Expr::Entity(inner) => {
-                w!(self, "{}", inner.mapped_item);
+                if self.settings.entities {
+                    w!(self, "{}", inner.mapped_item);
+                } else {
+                    w!(self, "{}", HtmlEscape(&parser.source[node.start..node.end]));
+                }
             }
             Expr::Table(inner) => {
                 w!(self, "<table");
                 self.prop(node);
                 w!(self, ">\n");
 
-                for id in &inner.children {
-                    self.export_rec(id, parser);
+                for (i, column) in inner.columns.iter().enumerate() {
+                    if column.group_start {
+                        if i > 0 {
+                            w!(self, "</colgroup>\n");
+                        }
+                        w!(self, "<colgroup>\n");
+                    }
+                    w!(self, r#"<col class="{}">"#, align_class(column.align));
+                    w!(self, "\n");
+                }
+                if !inner.columns.is_empty() {
+                    w!(self, "</colgroup>\n");
+                }
+
+                let skip = usize::from(inner.special_column);
+                for (i, group) in inner.groups.iter().enumerate() {
+                    let is_header = i == 0 && inner.header().is_some();
+                    w!(self, "{}\n", if is_header { "<thead>" } else { "<tbody>" });
+                    for id in group {
+                        let Expr::TableRow(TableRow::Standard(cells)) = &parser.pool[*id].obj
+                        else {
+                            continue;
+                        };
+                        w!(self, "<tr>\n");
+                        for (col, cell_id) in cells.iter().enumerate().skip(skip) {
+                            let class = inner
+                                .columns
+                                .get(col - skip)
+                                .map_or("org-left", |column| align_class(column.align));
+                            if is_header {
+                                w!(self, r#"<th scope="col" class="{class}">"#);
+                            } else {
+                                w!(self, r#"<td class="{class}">"#);
+                            }
+                            if let Expr::TableCell(cell) = &parser.pool[*cell_id].obj {
+                                for id in &cell.0 {
+                                    self.export_rec(id, parser);
+                                }
+                            }
+                            w!(self, "{}\n", if is_header { "</th>" } else { "</td>" });
+                        }
+                        w!(self, "</tr>\n");
+                    }
+                    w!(
+                        self,
+                        "{}\n",
+                        if is_header { "</thead>" } else { "</tbody>" }
+                    );
                 }
 
                 w!(self, "</table>\n");
@@ -726,6 +1136,9 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
             Expr::Emoji(inner) => {
                 w!(self, "{}", inner.mapped_item);
             }
+            Expr::Superscript(inner) if !self.scripts_enabled(&inner.0) => {
+                w!(self, "{}", HtmlEscape(&parser.source[node.start..node.end]));
+            }
             Expr::Superscript(inner) => {
                 w!(self, "<sup>");
                 match &inner.0 {
@@ -740,6 +1153,9 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                 }
                 w!(self, "</sup>");
             }
+            Expr::Subscript(inner) if !self.scripts_enabled(&inner.0) => {
+                w!(self, "{}", HtmlEscape(&parser.source[node.start..node.end]));
+            }
             Expr::Subscript(inner) => {
                 w!(self, "<sub>");
                 match &inner.0 {
@@ -765,6 +1181,38 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                     HtmlEscape(inner.0)
                 );
             }
+            Expr::RadioTarget(inner) => {
+                w!(self, "<span");
+                self.prop(node);
+                w!(self, ">{}</span>", HtmlEscape(inner.0));
+            }
+            Expr::RadioLink(inner) => {
+                // radio targets always have an id
+                if let Some(target) = &parser.pool[inner.target].id_target {
+                    w!(self, r##"<a href="#{target}">"##);
+                    w!(self, "{}</a>", HtmlEscape(inner.contents));
+                } else {
+                    w!(self, "{}", HtmlEscape(inner.contents));
+                }
+            }
+            Expr::Citation(inner) => {
+                for reference in &inner.references {
+                    if !self.cited.iter().any(|key| key == reference.key) {
+                        self.cited.push(reference.key.into());
+                    }
+                }
+                if let Some(processor) = &self.cite_processor {
+                    let formatted = processor.cite(inner, &self.cited);
+                    w!(self, "{formatted}");
+                } else {
+                    w!(self, "{}", HtmlEscape(inner.to_string()));
+                }
+            }
+            Expr::StatisticsCookie(_) if !self.settings.statistics => {}
+            Expr::StatisticsCookie(inner) => {
+                let cookie = parser.statistics(*node_id).unwrap_or(*inner);
+                w!(self, "<code>{cookie}</code>");
+            }
             Expr::Macro(macro_call) => {
                 let macro_contents = match macro_handle(parser, macro_call, self.config_opts()) {
                     Ok(contents) => contents,
@@ -792,6 +1240,14 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                 }
             }
             Expr::Drawer(inner) => {
+                if !self.settings.drawers.includes(inner.name) {
+                    return;
+                }
+                for id in &inner.children {
+                    self.export_rec(id, parser);
+                }
+            }
+            Expr::DynamicBlock(inner) => {
                 for id in &inner.children {
                     self.export_rec(id, parser);
                 }
@@ -820,6 +1276,7 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
             Expr::FootnoteDef(_) => {
                 // handled after root
             }
+            Expr::FootnoteRef(_) if !self.settings.footnotes => {}
             Expr::FootnoteRef(inner) => {
                 let foot_len = self.footnotes.len();
                 let target_id = if let Some(label) = inner.label {
@@ -860,6 +1317,34 @@ impl<'buf> ExporterInner<'buf> for Html<'buf> {
                     index,
                 );
             }
+            Expr::Timestamp(inner) => {
+                self.timestamp(inner);
+            }
+            Expr::SexpTimestamp(inner) => {
+                w!(
+                    self,
+                    r#"<span class="timestamp-wrapper"><span class="timestamp">{}</span></span>"#,
+                    HtmlEscape(inner.to_string())
+                );
+            }
+            // diary entries are only meaningful to the agenda
+            Expr::DiarySexp(_) => {}
+            Expr::Clock(inner) => {
+                w!(
+                    self,
+                    r#"<p class="clock"><span class="timestamp-kwd">CLOCK:</span> "#
+                );
+                self.timestamp(&inner.timestamp());
+                if let Some(minutes) = inner.minutes() {
+                    w!(
+                        self,
+                        r#" <span class="timestamp">({}:{:02})</span>"#,
+                        minutes / 60,
+                        minutes % 60
+                    );
+                }
+                w!(self, "</p>\n");
+            }
         }
     }
 
@@ -892,71 +1377,353 @@ impl<'buf> Html<'buf> {
         }
     }
 
-    fn class(&mut self, name: &str) {
-        w!(self, r#" class="{name}""#);
-    }
-
-    fn attr(&mut self, key: &str, val: &str) {
-        w!(self, r#" {}="{}""#, key, HtmlEscape(val));
-    }
-
-    fn exp_footnotes(&mut self, parser: &Parser) {
-        if self.footnotes.is_empty() {
+    /// Writes the contents of a src or example block, adding line numbers
+    /// and coderef anchors
+    fn code_lines(&mut self, node_id: NodeID, switches: &BlockSwitches, contents: &str) {
+        let start = self.line_numbers.get(&node_id).copied();
+        let has_labels = contents
+            .lines()
+            .any(|line| switches.split_label(line).1.is_some());
+        if start.is_none() && !has_labels {
+            w!(self, "{}", HtmlEscape(contents));
             return;
         }
 
-        // get last heading, and check if its title is Footnotes,
-        // if so, destroy it
-        let heading_query = parser.pool.iter().rev().find(|node| {
-            if let Expr::Heading(head) = &node.obj {
-                if let Some(title) = &head.title {
-                    if title.0 == "Footnotes\n" {
-                        return true;
-                    }
+        let width = start.map_or(0, |start| {
+            (start + contents.lines().count())
+                .saturating_sub(1)
+                .to_string()
+                .len()
+        });
+        for (i, line) in contents.lines().enumerate() {
+            let (code, label) = switches.split_label(line);
+            if let Some(label) = label {
+                w!(
+                    self,
+                    r#"<span id="coderef-{}" class="coderef-off">"#,
+                    HtmlEscape(label)
+                );
+            }
+            if let Some(start) = start {
+                w!(
+                    self,
+                    r#"<span class="linenr">{:>width$}: </span>"#,
+                    start + i
+                );
+            }
+            w!(self, "{}", HtmlEscape(code));
+            if let Some(label) = label {
+                if switches.retain_labels() {
+                    w!(self, " ({})", HtmlEscape(label));
                 }
+                w!(self, "</span>");
             }
-            false
-        });
-
-        w!(
-            self,
-            r#"
-<div id="footnotes">
-    <style>
-    .footdef p {{
-    display:inline;
-    }}
-    </style>
-"#
-        );
+            w!(self, "\n");
+        }
+    }
 
-        if heading_query.is_none() {
+    /// Writes the TODO keyword, priority, title and tags of a headline or inline task
+    fn headline_title(
+        &mut self,
+        parser: &Parser,
+        keyword: Option<TodoKeyword>,
+        priority: &Option<Priority>,
+        title: &Option<(&str, Vec<NodeID>)>,
+        node_id: NodeID,
+    ) {
+        if let Some(keyword) = keyword.filter(|_| self.settings.todo_keywords) {
+            let state = match keyword.state {
+                TodoState::Active => "todo",
+                TodoState::Done => "done",
+            };
             w!(
                 self,
-                r#"    <h2 class="footnotes">Footnotes</h2>
-"#
+                r#"<span class="{state} {0}">{0}</span> "#,
+                HtmlEscape(keyword.name)
             );
         }
+        if let Some(priority) = priority.as_ref().filter(|_| self.settings.priority) {
+            let priority = match priority {
+                Priority::A => "A".into(),
+                Priority::B => "B".into(),
+                Priority::C => "C".into(),
+                Priority::Num(num) => num.to_string(),
+            };
+            w!(self, r#"<span class="priority">[{priority}]</span> "#);
+        }
+        if let Some(title) = title {
+            for id in &title.1 {
+                self.export_rec(id, parser);
+            }
+        }
+        if !self.settings.tags {
+            return;
+        }
+        let tags = if self.settings.inherited_tags {
+            parser.tags(node_id)
+        } else {
+            let own_tags = match &parser.pool[node_id].obj {
+                Expr::Heading(heading) => &heading.tags,
+                Expr::InlineTask(task) => &task.tags,
+                _ => &None,
+            };
+            // inherited tags are only referenced, so a headline can have none of its own
+            own_tags
+                .iter()
+                .flatten()
+                .rev()
+                .filter_map(|tag| match tag {
+                    Tag::Raw(val) => Some(*val),
+                    Tag::Loc(_) => None,
+                })
+                .collect()
+        };
+        if !tags.is_empty() {
+            w!(self, r#" <span class="tag">"#);
+            for tag in tags {
+                w!(self, r#"<span class="{0}">{0}</span>"#, HtmlEscape(tag));
+            }
+            w!(self, "</span>");
+        }
+    }
+
+    /// Writes the section number of a headline, if it has one
+    fn section_number(&mut self, parser: &Parser, node_id: NodeID, level: usize) {
+        // the subtree of an :UNNUMBERED: headline isn't numbered either
+        if parser
+            .property_with(node_id, "UNNUMBERED", true)
+            .is_some_and(|val| val != "nil")
+        {
+            return;
+        }
+        let Some(max_level) = self
+            .settings
+            .section_numbers
+            .max_level(self.settings.headline_levels)
+        else {
+            return;
+        };
+        if level > max_level {
+            return;
+        }
 
+        // missing parent levels are numbered 0
+        self.section_numbers.resize(level, 0);
+        self.section_numbers[level - 1] += 1;
+        let number = self
+            .section_numbers
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
         w!(
             self,
-            r#"    <div id="text-footnotes">
-"#
+            r#"<span class="section-number-{level}">{number}</span> "#
         );
+    }
 
-        // FIXME
-        // lifetime shenanigans making me do this.. can't figure em out
-        // would like to self.footnotes.iter(), but we get multiple
-        // immutable borrows, so self.footnotes.copied.iter(), but still no go
-        let man = self.footnotes.clone();
-        for (mut pos, def_id) in man.iter().enumerate() {
-            pos += 1;
-            w!(
-                self,
-                r##"
-
-<div class="footdef">
-<sup>
+    /// Writes emphasized text within `tag`, or between its markers if emphasis isn't exported
+    fn emphasis(&mut self, parser: &Parser, children: &[NodeID], tag: &str, marker: char) {
+        if self.settings.emphasis {
+            w!(self, "<{tag}>");
+        } else {
+            w!(self, "{marker}");
+        }
+        for id in children {
+            self.export_rec(id, parser);
+        }
+        if self.settings.emphasis {
+            w!(self, "</{tag}>");
+        } else {
+            w!(self, "{marker}");
+        }
+    }
+
+    fn scripts_enabled(&self, script: &PlainOrRec) -> bool {
+        match self.settings.sub_superscripts {
+            SubSuperscripts::Yes => true,
+            SubSuperscripts::Braces => matches!(script, PlainOrRec::Rec(_)),
+            SubSuperscripts::No => false,
+        }
+    }
+
+    fn class(&mut self, name: &str) {
+        w!(self, r#" class="{name}""#);
+    }
+
+    fn attr(&mut self, key: &str, val: &str) {
+        w!(self, r#" {}="{}""#, key, HtmlEscape(val));
+    }
+
+    /// Writes a timestamp, each end of a range gets its own `<time>`
+    fn timestamp(&mut self, inner: &Timestamp) {
+        w!(self, r#"<span class="timestamp-wrapper">"#);
+        match inner.end {
+            // a same day range is a single timestamp, as in ox-html
+            Some(_) if inner.same_day_end_time().is_some() => self.time(&inner.start, inner),
+            Some(end) => {
+                self.time(
+                    &inner.start,
+                    &Timestamp {
+                        end: None,
+                        ..inner.clone()
+                    },
+                );
+                w!(self, "&#x2013;");
+                self.time(
+                    &end,
+                    &Timestamp {
+                        active: inner.active,
+                        start: end,
+                        end: None,
+                        range_kind: inner.range_kind,
+                        repeater: None,
+                        warning: None,
+                    },
+                );
+            }
+            None => self.time(&inner.start, inner),
+        }
+        w!(self, "</span>");
+    }
+
+    /// Writes a `<time>` element, `stamp` is the displayed text
+    fn time(&mut self, date_time: &DateTime, stamp: &Timestamp) {
+        let date = date_time.date;
+        w!(
+            self,
+            r#"<time class="timestamp" datetime="{:04}-{:02}-{:02}"#,
+            date.year,
+            date.month,
+            date.day
+        );
+        if let Some(time) = date_time.time {
+            w!(self, "T{time}");
+        }
+        w!(self, r#"">{}</time>"#, HtmlEscape(stamp.to_string()));
+    }
+
+    /// Writes the `#+TITLE:` as a heading, like ox-html.
+    fn exp_title(&mut self, parser: &Parser) {
+        let Some(title) = parser.keywords.get("title").filter(|_| self.settings.title) else {
+            return;
+        };
+        w!(self, r#"<h1 class="title">"#);
+        self.exp_keyword_value(title);
+        w!(self, "</h1>\n");
+    }
+
+    /// Writes the author, date and time of export after the contents, like ox-html.
+    fn exp_postamble(&mut self, parser: &Parser) {
+        let author = parser
+            .keywords
+            .get("author")
+            .filter(|_| self.settings.author);
+        let date = parser.keywords.get("date").filter(|_| self.settings.date);
+        if author.is_none() && date.is_none() && !self.settings.timestamp {
+            return;
+        }
+
+        w!(
+            self,
+            r#"<div id="postamble" class="status">
+"#
+        );
+        if let Some(author) = author {
+            w!(self, r#"<p class="author">Author: "#);
+            self.exp_keyword_value(author);
+            w!(self, "</p>\n");
+        }
+        if let Some(date) = date {
+            w!(self, r#"<p class="date">Date: "#);
+            self.exp_keyword_value(date);
+            w!(self, "</p>\n");
+        }
+        if self.settings.timestamp {
+            w!(
+                self,
+                r#"<p class="date">Created: {}</p>
+"#,
+                export_time()
+            );
+        }
+        w!(self, "</div>\n");
+    }
+
+    /// Exports the objects in the value of a keyword, such as `#+TITLE:`.
+    fn exp_keyword_value(&mut self, val: &str) {
+        if let Err(mut err_vec) = Html::export_macro_buf(val, self, self.config_opts().clone()) {
+            self.errors().append(&mut err_vec);
+        }
+    }
+
+    fn exp_bibliography(&mut self) {
+        if self.cited.is_empty() {
+            return;
+        }
+        if let Some(processor) = &self.cite_processor {
+            let bibliography = processor.bibliography(&self.cited);
+            w!(self, "{bibliography}");
+        }
+    }
+
+    fn exp_footnotes(&mut self, parser: &Parser) {
+        if self.footnotes.is_empty() {
+            return;
+        }
+
+        // get last heading, and check if its title is Footnotes,
+        // if so, destroy it
+        let heading_query = parser.pool.iter().rev().find(|node| {
+            if let Expr::Heading(head) = &node.obj {
+                if let Some(title) = &head.title {
+                    if title.0 == "Footnotes\n" {
+                        return true;
+                    }
+                }
+            }
+            false
+        });
+
+        w!(
+            self,
+            r#"
+<div id="footnotes">
+    <style>
+    .footdef p {{
+    display:inline;
+    }}
+    </style>
+"#
+        );
+
+        if heading_query.is_none() {
+            w!(
+                self,
+                r#"    <h2 class="footnotes">Footnotes</h2>
+"#
+            );
+        }
+
+        w!(
+            self,
+            r#"    <div id="text-footnotes">
+"#
+        );
+
+        // FIXME
+        // lifetime shenanigans making me do this.. can't figure em out
+        // would like to self.footnotes.iter(), but we get multiple
+        // immutable borrows, so self.footnotes.copied.iter(), but still no go
+        let man = self.footnotes.clone();
+        for (mut pos, def_id) in man.iter().enumerate() {
+            pos += 1;
+            w!(
+                self,
+                r##"
+
+<div class="footdef">
+<sup>
     <a id="fn.{pos}" href= "#fnr.{pos}" role="doc-backlink">{pos}</a>
 </sup>
 "##
@@ -991,6 +1758,7 @@ impl fmt::Write for Html<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::settings::{Depth, ExportOverrides};
     use pretty_assertions::assert_eq;
 
     fn html_export(input: &str) -> String {
@@ -1443,4 +2211,732 @@ here
             "<h1 id=\"yeah\">yeah</h1>\n<p>hello</p>\n<p>hi</p>\n<p>content</p>\n<p>here</p>\n"
         );
     }
+
+    #[test]
+    fn timestamps() {
+        let a = html_export("<2023-08-16 Wed 10:00 -3d>\n");
+        assert_eq!(
+            a,
+            r#"<p><span class="timestamp-wrapper"><time class="timestamp" datetime="2023-08-16T10:00">&lt;2023-08-16 Wed 10:00 -3d&gt;</time></span></p>
+"#
+        );
+
+        let b = html_export("[2023-08-16 Wed]--[2023-08-18 Fri]\n");
+        assert_eq!(
+            b,
+            r#"<p><span class="timestamp-wrapper"><time class="timestamp" datetime="2023-08-16">[2023-08-16 Wed]</time>&#x2013;<time class="timestamp" datetime="2023-08-18">[2023-08-18 Fri]</time></span></p>
+"#
+        );
+
+        let c = html_export("<2023-08-16 Wed 10:00-12:00>\n");
+        assert_eq!(
+            c,
+            r#"<p><span class="timestamp-wrapper"><time class="timestamp" datetime="2023-08-16T10:00">&lt;2023-08-16 Wed 10:00-12:00&gt;</time></span></p>
+"#
+        );
+    }
+
+    #[test]
+    fn planning() {
+        let a = html_export(
+            r"* TODO task
+SCHEDULED: <2023-08-16 Wed> DEADLINE: <2023-08-20 Sun>
+",
+        );
+        assert_eq!(
+            a,
+            r#"<h1 id="task"><span class="todo TODO">TODO</span> task</h1>
+<p class="planning"><span class="timestamp-kwd">DEADLINE:</span> <span class="timestamp-wrapper"><time class="timestamp" datetime="2023-08-20">&lt;2023-08-20 Sun&gt;</time></span> <span class="timestamp-kwd">SCHEDULED:</span> <span class="timestamp-wrapper"><time class="timestamp" datetime="2023-08-16">&lt;2023-08-16 Wed&gt;</time></span></p>
+"#
+        );
+    }
+
+    #[test]
+    fn clock() {
+        let a = html_export("CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30\n");
+        assert_eq!(
+            a,
+            r#"<p class="clock"><span class="timestamp-kwd">CLOCK:</span> <span class="timestamp-wrapper"><time class="timestamp" datetime="2023-08-16T09:00">[2023-08-16 Wed 09:00]</time>&#x2013;<time class="timestamp" datetime="2023-08-16T10:30">[2023-08-16 Wed 10:30]</time></span> <span class="timestamp">(1:30)</span></p>
+"#
+        );
+    }
+
+    #[test]
+    fn radio_targets() {
+        let a = html_export(
+            r"Read about <<<Radio Waves>>> first.
+
+Then radio waves again.
+",
+        );
+        assert_eq!(
+            a,
+            r##"<p>Read about <span id="radio-waves">Radio Waves</span> first.</p>
+<p>Then <a href="#radio-waves">radio waves</a> again.</p>
+"##
+        );
+    }
+
+    #[test]
+    fn citations() {
+        let mut processor = crate::BasicProcessor::default();
+        processor.insert(
+            "doe2020",
+            crate::BibEntry {
+                authors: vec![crate::Name {
+                    family: "Doe".into(),
+                    given: Some("Jane".into()),
+                }],
+                year: Some("2020".into()),
+                title: Some("Title".into()),
+                container: None,
+            },
+        );
+        let mut conf = ConfigOptions::default();
+        conf.set_citation_processor(processor);
+
+        let a = Html::export(
+            r"As shown [cite:@doe2020 p. 3].
+
+#+print_bibliography:
+
+after
+",
+            conf,
+        )
+        .unwrap();
+        assert_eq!(
+            a,
+            r##"<p>As shown (<a href="#bib-doe2020">Doe, 2020</a>, p. 3).</p>
+<div id="bibliography">
+<h2>Bibliography</h2>
+<ul>
+<li id="bib-doe2020">Doe, J. (2020). <i>Title</i>.</li>
+</ul>
+</div>
+<p>after</p>
+"##
+        );
+    }
+
+    #[test]
+    fn citation_bibliography_file() {
+        let dir = std::env::temp_dir().join("org-rust-citation-test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("refs.bib"),
+            "@book{roe, author = {Roe, Richard}, title = {Book}, year = 2019}",
+        )
+        .unwrap();
+
+        let a = Html::export(
+            r"#+bibliography: refs.bib
+#+cite_export: basic numeric
+See [cite:@roe].
+
+#+print_bibliography:
+",
+            ConfigOptions::new(Some(dir.join("doc.org"))),
+        )
+        .unwrap();
+        assert_eq!(
+            a,
+            r##"<p>See [<a href="#bib-roe">1</a>].</p>
+<div id="bibliography">
+<h2>Bibliography</h2>
+<ol>
+<li id="bib-roe">Roe, R. (2019). <i>Book</i>.</li>
+</ol>
+</div>
+"##
+        );
+
+        // without a #+print_bibliography: keyword, only the citation is exported
+        let unprinted = Html::export(
+            "#+bibliography: refs.bib\nSee [cite:@roe].\n",
+            ConfigOptions::new(Some(dir.join("doc.org"))),
+        )
+        .unwrap();
+        assert_eq!(
+            unprinted,
+            r##"<p>See (<a href="#bib-roe">Roe, 2019</a>).</p>
+"##
+        );
+
+        let missing = Html::export(
+            "#+bibliography: missing.bib\n[cite:@roe]\n",
+            ConfigOptions::new(Some(dir.join("doc.org"))),
+        );
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn statistics_cookies() {
+        let a = html_export(
+            r"- [-] list [/]
+  - [X] one
+  - [ ] two
+",
+        );
+        assert_eq!(
+            a,
+            r#"<ul>
+<li class="trans"><p>list <code>[1/2]</code></p>
+<ul>
+<li class="on"><p>one</p>
+</li>
+<li class="off"><p>two</p>
+</li>
+</ul>
+</li>
+</ul>
+"#
+        );
+    }
+
+    #[test]
+    fn low_level_headings() {
+        let a = html_export(
+            r"#+options: H:2
+* one
+** two
+*** three
+text
+*** three again
+**** four
+",
+        );
+        assert_eq!(
+            a,
+            r#"<h1 id="one">one</h1>
+<h2 id="two">two</h2>
+<ul class="org-ul">
+<li id="three">three<br>
+<p>text</p>
+</li>
+<li id="three-again">three again<br>
+<ul class="org-ul">
+<li id="four">four<br>
+</li>
+</ul>
+</li>
+</ul>
+"#
+        );
+    }
+
+    #[test]
+    fn deep_headings() {
+        let a = html_export(
+            r"#+options: H:8
+******* seven
+",
+        );
+        assert_eq!(
+            a,
+            r#"<div class="outline-7" id="seven" role="heading" aria-level="7">seven</div>
+"#
+        );
+    }
+
+    #[test]
+    fn inline_tasks() {
+        let a = html_export(
+            r"*************** TODO call back :phone:
+*************** DONE with contents
+the contents
+*************** END
+",
+        );
+        assert_eq!(
+            a,
+            r#"<div class="inlinetask" id="call-back">
+<b><span class="todo TODO">TODO</span> call back <span class="tag"><span class="phone">phone</span></span></b>
+</div>
+<div class="inlinetask" id="with-contents">
+<b><span class="done DONE">DONE</span> with contents</b><br>
+<p>the contents</p>
+</div>
+"#
+        );
+    }
+
+    #[test]
+    fn fixed_width() {
+        let a = html_export(
+            r": <b>not bold</b>
+:
+:   & indented
+",
+        );
+        assert_eq!(
+            a,
+            r#"<pre class="example">
+&lt;b&gt;not bold&lt;/b&gt;
+
+  &amp; indented
+</pre>
+"#
+        );
+    }
+
+    #[test]
+    fn babel_calls() {
+        let a = html_export(
+            r#"#+call: answer()
+
+#+RESULTS:
+: 42
+
+#+call: answer() :exports both
+
+#+RESULTS:
+: 42
+
+#+call: answer() :exports none
+
+#+RESULTS:
+: 42
+
+#+call: answer() :var x=" :exports none"
+
+#+RESULTS:
+: 43
+"#,
+        );
+        assert_eq!(
+            a,
+            r#"<pre class="example">
+42
+</pre>
+<pre class="example">
+#+call: answer() :exports both
+</pre>
+<pre class="example">
+42
+</pre>
+<pre class="example">
+43
+</pre>
+"#
+        );
+
+        let a = html_export(
+            r"#+PROPERTY: header-args :exports code
+#+call: answer()
+
+#+RESULTS:
+: 42
+",
+        );
+        assert_eq!(
+            a,
+            r#"<pre class="example">
+#+call: answer()
+</pre>
+"#
+        );
+    }
+
+    #[test]
+    fn inline_babel_calls() {
+        let a = html_export("call_answer() is {{{results(=42=)}}}, call_answer()[:exports code]\n");
+        assert_eq!(
+            a,
+            r#"<p> is <code>42</code>, <code>call_answer()[:exports code]</code></p>
+"#
+        );
+    }
+
+    #[test]
+    fn diary_sexps() {
+        let a = html_export(
+            r"%%(diary-anniversary 10 31 1948) Arthur's birthday
+every <%%(diary-float t 4 2)>
+",
+        );
+        assert_eq!(
+            a,
+            r#"<p>every <span class="timestamp-wrapper"><span class="timestamp">&lt;%%(diary-float t 4 2)&gt;</span></span></p>
+"#
+        );
+    }
+
+    #[test]
+    fn coderefs() {
+        let a = html_export(
+            r"see [[(entry)]] and [[(loop)][the loop]]
+
+#+begin_src rust -n 9
+fn main() { (ref:entry)
+    loop {} (ref:loop)
+}
+#+end_src
+
+#+begin_example +n -r
+next (ref:next)
+#+end_example
+
+[[(next)]]
+",
+        );
+        assert_eq!(
+            a,
+            r##"<p>see <a href="#coderef-entry" class="coderef">entry</a> and <a href="#coderef-loop" class="coderef">the loop</a></p>
+<pre><code class="src" class="src-rust">
+<span id="coderef-entry" class="coderef-off"><span class="linenr"> 9: </span>fn main() { (entry)</span>
+<span id="coderef-loop" class="coderef-off"><span class="linenr">10: </span>    loop {} (loop)</span>
+<span class="linenr">11: </span>}
+</pre></code>
+<pre class="example">
+<span id="coderef-next" class="coderef-off"><span class="linenr">12: </span>next</span>
+</pre>
+<p><a href="#coderef-next" class="coderef">12</a></p>
+"##
+        );
+
+        // labels no block has are broken links
+        let a = html_export(
+            r"#+OPTIONS: broken-links:mark
+see [[(missing)]]
+
+#+begin_src rust
+fn main() {} (ref:entry)
+#+end_src
+",
+        );
+        assert!(
+            a.starts_with("<p>see [BROKEN LINK: (missing)]</p>\n"),
+            "{a}"
+        );
+        let errors = Html::export_tree(
+            &parse_org("#+OPTIONS: broken-links:nil\nsee [[(missing)]]\n"),
+            &mut String::new(),
+            ConfigOptions::default(),
+        );
+        assert!(errors.is_err());
+    }
+
+    #[test]
+    fn src_exports() {
+        let a = html_export(
+            r"#+PROPERTY: header-args :exports both
+#+begin_src python
+print(1)
+#+end_src
+
+#+RESULTS:
+: 1
+
+#+begin_src python :exports results
+print(2)
+#+end_src
+
+#+RESULTS:
+: 2
+
+#+begin_src python :exports code
+print(3)
+#+end_src
+
+#+RESULTS:
+: 3
+
+#+begin_src python :exports none
+print(4)
+#+end_src
+",
+        );
+        assert_eq!(
+            a,
+            r#"<pre><code class="src" class="src-python">
+print(1)
+</pre></code>
+<pre class="example">
+1
+</pre>
+<pre class="example">
+2
+</pre>
+<pre><code class="src" class="src-python">
+print(3)
+</pre></code>
+"#
+        );
+    }
+
+    #[test]
+    fn src_noweb() {
+        let a = html_export(
+            r"#+NAME: greet
+#+begin_src sh
+echo hi
+#+end_src
+
+#+begin_src sh :noweb yes :exports code
+<<greet>> && <<greet>>
+#+end_src
+
+#+begin_src sh :noweb tangle
+<<greet>>
+#+end_src
+",
+        );
+        assert_eq!(
+            a,
+            r#"<pre><code class="src" class="src-sh" id="greet">
+echo hi
+</pre></code>
+<pre><code class="src" class="src-sh">
+echo hi &amp;&amp; echo hi
+</pre></code>
+<pre><code class="src" class="src-sh">
+&lt;&lt;greet&gt;&gt;
+</pre></code>
+"#
+        );
+    }
+
+    #[test]
+    fn verse_block() {
+        let a = html_export(
+            r"#+begin_verse
+Great clouds overhead
+   Tiny /black/ birds \\
+   - rise & fall
+
+\alpha
+#+end_verse
+",
+        );
+        assert_eq!(
+            a,
+            r#"<p class="verse">
+Great clouds overhead<br>
+&#xa0;&#xa0;&#xa0;Tiny <em>black</em> birds <br>
+&#xa0;&#xa0;&#xa0;- rise &amp; fall<br>
+<br>
+α<br>
+</p>
+"#
+        );
+    }
+
+    #[test]
+    fn table_groups() {
+        let a = html_export(
+            r"| / | <    | >   |
+| ! | x    | y   |
+|   | item | qty |
+|---+------+-----|
+|   | <r>  |     |
+|   | a    | 1   |
+|---+------+-----|
+|   | *b*  | 2   |
+",
+        );
+        assert_eq!(
+            a,
+            r#"<table>
+<colgroup>
+<col class="org-right">
+<col class="org-right">
+</colgroup>
+<thead>
+<tr>
+<th scope="col" class="org-right"> item</th>
+<th scope="col" class="org-right"> qty</th>
+</tr>
+</thead>
+<tbody>
+<tr>
+<td class="org-right"> a</td>
+<td class="org-right"> 1</td>
+</tr>
+</tbody>
+<tbody>
+<tr>
+<td class="org-right"> <b>b</b></td>
+<td class="org-right"> 2</td>
+</tr>
+</tbody>
+</table>
+"#
+        );
+    }
+
+    #[test]
+    fn table_column_cookies() {
+        let a = html_export(
+            r"| / | <l> | <r10> | <> | <c> |
+|   | a   | 1     | x  | y   |
+",
+        );
+        assert_eq!(
+            a,
+            r#"<table>
+<colgroup>
+<col class="org-left">
+<col class="org-right">
+</colgroup>
+<colgroup>
+<col class="org-left">
+</colgroup>
+<colgroup>
+<col class="org-center">
+</colgroup>
+<tbody>
+<tr>
+<td class="org-left"> a</td>
+<td class="org-right"> 1</td>
+<td class="org-left"> x</td>
+<td class="org-center"> y</td>
+</tr>
+</tbody>
+</table>
+"#
+        );
+    }
+
+    #[test]
+    fn export_options() {
+        let a = html_export(
+            r"#+OPTIONS: num:t todo:nil pri:t tags:nil p:nil *:nil e:nil \n:t
+* TODO [#A] one :tag:
+DEADLINE: <2023-08-20 Sun>
+*bold* \alpha
+next line
+** two
+:PROPERTIES:
+:UNNUMBERED: t
+:END:
+*** three
+* four
+",
+        );
+        assert_eq!(
+            a,
+            r#"<h1 id="one"><span class="section-number-1">1</span> <span class="priority">[A]</span> one</h1>
+<p>*bold* \alpha<br>
+next line</p>
+<h2 id="two">two</h2>
+<h3 id="three">three</h3>
+<h1 id="four"><span class="section-number-1">2</span> four</h1>
+"#
+        );
+    }
+
+    #[test]
+    fn export_options_text() {
+        let a = html_export(
+            r#"#+OPTIONS: ':t -:t ^:{} tex:verbatim f:nil stat:nil broken-links:mark
+"quoted" it's -- a---b... a_b \(x\) [1/2] [fn:1] [[nowhere]]
+
+[fn:1] note
+"#,
+        );
+        assert_eq!(
+            a,
+            "<p>\u{201c}quoted\u{201d} it\u{2019}s \u{2013} a\u{2014}b\u{2026} a_b \\(x\\)   [BROKEN LINK: nowhere]</p>\n"
+        );
+    }
+
+    #[test]
+    fn export_properties() {
+        let a = html_export(
+            r#"#+OPTIONS: prop:("ID") d:(not "LOGBOOK")
+* one
+:PROPERTIES:
+:ID: abc
+:OTHER: val
+:END:
+:LOGBOOK:
+hidden
+:END:
+:NOTES:
+shown
+:END:
+"#,
+        );
+        assert_eq!(
+            a,
+            r#"<h1 id="one">one</h1>
+<pre class="example">
+ID: abc
+</pre>
+<p>shown</p>
+"#
+        );
+    }
+
+    #[test]
+    fn document_metadata() {
+        let a = html_export(
+            r"#+title: A /short/ story
+#+author: me
+#+date: today
+#+OPTIONS: title:t author:t date:t
+text
+",
+        );
+        assert_eq!(
+            a,
+            r#"<h1 class="title">A <em>short</em> story</h1>
+<p>text</p>
+<div id="postamble" class="status">
+<p class="author">Author: me</p>
+<p class="date">Date: today</p>
+</div>
+"#
+        );
+
+        let a = html_export(
+            r"#+title: Story
+#+author: me
+#+OPTIONS: title:nil author:nil date:t timestamp:t
+text
+",
+        );
+        assert!(a.starts_with("<p>text</p>\n<div id=\"postamble\" class=\"status\">\n"));
+        assert!(a.contains(r#"<p class="date">Created: "#));
+        assert!(!a.contains("me"));
+    }
+
+    #[test]
+    fn export_overrides() {
+        let mut conf = ConfigOptions::default();
+        conf.set_export_overrides(ExportOverrides {
+            toc: Some(Depth::Off),
+            ..Default::default()
+        });
+        let a = Html::export("#+OPTIONS: toc:t num:t\n* one\n", conf).unwrap();
+        assert_eq!(
+            a,
+            r#"<h1 id="one"><span class="section-number-1">1</span> one</h1>
+"#
+        );
+    }
+
+    #[test]
+    fn inherited_tags() {
+        let mut conf = ConfigOptions::default();
+        conf.set_export_settings(ExportSettings {
+            inherited_tags: true,
+            ..Default::default()
+        });
+        let a = Html::export(
+            r"#+FILETAGS: :project:
+* one :work:
+** two :home:
+",
+            conf,
+        )
+        .unwrap();
+        assert_eq!(
+            a,
+            r#"<h1 id="one">one <span class="tag"><span class="project">project</span><span class="work">work</span></span></h1>
+<h2 id="two">two <span class="tag"><span class="project">project</span><span class="work">work</span><span class="home">home</span></span></h2>
+"#
+        );
+    }
 }
@@ -151,16 +151,10 @@ impl<'a> InclParams<'a> {
                 ":minlevel" => {
                     if let Some(not_kwarg) = params.next_if(is_not_kwarg) {
                         let temp = not_kwarg.parse::<usize>()?;
-                        // FIXME: generalize headline level parsing with heading.rs in the parser
-                        min_level = match temp {
-                            1 => Some(HeadingLevel::One),
-                            2 => Some(HeadingLevel::Two),
-                            3 => Some(HeadingLevel::Three),
-                            4 => Some(HeadingLevel::Four),
-                            5 => Some(HeadingLevel::Five),
-                            6 => Some(HeadingLevel::Six),
-                            _ => Err(IncludeError::InvalidMinLevel { received: temp })?,
-                        };
+                        min_level = Some(
+                            HeadingLevel::new(temp)
+                                .ok_or(IncludeError::InvalidMinLevel { received: temp })?,
+                        );
                     }
                 }
                 _ => Err(IncludeError::UnsupportedKwarg(kwarg.into()))?,
@@ -293,7 +287,7 @@ pub enum IncludeError {
     UnsupportedKwarg(String),
     #[error("lines provided are not in base 10: {0}")]
     LinesError(#[from] ParseIntError),
-    #[error("expected a minlevel of 1-6, received: {received}")]
+    #[error("expected a minlevel of at least 1, received: {received}")]
     InvalidMinLevel { received: usize },
     #[error("minlevel was not a number: {0}")]
     NotStringMinlevel(String),
@@ -31,13 +31,23 @@
 //! assert_eq!(org_str, "* Hello Org!\n");
 //! ```
 
+mod cite;
+mod dynblock;
+mod execute;
 mod html;
 mod include;
 mod org;
 mod org_macros;
+mod settings;
 mod types;
 mod utils;
 
+pub use cite::{BasicProcessor, BasicStyle, BibEntry, CitationProcessor, CiteError, Name};
+pub use dynblock::{ClockTable, ColumnView, DynamicBlockGenerator, DynamicBlockRegistry};
+pub use execute::{ExecuteError, LanguageExecutor, Python, Shell, SourceExecutor, Value};
 pub use html::Html;
 pub use org::Org;
+pub use settings::{
+    BrokenLinks, Depth, ExportOverrides, ExportSettings, LatexExport, Selection, SubSuperscripts,
+};
 pub use types::{ConfigOptions, ExportError, Exporter};
@@ -4,24 +4,31 @@ use std::fmt::Write;
 
 use crate::include::include_handle;
 use crate::org_macros::macro_handle;
+use crate::settings::{BrokenLinks, ExportSettings, LatexExport};
 use crate::types::{ConfigOptions, Exporter, ExporterInner, LogicErrorKind};
+use crate::utils::{export_time, is_broken_link, node_name};
 use crate::ExportError;
-use org_parser::element::{Block, BulletKind, CounterKind, Priority, TableRow, Tag};
+use org_parser::element::{
+    Affiliated, Block, BulletKind, CounterKind, Priority, TableRow, Tag, TodoKeyword,
+};
 use org_parser::object::{LatexFragment, PlainOrRec};
 
-use org_parser::{parse_org, Expr, NodeID, Parser};
+use org_parser::{parse_org, recalculate, Expr, NodeID, Parser};
 
 /// Org-Mode Content Exporter
 ///
 /// This backend might seem a little unncessary, but it's fairly useful as a sanity check
 /// for the parser.
 ///
-/// It also carries out some modifications to the source such as prettifying tables and resolving
-/// macros
+/// It also carries out some modifications to the source such as prettifying tables, recalculating
+/// their `#+TBLFM:` formulas and resolving macros
 pub struct Org<'buf> {
     buf: &'buf mut dyn fmt::Write,
     indentation_level: u8,
     on_newline: bool,
+    // within a verse block, where line breaks are kept
+    in_verse: bool,
+    settings: ExportSettings,
     conf: ConfigOptions,
     errors: Vec<ExportError>,
 }
@@ -51,16 +58,24 @@ impl<'buf> Exporter<'buf> for Org<'buf> {
     fn export_tree<'inp, T: fmt::Write>(
         parsed: &Parser,
         buf: &'buf mut T,
-        conf: ConfigOptions,
+        mut conf: ConfigOptions,
     ) -> core::result::Result<(), Vec<ExportError>> {
+        let settings =
+            ExportSettings::resolve(parsed, conf.export_settings(), conf.export_overrides());
+        conf.set_export_settings(settings.clone());
         let mut obj = Org {
             buf,
             indentation_level: 0,
             on_newline: false,
+            in_verse: false,
+            settings,
             conf,
             errors: Vec::new(),
         };
 
+        if obj.settings.timestamp {
+            w!(obj, "# Created {}\n", export_time());
+        }
         obj.export_rec(&parsed.pool.root_id(), &parsed);
 
         if obj.errors().is_empty() {
@@ -83,6 +98,8 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
             buf,
             indentation_level: 0,
             on_newline: false,
+            in_verse: false,
+            settings: conf.export_settings().clone(),
             conf: ConfigOptions::default(),
             errors: Vec::new(),
         };
@@ -104,70 +121,68 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                 }
             }
             Expr::Heading(inner) => {
-                for _ in 0..inner.heading_level.into() {
-                    w!(self, "*");
-                }
-                w!(self, " ");
-
-                if let Some(keyword) = inner.keyword {
-                    w!(self, "{keyword} ");
-                }
-
-                if let Some(priority) = &inner.priority {
-                    w!(self, "[#");
-                    match priority {
-                        Priority::A => w!(self, "A"),
-                        Priority::B => w!(self, "B"),
-                        Priority::C => w!(self, "C"),
-                        Priority::Num(num) => w!(self, "{num}"),
-                    };
-                    w!(self, "] ");
-                }
+                self.export_headline(
+                    parser,
+                    inner.heading_level.into(),
+                    inner.keyword,
+                    &inner.priority,
+                    &inner.title,
+                    &inner.tags,
+                );
 
-                if let Some(title) = &inner.title {
-                    for id in &title.1 {
-                        self.export_rec(id, parser);
+                if let Some(planning) = inner.planning.as_ref().filter(|_| self.settings.planning) {
+                    let mut planning_line = Vec::new();
+                    if let Some(deadline) = &planning.deadline {
+                        planning_line.push(format!("DEADLINE: {deadline}"));
+                    }
+                    if let Some(scheduled) = &planning.scheduled {
+                        planning_line.push(format!("SCHEDULED: {scheduled}"));
+                    }
+                    if let Some(closed) = &planning.closed {
+                        planning_line.push(format!("CLOSED: {closed}"));
                     }
+                    w!(self, "{}\n", planning_line.join(" "));
                 }
 
-                // fn tag_search<T: Write>(loc: NodeID, pool: &NodePool, self: &mut T) -> Result {
-                //     if let Expr::Heading(loc) = &pool[loc].obj {
-                //         if let Some(sub_tags) = loc.tags.as_ref() {
-                //             for thang in sub_tags.iter().rev() {
-                //                 match thang {
-                //                     Tag::Raw(val) => w!(self, ":{val}"),
-                //                     Tag::Loc(id, parser) => {
-                //                         tag_search(*id, pool, self)?;
-                //                     }
-                //                 }
-                //             }
-                //         }
-                //     }
-                //     Ok(())
-                // }
-
-                if let Some(tags) = &inner.tags {
-                    let mut valid_out = String::new();
-                    for tag in tags.iter().rev() {
-                        match tag {
-                            Tag::Raw(val) => w!(&mut valid_out, ":{val}"),
-                            Tag::Loc(_id) => {
-                                // do nothing with it
-                            }
+                if let Some(properties) = &inner.properties {
+                    let mut exported = properties
+                        .iter()
+                        .filter(|(key, _)| self.settings.properties.includes(key))
+                        .collect::<Vec<_>>();
+                    if !exported.is_empty() {
+                        exported.sort_by_key(|(key, _)| *key);
+                        w!(self, ":PROPERTIES:\n");
+                        for (key, val) in exported {
+                            w!(self, ":{key}: {val}\n");
                         }
-                    }
-                    // handles the case where a parent heading has no tags
-                    if !valid_out.is_empty() {
-                        w!(self, " {valid_out}:");
+                        w!(self, ":END:\n");
                     }
                 }
 
-                w!(self, "\n");
+                if let Some(children) = &inner.children {
+                    for id in children {
+                        self.export_rec(id, parser);
+                    }
+                }
+            }
+            Expr::InlineTask(inner) => {
+                self.export_headline(
+                    parser,
+                    inner.heading_level.into(),
+                    inner.keyword,
+                    &inner.priority,
+                    &inner.title,
+                    &inner.tags,
+                );
 
                 if let Some(children) = &inner.children {
                     for id in children {
                         self.export_rec(id, parser);
                     }
+                    for _ in 0..inner.heading_level.into() {
+                        w!(self, "*");
+                    }
+                    w!(self, " END\n");
                 }
             }
             Expr::Block(inner) => {
@@ -230,10 +245,12 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                         w!(self, "#+end_comment\n");
                     }
                     Block::Example {
+                        switches,
                         parameters,
                         contents,
+                        ..
                     } => {
-                        w!(self, "#+begin_example");
+                        w!(self, "#+begin_example{switches}");
                         for (key, val) in parameters {
                             w!(self, " :{} {}", key, val);
                         }
@@ -255,16 +272,33 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                     }
                     Block::Src {
                         language,
-                        parameters,
+                        switches,
+                        header,
                         contents,
+                        results,
+                        results_hash,
+                        ..
                     } => {
                         let lang = if let Some(word) = language { word } else { "" };
-                        w!(self, "#+begin_src {}", lang);
-                        for (key, val) in parameters {
-                            w!(self, " :{} {}", key, val);
+                        w!(self, "#+begin_src {}{}", lang, switches);
+                        // the raw header keeps repeated keys, like multiple :var
+                        if !header.is_empty() {
+                            w!(self, " {header}");
                         }
                         w!(self, "\n{contents}");
                         w!(self, "#+end_src\n");
+                        if let Some(results) = results {
+                            w!(self, "\n#+RESULTS");
+                            if let Some(hash) = results_hash {
+                                w!(self, "[{hash}]");
+                            }
+                            w!(self, ":");
+                            if let Some(name) = node_name(parser, *node_id) {
+                                w!(self, " {name}");
+                            }
+                            w!(self, "\n");
+                            self.export_rec(results, parser);
+                        }
                     }
                     Block::Verse {
                         parameters,
@@ -274,12 +308,30 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                         for (key, val) in parameters {
                             w!(self, " :{} {}", key, val);
                         }
-                        w!(self, "\n{contents}");
+                        w!(self, "\n");
+                        self.in_verse = true;
+                        for id in contents {
+                            self.export_rec(id, parser);
+                        }
+                        self.in_verse = false;
                         w!(self, "#+end_verse\n");
                     }
                 }
             }
             Expr::RegularLink(inner) => {
+                if self.settings.broken_links != BrokenLinks::Ignore
+                    && is_broken_link(parser, &inner.path.obj)
+                {
+                    let path = inner.path.to_str(parser.source);
+                    if self.settings.broken_links == BrokenLinks::Mark {
+                        w!(self, "[BROKEN LINK: {path}]");
+                        return;
+                    }
+                    self.errors.push(ExportError::LogicError {
+                        span: node.start..node.end,
+                        source: LogicErrorKind::BrokenLink(path.into()),
+                    });
+                }
                 w!(self, "[");
                 w!(self, "[{}]", inner.path.obj);
                 if let Some(children) = &inner.description {
@@ -327,14 +379,28 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                 }
                 w!(self, "_");
             }
+            // elements like blocks and drawers don't include the newline ending their
+            // last line, which is parsed as a blank line
+            Expr::BlankLine
+                if self.settings.write_back
+                    && parser.source[..node.start]
+                        .chars()
+                        .next_back()
+                        .is_some_and(|chr| chr != '\n') => {}
             Expr::BlankLine => {
                 w!(self, "\n");
             }
             Expr::SoftBreak => {
-                w!(self, " ");
+                if self.in_verse || self.settings.preserve_breaks {
+                    w!(self, "\n");
+                } else {
+                    w!(self, " ");
+                }
             }
             Expr::LineBreak => {
+                // the newline ending the line belongs to the line break
                 w!(self, r#"\\"#);
+                w!(self, "\n");
             }
             Expr::HorizontalRule => {
                 w!(self, "-----\n");
@@ -348,6 +414,29 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
             Expr::Code(inner) => {
                 w!(self, "~{}~", inner.0);
             }
+            Expr::FixedWidth(inner) => {
+                for line in &inner.0 {
+                    if line.is_empty() {
+                        w!(self, ":\n");
+                    } else {
+                        w!(self, ": {line}\n");
+                    }
+                }
+            }
+            Expr::BabelCall(inner) => {
+                w!(self, "{inner}\n");
+                if let Some(results) = inner.results {
+                    w!(self, "#+RESULTS");
+                    if let Some(hash) = inner.results_hash {
+                        w!(self, "[{hash}]");
+                    }
+                    w!(self, ":\n");
+                    self.export_rec(&results, parser);
+                }
+            }
+            Expr::InlineBabelCall(inner) => {
+                w!(self, "{inner}");
+            }
             Expr::Comment(inner) => {
                 w!(self, "# {}\n", inner.0);
             }
@@ -358,6 +447,9 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                 }
                 w!(self, "{{{}}}", inner.body);
             }
+            Expr::Keyword(inner) if self.settings.write_back => {
+                w!(self, "#+{}: {}\n", inner.key, inner.val);
+            }
             Expr::Keyword(inner) => {
                 if inner.key.to_ascii_lowercase() == "include" {
                     if let Err(e) = include_handle(inner.val, self) {
@@ -365,10 +457,21 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                             span: node.start..node.end,
                             source: LogicErrorKind::Include(e),
                         });
-                        return;
                     }
+                    return;
+                }
+                let enabled = match inner.key.to_ascii_lowercase().as_str() {
+                    "title" => self.settings.title,
+                    "author" => self.settings.author,
+                    "date" => self.settings.date,
+                    _ => false,
+                };
+                if enabled {
+                    w!(self, "#+{}: {}\n", inner.key, inner.val);
                 }
             }
+            Expr::LatexEnv(_) | Expr::LatexFragment(_)
+                if self.settings.latex == LatexExport::No => {}
             Expr::LatexEnv(inner) => {
                 w!(
                     self,
@@ -441,7 +544,17 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                 w!(self, "[[{}:{}]]", inner.protocol, inner.path);
             }
             Expr::Entity(inner) => {
-                w!(self, "{}", inner.mapped_item);
+                if self.settings.entities {
+                    w!(self, "{}", inner.mapped_item);
+                } else {
+                    w!(self, "{}", &parser.source[node.start..node.end]);
+                }
+            }
+            Expr::Table(_) if self.settings.write_back => {
+                let table = parser.source[node.start..node.end].trim_end();
+                for line in table.lines() {
+                    w!(self, "{}\n", line.trim_start());
+                }
             }
             Expr::Table(inner) => {
                 let mut build_vec: Vec<Vec<String>> = Vec::with_capacity(inner.rows);
@@ -468,6 +581,8 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                                             buf: &mut cell_buf,
                                             indentation_level: self.indentation_level,
                                             on_newline: self.on_newline,
+                                            in_verse: false,
+                                            settings: self.settings.clone(),
                                             conf: self.conf.clone(),
                                             errors: Vec::new(),
                                         };
@@ -485,6 +600,18 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                     }
                 }
 
+                // tables whose formulas can't be parsed are written unchanged, as they
+                // may use Calc or elisp features that aren't supported, and formulas
+                // that can't be evaluated are skipped
+                if let Ok(formulas) = &inner.formulas {
+                    let mut rows = build_vec
+                        .iter()
+                        .map(|row| (!row.is_empty()).then(|| row.clone()))
+                        .collect::<Vec<_>>();
+                    let _ = recalculate(formulas, &mut rows);
+                    build_vec = rows.into_iter().map(Option::unwrap_or_default).collect();
+                }
+
                 // we use .get throughout because hrule rows are empty
                 // and empty cells don't appear in the table, but we still have
                 // to represent them
@@ -541,6 +668,10 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                     }
                     w!(self, "\n");
                 }
+
+                for line in &inner.tblfm {
+                    w!(self, "#+TBLFM: {line}\n");
+                }
             }
 
             Expr::TableRow(_) => {
@@ -583,6 +714,26 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
             Expr::Target(inner) => {
                 w!(self, "<<{}>>", inner.0);
             }
+            Expr::RadioTarget(inner) => {
+                w!(self, "<<<{}>>>", inner.0);
+            }
+            Expr::RadioLink(inner) => {
+                w!(self, "{}", inner.contents);
+            }
+            Expr::Citation(inner) => {
+                w!(self, "{inner}");
+            }
+            Expr::StatisticsCookie(inner) if self.settings.write_back => {
+                w!(self, "{inner}");
+            }
+            Expr::StatisticsCookie(_) if !self.settings.statistics => {}
+            Expr::StatisticsCookie(inner) => {
+                let cookie = parser.statistics(*node_id).unwrap_or(*inner);
+                w!(self, "{cookie}");
+            }
+            Expr::Macro(_) if self.settings.write_back => {
+                w!(self, "{}", &parser.source[node.start..node.end]);
+            }
             Expr::Macro(macro_call) => {
                 let macro_contents = match macro_handle(parser, macro_call, self.config_opts()) {
                     Ok(contents) => contents,
@@ -610,19 +761,37 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                 }
             }
             Expr::Drawer(inner) => {
+                if !self.settings.drawers.includes(inner.name) {
+                    return;
+                }
                 w!(self, ":{}:\n", inner.name);
                 for id in &inner.children {
                     self.export_rec(id, parser);
                 }
                 w!(self, ":end:\n");
             }
+            Expr::DynamicBlock(inner) => {
+                w!(self, "#+BEGIN: {}", inner.name);
+                for (key, val) in &inner.parameters {
+                    w!(self, " :{} {}", key, val);
+                }
+                w!(self, "\n");
+                for id in &inner.children {
+                    self.export_rec(id, parser);
+                }
+                w!(self, "#+END:\n");
+            }
             Expr::ExportSnippet(inner) => {
                 if inner.backend == "org" {
                     w!(self, "{}", inner.contents);
                 }
             }
+            Expr::Affiliated(_) | Expr::MacroDef(_) if self.settings.write_back => {
+                w!(self, "{}", &parser.source[node.start..node.end]);
+            }
             Expr::Affiliated(_) => {}
             Expr::MacroDef(_) => {}
+            Expr::FootnoteDef(_) | Expr::FootnoteRef(_) if !self.settings.footnotes => {}
             Expr::FootnoteDef(inner) => {
                 w!(self, r"[fn:{}] ", inner.label);
 
@@ -643,6 +812,22 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
                 }
                 w!(self, "]");
             }
+            Expr::Timestamp(inner) => {
+                w!(self, "{inner}");
+            }
+            Expr::SexpTimestamp(inner) => {
+                w!(self, "{inner}");
+            }
+            Expr::DiarySexp(inner) => {
+                w!(self, "%%{}", inner.sexp);
+                if let Some(description) = inner.description {
+                    w!(self, " {description}");
+                }
+                w!(self, "\n");
+            }
+            Expr::Clock(inner) => {
+                w!(self, "{inner}\n");
+            }
         }
     }
 
@@ -659,6 +844,79 @@ impl<'buf> ExporterInner<'buf> for Org<'buf> {
     }
 }
 
+impl Org<'_> {
+    /// Writes the stars, keyword, priority, title and tags of a headline.
+    fn export_headline(
+        &mut self,
+        parser: &Parser,
+        heading_level: usize,
+        keyword: Option<TodoKeyword>,
+        priority: &Option<Priority>,
+        title: &Option<(&str, Vec<NodeID>)>,
+        tags: &Option<Vec<Tag>>,
+    ) {
+        for _ in 0..heading_level {
+            w!(self, "*");
+        }
+        w!(self, " ");
+
+        if let Some(keyword) = keyword.filter(|_| self.settings.todo_keywords) {
+            w!(self, "{} ", keyword.name);
+        }
+
+        if let Some(priority) = priority.as_ref().filter(|_| self.settings.priority) {
+            w!(self, "[#");
+            match priority {
+                Priority::A => w!(self, "A"),
+                Priority::B => w!(self, "B"),
+                Priority::C => w!(self, "C"),
+                Priority::Num(num) => w!(self, "{num}"),
+            };
+            w!(self, "] ");
+        }
+
+        if let Some(title) = title {
+            for id in &title.1 {
+                self.export_rec(id, parser);
+            }
+        }
+
+        // fn tag_search<T: Write>(loc: NodeID, pool: &NodePool, self: &mut T) -> Result {
+        //     if let Expr::Heading(loc) = &pool[loc].obj {
+        //         if let Some(sub_tags) = loc.tags.as_ref() {
+        //             for thang in sub_tags.iter().rev() {
+        //                 match thang {
+        //                     Tag::Raw(val) => w!(self, ":{val}"),
+        //                     Tag::Loc(id, parser) => {
+        //                         tag_search(*id, pool, self)?;
+        //                     }
+        //                 }
+        //             }
+        //         }
+        //     }
+        //     Ok(())
+        // }
+
+        if let Some(tags) = tags.as_ref().filter(|_| self.settings.tags) {
+            let mut valid_out = String::new();
+            for tag in tags.iter().rev() {
+                match tag {
+                    Tag::Raw(val) => w!(&mut valid_out, ":{val}"),
+                    Tag::Loc(_id) => {
+                        // do nothing with it
+                    }
+                }
+            }
+            // handles the case where a parent heading has no tags
+            if !valid_out.is_empty() {
+                w!(self, " {valid_out}:");
+            }
+        }
+
+        w!(self, "\n");
+    }
+}
+
 impl<'buf> fmt::Write for Org<'buf> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         if self.indentation_level > 0 {
@@ -687,6 +945,7 @@ impl<'buf> fmt::Write for Org<'buf> {
 mod tests {
     use super::*;
 
+    use crate::settings::ExportOverrides;
     use pretty_assertions::assert_eq;
 
     fn org_export(input: &str) -> String {
@@ -1091,6 +1350,54 @@ more content here this is a pargraph
         );
     }
 
+    #[test]
+    fn table_formulas() {
+        let a = org_export(
+            r"
+|item|qty|price|total|
+|-
+|rent|1|1200|0|
+|food|4|80.5||
+|-
+|total||||
+#+TBLFM: $4=$2*$3::@>$4=vsum(@I..@II);%.2f
+",
+        );
+
+        assert_eq!(
+            a,
+            r"
+| item  | qty | price | total   |
+|-------+-----+-------+---------+
+| rent  | 1   | 1200  | 1200    |
+| food  | 4   | 80.5  | 322     |
+|-------+-----+-------+---------+
+| total |     |       | 1522.00 |
+#+TBLFM: $4=$2*$3::@>$4=vsum(@I..@II);%.2f
+"
+        );
+
+        // formulas that can't be evaluated leave the table as it is
+        for formula in [
+            "$1=$1/0",
+            "$1=remote(a, @1$1)",
+            "$1='(+ 1 2)",
+            "@1$1=vsum(@I..@III)",
+        ] {
+            assert_eq!(
+                org_export(&format!("|1|\n#+TBLFM: {formula}\n")),
+                format!("| 1 |\n#+TBLFM: {formula}\n")
+            );
+        }
+
+        // while the others are still applied
+        let a = org_export("|1||\n|2||\n|3||\n#+TBLFM: @4$2=1::$2=$1*2\n");
+        assert_eq!(
+            a,
+            "| 1 | 2 |\n| 2 | 4 |\n| 3 | 6 |\n#+TBLFM: @4$2=1::$2=$1*2\n"
+        );
+    }
+
     #[test]
     fn proper_list_indent() {
         let a = org_export(
@@ -1309,4 +1616,302 @@ four
 "
         );
     }
+
+    #[test]
+    fn timestamps() {
+        let a = org_export("<2023-08-16 Wed 10:00-12:00 +1w> and [2023-08-16]--[2023-08-18 Fri]\n");
+
+        assert_eq!(
+            a,
+            "<2023-08-16 Wed 10:00-12:00 +1w> and [2023-08-16]--[2023-08-18 Fri]\n"
+        );
+
+        let b = org_export("<2023-08-16 Wed 10:00>--<2023-08-16 Wed 12:00>\n");
+        assert_eq!(b, "<2023-08-16 Wed 10:00>--<2023-08-16 Wed 12:00>\n");
+    }
+
+    #[test]
+    fn planning() {
+        let a = org_export(
+            r"* DONE task
+   CLOSED: [2023-08-16 Wed 10:22]  SCHEDULED: <2023-08-16 Wed>
+body
+",
+        );
+        assert_eq!(
+            a,
+            r"* DONE task
+SCHEDULED: <2023-08-16 Wed> CLOSED: [2023-08-16 Wed 10:22]
+body
+"
+        );
+    }
+
+    #[test]
+    fn logbook() {
+        let input = r#"* DONE task
+:LOGBOOK:
+- State "DONE"       from "TODO"       [2023-08-16 Wed 10:22]
+CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30
+:END:
+"#;
+        assert_eq!(
+            org_export(input),
+            r#"* DONE task
+:LOGBOOK:
+- State "DONE"       from "TODO"       [2023-08-16 Wed 10:22]
+CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30
+:end:
+
+"#
+        );
+    }
+
+    #[test]
+    fn radio_targets() {
+        let input = "Read about <<<radio waves>>> first.\n\nThen /Radio Waves/ again.\n";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn citations() {
+        let input = "See [cite/t:Global; see @doe p. 3; @roe].\n";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn statistics_cookies() {
+        let input = r"
+* parent [50%]
+** DONE one
+** DONE two
+";
+        assert_eq!(
+            org_export(input),
+            r"
+* parent [100%]
+** DONE one
+** DONE two
+"
+        );
+    }
+
+    #[test]
+    fn deep_headings() {
+        let input = "* one\n******** eight\n";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn inline_tasks() {
+        let input = r"* parent
+*************** TODO [#A] task :tag:
+contents
+*************** END
+*************** single line
+after
+";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn fixed_width() {
+        let input = r"
+- item
+  : inside a list
+  :
+: outside
+";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn babel_calls() {
+        let input = r"#+call: plot[:session none](data=tbl) :exports both
+#+RESULTS[3f2a]:
+: 42
+
+see call_square(x=4)[:results raw]
+
+#+call: plot[:session](x=1)[:results raw]
+";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn dynamic_block() {
+        let input = r"#+BEGIN: clocktable :scope file
+stale contents
+#+END:
+after
+";
+        assert_eq!(
+            org_export(input),
+            r"#+BEGIN: clocktable :scope file
+stale contents
+#+END:
+after
+"
+        );
+
+        // parameters keep the order they were written in
+        let input = "#+BEGIN: columnview :maxlevel 2 :id global :format \"%ITEM %TODO\" :indent t\n#+END:\n";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn diary_sexps() {
+        let input = r"%%(diary-anniversary 10 31 1948) Arthur's birthday
+%%(diary-block 12 30 2023 1 2 2024)
+every <%%(diary-float t 4 2)>
+";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn block_switches() {
+        let a = org_export(
+            r#"#+begin_src rust -n 10 -k -l "[%s]"
+fn main() {} [main]
+#+end_src
+"#,
+        );
+        assert!(a.starts_with(
+            r#"#+begin_src rust -n 10 -k -l "[%s]"
+fn main() {} [main]
+#+end_src
+"#
+        ));
+
+        let b = org_export("#+begin_example +n\ntext\n#+end_example\n");
+        assert!(b.starts_with("#+begin_example +n\ntext\n#+end_example\n"));
+    }
+
+    #[test]
+    fn src_header_and_results() {
+        let input = r"#+begin_src python :var x=1 :var y=2 :results output
+print(x + y)
+#+end_src
+
+#+RESULTS:
+: 3
+";
+        assert_eq!(org_export(input), input);
+    }
+
+    #[test]
+    fn verse_block() {
+        let a = org_export(
+            r"#+begin_verse
+Great clouds overhead
+   Tiny /black/ birds
+
+rise and fall
+#+end_verse
+",
+        );
+        assert!(a.starts_with(
+            r"#+begin_verse
+Great clouds overhead
+   Tiny /black/ birds
+
+rise and fall
+#+end_verse
+"
+        ));
+    }
+
+    #[test]
+    fn verse_line_breaks() {
+        let input = r"#+begin_verse
+a \\
+Tiny birds \\
+   rise
+#+end_verse
+";
+        assert!(org_export(input).starts_with(input));
+        assert_eq!(org_export("one \\\\\ntwo\n"), "one \\\\\ntwo\n");
+    }
+
+    #[test]
+    fn export_options() {
+        let a = org_export(
+            r"#+title: Document
+#+author: me
+#+OPTIONS: title:t author:nil todo:nil tags:nil p:nil prop:t f:nil \n:t
+* TODO one :tag:
+SCHEDULED: <2023-08-16 Wed>
+:PROPERTIES:
+:ID: abc
+:END:
+first[fn:1]
+second
+
+[fn:1] note
+",
+        );
+        assert_eq!(
+            a,
+            r"#+title: Document
+* one
+:PROPERTIES:
+:ID: abc
+:END:
+
+first
+second
+
+"
+        );
+
+        let a = org_export("#+OPTIONS: timestamp:t\ntext\n");
+        let (created, rest) = a.split_once('\n').unwrap();
+        assert!(created.starts_with("# Created "));
+        assert_eq!(created.len(), "# Created 2023-08-16 Wed 10:00".len());
+        assert_eq!(rest, "text\n");
+    }
+
+    #[test]
+    fn broken_links() {
+        let a = org_export(
+            r"#+OPTIONS: broken-links:mark
+* Second heading
+[[*Second heading]] [[Second]] [[*Second  heading]]
+",
+        );
+        assert_eq!(
+            a,
+            r"* Second heading
+[[*Second heading]] [BROKEN LINK: Second] [[*Second  heading]]
+"
+        );
+    }
+
+    #[test]
+    fn write_back() {
+        let input = r"#+title: Document
+#+MACRO: greet hello $1
+#+INCLUDE: ./missing.org
+* Tasks [0/1]
+** TODO one
+#+NAME: tbl
+#+CAPTION: Numbers
+#+ATTR_HTML: :border 1
+|a|b|
+|-
+| 1 |   |
+#+TBLFM: $2=$1*2
+
+{{{greet(world)}}}
+#+begin_src sh
+echo 1
+#+end_src
+";
+        let mut conf = ConfigOptions::default();
+        conf.set_export_overrides(ExportOverrides {
+            write_back: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(Org::export(input, conf).unwrap(), input);
+    }
 }
@@ -42,6 +42,8 @@ pub(crate) fn macro_handle<'a>(
                 unreachable!("darn")
             }
         }
+        // holds the results of an inline babel call
+        "results" => Ok(Cow::Owned(macro_call.args.join(","))),
         _ => macro_execute(parser, macro_call),
     }
 }
@@ -131,7 +133,7 @@ pub(crate) fn keyword_file<'a>(
     parsed
         .keywords
         .get(kw)
-        .map(|&f| f.to_owned().into())
+        .map(|f| f.to_owned().into())
         .ok_or(MacroError::Keyword { kw: kw.into() })
 }
 
@@ -0,0 +1,442 @@
+//! Export settings, set with `#+OPTIONS:` lines.
+
+use org_parser::Parser;
+
+/// What is exported, as set by the options of `#+OPTIONS:` lines.
+///
+/// Settings start from [`ConfigOptions::export_settings`](crate::ConfigOptions::export_settings),
+/// which the document's own `#+OPTIONS:` then adjust. Last,
+/// [`ConfigOptions::export_overrides`](crate::ConfigOptions::export_overrides) replace
+/// whatever the document chose, like the `ext-plist` of Emacs.
+///
+/// Unlike Emacs, nothing is numbered, listed, converted or timestamped by default: `num:`,
+/// `toc:`, `-:` and `timestamp:` are off, as are `title:`, `author:` and `date:`, while
+/// priorities, planning lines and every drawer are kept.
+/// Options that only change how text is displayed, such as `':` or `num:`,
+/// don't apply to the [`Org`](crate::Org) backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSettings {
+    /// `H:`, headlines deeper than this are exported as lists
+    pub headline_levels: usize,
+    /// `num:`, the headlines given section numbers
+    pub section_numbers: Depth,
+    /// `toc:`, the headlines listed in the table of contents
+    pub toc: Depth,
+    /// `^:`, whether `_` and `^` make subscripts and superscripts
+    pub sub_superscripts: SubSuperscripts,
+    /// `*:`, emphasized text such as `*bold*` and `/italic/`
+    pub emphasis: bool,
+    /// `f:`, footnote references and definitions
+    pub footnotes: bool,
+    /// `todo:`, the TODO keywords of headlines
+    pub todo_keywords: bool,
+    /// `pri:`, the priority cookies of headlines
+    pub priority: bool,
+    /// `tags:`, the tags of headlines
+    pub tags: bool,
+    /// Whether headlines also show the tags they inherit, including `#+FILETAGS:`.
+    ///
+    /// There is no `#+OPTIONS:` for this, it's only set through
+    /// [`ConfigOptions`](crate::ConfigOptions).
+    pub inherited_tags: bool,
+    /// `p:`, planning lines such as `DEADLINE:`
+    pub planning: bool,
+    /// `d:`, the drawers that are exported
+    pub drawers: Selection,
+    /// `prop:`, the headline properties that are exported
+    pub properties: Selection,
+    /// `tex:`, LaTeX fragments and environments
+    pub latex: LatexExport,
+    /// `broken-links:`, what happens to links that lead nowhere
+    pub broken_links: BrokenLinks,
+    /// `e:`, entities such as `\alpha`, which are otherwise kept as written
+    pub entities: bool,
+    /// `':`, turning quotes into curly quotes
+    pub smart_quotes: bool,
+    /// `-:`, turning `\-`, `--`, `---` and `...` into soft hyphens, dashes and ellipses
+    pub special_strings: bool,
+    /// `\n:`, keeping line breaks within paragraphs
+    pub preserve_breaks: bool,
+    /// `author:`, `date:` and `title:`, whether the document's metadata is included.
+    ///
+    /// `Org` writes the keywords, `Html` starts with the title and ends with the
+    /// author and date.
+    pub author: bool,
+    pub date: bool,
+    pub title: bool,
+    /// `timestamp:`, whether the time of export, in UTC, is included
+    pub timestamp: bool,
+    /// `stat:`, statistics cookies such as `[1/3]`
+    pub statistics: bool,
+    /// Whether the [`Org`](crate::Org) backend writes the document back as it was written,
+    /// rather than exporting it: every keyword, including `#+MACRO:` and `#+INCLUDE:`, and
+    /// `#+NAME:`, `#+CAPTION:` and `#+ATTR_*` lines are kept, while macros, statistics
+    /// cookies and tables are written as they are, without evaluating their formulas.
+    ///
+    /// There is no `#+OPTIONS:` for this, it's only set through
+    /// [`ConfigOptions`](crate::ConfigOptions).
+    pub write_back: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            headline_levels: 6,
+            section_numbers: Depth::Off,
+            toc: Depth::Off,
+            sub_superscripts: SubSuperscripts::Yes,
+            emphasis: true,
+            footnotes: true,
+            todo_keywords: true,
+            priority: true,
+            tags: true,
+            inherited_tags: false,
+            planning: true,
+            drawers: Selection::All,
+            properties: Selection::None,
+            latex: LatexExport::Yes,
+            broken_links: BrokenLinks::Ignore,
+            entities: true,
+            smart_quotes: false,
+            special_strings: false,
+            preserve_breaks: false,
+            author: false,
+            date: false,
+            title: false,
+            timestamp: false,
+            statistics: true,
+            write_back: false,
+        }
+    }
+}
+
+/// How deep into the outline an option such as `num:` or `toc:` reaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Depth {
+    /// `nil`
+    Off,
+    /// `t`, as deep as headlines are exported
+    All,
+    /// a number, the deepest headline level included
+    UpTo(usize),
+}
+
+impl Depth {
+    /// The deepest level included, which never exceeds `headline_levels`.
+    pub fn max_level(self, headline_levels: usize) -> Option<usize> {
+        match self {
+            Depth::Off => None,
+            Depth::All => Some(headline_levels),
+            Depth::UpTo(level) => Some(level.min(headline_levels)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubSuperscripts {
+    /// `t`
+    Yes,
+    /// `{}`, only when the script is surrounded by braces: `a_{b}`
+    Braces,
+    /// `nil`
+    No,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LatexExport {
+    /// `t`, converted by the backend
+    Yes,
+    /// `verbatim`, kept as written
+    Verbatim,
+    /// `nil`, left out
+    No,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrokenLinks {
+    /// `nil`, a broken link is an error
+    Error,
+    /// `t`, broken links are exported as is
+    Ignore,
+    /// `mark`, broken links are replaced with `[BROKEN LINK: path]`
+    Mark,
+}
+
+/// The drawers or properties chosen by `d:` and `prop:`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    /// `t`
+    All,
+    /// `nil`
+    None,
+    /// `("NAME" ...)`
+    Only(Vec<String>),
+    /// `(not "NAME" ...)`
+    Except(Vec<String>),
+}
+
+impl Selection {
+    /// Whether `name` is selected, ignoring case.
+    pub fn includes(&self, name: &str) -> bool {
+        let listed = |names: &[String]| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+        match self {
+            Selection::All => true,
+            Selection::None => false,
+            Selection::Only(names) => listed(names),
+            Selection::Except(names) => !listed(names),
+        }
+    }
+
+    fn parse(val: &str) -> Self {
+        let Some(list) = val.strip_prefix('(').and_then(|val| val.strip_suffix(')')) else {
+            return if val == "nil" {
+                Selection::None
+            } else {
+                Selection::All
+            };
+        };
+        let (negated, list) = match list.trim_start().strip_prefix("not ") {
+            Some(rest) => (true, rest),
+            None => (false, list),
+        };
+        let names = list
+            .split_whitespace()
+            .map(|name| name.trim_matches('"').to_owned())
+            .collect();
+        if negated {
+            Selection::Except(names)
+        } else {
+            Selection::Only(names)
+        }
+    }
+}
+
+/// Settings that win over a document's `#+OPTIONS:`.
+///
+/// Only the fields that are set replace the document's choice.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportOverrides {
+    pub headline_levels: Option<usize>,
+    pub section_numbers: Option<Depth>,
+    pub toc: Option<Depth>,
+    pub sub_superscripts: Option<SubSuperscripts>,
+    pub emphasis: Option<bool>,
+    pub footnotes: Option<bool>,
+    pub todo_keywords: Option<bool>,
+    pub priority: Option<bool>,
+    pub tags: Option<bool>,
+    pub inherited_tags: Option<bool>,
+    pub planning: Option<bool>,
+    pub drawers: Option<Selection>,
+    pub properties: Option<Selection>,
+    pub latex: Option<LatexExport>,
+    pub broken_links: Option<BrokenLinks>,
+    pub entities: Option<bool>,
+    pub smart_quotes: Option<bool>,
+    pub special_strings: Option<bool>,
+    pub preserve_breaks: Option<bool>,
+    pub author: Option<bool>,
+    pub date: Option<bool>,
+    pub title: Option<bool>,
+    pub timestamp: Option<bool>,
+    pub statistics: Option<bool>,
+    pub write_back: Option<bool>,
+}
+
+impl ExportOverrides {
+    /// Replaces the fields of `settings` that are set.
+    pub fn apply_to(&self, settings: &mut ExportSettings) {
+        macro_rules! replace {
+            ($($field:ident),*) => {
+                $(if let Some(val) = &self.$field {
+                    settings.$field = val.clone();
+                })*
+            };
+        }
+        replace!(
+            headline_levels,
+            section_numbers,
+            toc,
+            sub_superscripts,
+            emphasis,
+            footnotes,
+            todo_keywords,
+            priority,
+            tags,
+            inherited_tags,
+            planning,
+            drawers,
+            properties,
+            latex,
+            broken_links,
+            entities,
+            smart_quotes,
+            special_strings,
+            preserve_breaks,
+            author,
+            date,
+            title,
+            timestamp,
+            statistics,
+            write_back
+        );
+    }
+}
+
+impl ExportSettings {
+    /// The settings of a document: `base` adjusted by its `#+OPTIONS:`, then `overrides`.
+    pub fn resolve(parser: &Parser, base: &ExportSettings, overrides: &ExportOverrides) -> Self {
+        let mut ret = base.clone();
+        for options in parser.keywords.get_all("options") {
+            ret.apply(options);
+        }
+        overrides.apply_to(&mut ret);
+        ret
+    }
+
+    /// Applies the value of an `#+OPTIONS:` line, such as `toc:nil H:2`.
+    ///
+    /// Unknown options and invalid values are ignored.
+    pub fn apply(&mut self, options: &str) {
+        let flag = |val: &str| val != "nil";
+        let depth = |val: &str| match val {
+            "nil" => Depth::Off,
+            _ => val.parse().map_or(Depth::All, Depth::UpTo),
+        };
+
+        for option in split_options(options) {
+            let Some((key, val)) = option.split_once(':') else {
+                continue;
+            };
+            match key {
+                "H" => {
+                    if let Ok(levels) = val.parse() {
+                        self.headline_levels = levels;
+                    }
+                }
+                "num" => self.section_numbers = depth(val),
+                "toc" => self.toc = depth(val),
+                "^" => {
+                    self.sub_superscripts = match val {
+                        "nil" => SubSuperscripts::No,
+                        "{}" => SubSuperscripts::Braces,
+                        _ => SubSuperscripts::Yes,
+                    }
+                }
+                "*" => self.emphasis = flag(val),
+                "f" => self.footnotes = flag(val),
+                "todo" => self.todo_keywords = flag(val),
+                "pri" => self.priority = flag(val),
+                // `not-in-toc` only concerns the table of contents, which lists no tags
+                "tags" => self.tags = flag(val),
+                "p" => self.planning = flag(val),
+                "d" => self.drawers = Selection::parse(val),
+                "prop" => self.properties = Selection::parse(val),
+                "tex" => {
+                    self.latex = match val {
+                        "nil" => LatexExport::No,
+                        "verbatim" => LatexExport::Verbatim,
+                        _ => LatexExport::Yes,
+                    }
+                }
+                "broken-links" => {
+                    self.broken_links = match val {
+                        "nil" => BrokenLinks::Error,
+                        "mark" => BrokenLinks::Mark,
+                        _ => BrokenLinks::Ignore,
+                    }
+                }
+                "e" => self.entities = flag(val),
+                "'" => self.smart_quotes = flag(val),
+                "-" => self.special_strings = flag(val),
+                "\\n" => self.preserve_breaks = flag(val),
+                "author" => self.author = flag(val),
+                "date" => self.date = flag(val),
+                "title" => self.title = flag(val),
+                "timestamp" => self.timestamp = flag(val),
+                "stat" => self.statistics = flag(val),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Splits options on whitespace, keeping lists such as `d:(not "LOGBOOK")` together.
+fn split_options(options: &str) -> Vec<&str> {
+    let mut ret = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut start = None;
+    for (i, chr) in options.char_indices() {
+        match chr {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            chr if chr.is_whitespace() && depth == 0 && !in_string => {
+                if let Some(begin) = start.take() {
+                    ret.push(&options[begin..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(begin) = start {
+        ret.push(&options[begin..]);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn apply_options() {
+        let mut settings = ExportSettings::default();
+        settings
+            .apply(r#"H:2 num:1 toc:t ^:{} *:nil \n:t d:(not "LOGBOOK") prop:("ID") tex:verbatim"#);
+        settings.apply("broken-links:mark todo:nil unknown:t stat:nil");
+
+        assert_eq!(
+            settings,
+            ExportSettings {
+                headline_levels: 2,
+                section_numbers: Depth::UpTo(1),
+                toc: Depth::All,
+                sub_superscripts: SubSuperscripts::Braces,
+                emphasis: false,
+                preserve_breaks: true,
+                drawers: Selection::Except(vec!["LOGBOOK".into()]),
+                properties: Selection::Only(vec!["ID".into()]),
+                latex: LatexExport::Verbatim,
+                broken_links: BrokenLinks::Mark,
+                todo_keywords: false,
+                statistics: false,
+                ..Default::default()
+            }
+        );
+        assert_eq!(settings.toc.max_level(settings.headline_levels), Some(2));
+        assert!(settings.drawers.includes("notes"));
+        assert!(!settings.drawers.includes("logbook"));
+    }
+
+    #[test]
+    fn overrides_win() {
+        let parser = org_parser::parse_org("#+OPTIONS: toc:t H:2\n");
+        let base = ExportSettings {
+            headline_levels: 4,
+            ..Default::default()
+        };
+        let overrides = ExportOverrides {
+            toc: Some(Depth::Off),
+            ..Default::default()
+        };
+
+        let settings = ExportSettings::resolve(&parser, &base, &overrides);
+        assert_eq!(settings.toc, Depth::Off);
+        assert_eq!(settings.headline_levels, 2);
+    }
+}
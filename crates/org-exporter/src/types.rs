@@ -1,14 +1,22 @@
 use core::fmt;
-use org_parser::{NodeID, Parser};
-use std::{ops::Range, path::PathBuf};
+use org_parser::{NodeID, NowebError, Parser};
+use std::{ops::Range, path::PathBuf, sync::Arc};
 use thiserror::Error;
 
+use crate::cite::{CitationProcessor, CiteError};
+use crate::settings::{ExportOverrides, ExportSettings};
 use crate::{include::IncludeError, org_macros::MacroError};
 
 #[derive(Debug, Clone, Default)]
 pub struct ConfigOptions {
     /// Used for evaluating relative paths in #+include: statements
     file_path: Option<PathBuf>,
+    /// Used for rendering citations, falls back to a [`BasicProcessor`](crate::BasicProcessor)
+    citation_processor: Option<Arc<dyn CitationProcessor + Send + Sync>>,
+    /// Settings that the document's `#+OPTIONS:` start from
+    export_settings: ExportSettings,
+    /// Settings that replace the document's `#+OPTIONS:`
+    export_overrides: ExportOverrides,
 }
 
 #[derive(Debug, Error)]
@@ -26,6 +34,12 @@ pub enum LogicErrorKind {
     Include(#[from] IncludeError),
     #[error("{0}")]
     Macro(#[from] MacroError),
+    #[error("{0}")]
+    Citation(#[from] CiteError),
+    #[error("{0}")]
+    Noweb(#[from] NowebError),
+    #[error("broken link: {0}")]
+    BrokenLink(String),
 }
 
 #[derive(Debug, Error)]
@@ -38,11 +52,37 @@ pub struct FileError {
 
 impl ConfigOptions {
     pub fn new(file_path: Option<PathBuf>) -> Self {
-        Self { file_path }
+        Self {
+            file_path,
+            citation_processor: None,
+            export_settings: ExportSettings::default(),
+            export_overrides: ExportOverrides::default(),
+        }
     }
     pub fn file_path(&self) -> &Option<PathBuf> {
         &self.file_path
     }
+    pub fn set_citation_processor(
+        &mut self,
+        processor: impl CitationProcessor + Send + Sync + 'static,
+    ) {
+        self.citation_processor = Some(Arc::new(processor));
+    }
+    pub fn citation_processor(&self) -> Option<&Arc<dyn CitationProcessor + Send + Sync>> {
+        self.citation_processor.as_ref()
+    }
+    pub fn set_export_settings(&mut self, settings: ExportSettings) {
+        self.export_settings = settings;
+    }
+    pub fn export_settings(&self) -> &ExportSettings {
+        &self.export_settings
+    }
+    pub fn set_export_overrides(&mut self, overrides: ExportOverrides) {
+        self.export_overrides = overrides;
+    }
+    pub fn export_overrides(&self) -> &ExportOverrides {
+        &self.export_overrides
+    }
 }
 
 /// Trait for exporter implementations
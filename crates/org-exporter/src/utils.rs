@@ -1,47 +1,81 @@
-use std::error::Error;
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use org_parser::{element::Heading, NodeID, Parser};
+use org_parser::element::{Block, Heading};
+use org_parser::object::{DateTime, PathReg};
+use org_parser::{Expr, NodeID, Parser};
 
 pub(crate) fn keyword_lookup<'a>(parser: &'a Parser, name: &'a str) -> Option<&'a str> {
-    parser.keywords.get(name).copied()
+    parser.keywords.get(name)
 }
 
-#[derive(Default, Debug, Clone, Copy)]
-pub(crate) struct Options {
-    toc: Option<u8>,
+/// Whether a link leads nowhere: a custom id no headline has, a coderef no block has,
+/// or a fuzzy link, such as `[[Some heading]]`, that matches no target.
+///
+/// Links that look like paths to files aren't checked.
+pub(crate) fn is_broken_link(parser: &Parser, path: &PathReg) -> bool {
+    match path {
+        PathReg::Unspecified(path) => {
+            let is_file = path.starts_with(['.', '/', '~'])
+                || path.contains('/')
+                || (path.contains('.') && !path.contains(char::is_whitespace));
+            // `[[*Heading]]` searches only headings, but any target of that name will do
+            let name = path.strip_prefix('*').unwrap_or(path);
+            !is_file
+                && !parser
+                    .targets
+                    .keys()
+                    .any(|target| target.split_whitespace().eq(name.split_whitespace()))
+        }
+        PathReg::CustomId(id) => !parser.pool.iter().any(|node| match &node.obj {
+            Expr::Heading(heading) => heading.properties.as_ref().is_some_and(|props| {
+                props
+                    .iter()
+                    .any(|(key, val)| key.eq_ignore_ascii_case("custom_id") && val == id)
+            }),
+            _ => false,
+        }),
+        PathReg::Coderef(label) => !parser.pool.iter().any(|node| match &node.obj {
+            Expr::Block(Block::Src { coderefs, .. } | Block::Example { coderefs, .. }) => {
+                coderefs.iter().any(|coderef| coderef.label == *label)
+            }
+            _ => false,
+        }),
+        _ => false,
+    }
 }
 
-impl Options {
-    pub(crate) fn new(toc: Option<u8>) -> Self {
-        Self { toc }
-    }
+/// The current time in UTC, written when `timestamp:` is set.
+pub(crate) fn export_time() -> DateTime<'static> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    DateTime::from_minutes((secs / 60) as i64)
+}
 
-    pub(crate) fn handle_opts(parser: &Parser) -> Result<Options, Box<dyn Error>> {
-        // TODO: make keywords not case sensitive
-        if let Some(options) = keyword_lookup(parser, "options") {
-            let ret = options.split_ascii_whitespace();
-            let mut toc = None;
-            for optpair in ret {
-                if let Some((opt, val)) = optpair.split_once(':') {
-                    match opt {
-                        "toc" => {
-                            toc = if val == "nil" {
-                                None
-                            } else if let Ok(num) = val.parse::<u8>() {
-                                Some(num)
-                            } else {
-                                Some(6)
-                            };
-                        }
-                        _ => {}
-                    }
-                }
-            }
+/// The `#+NAME:` of a node.
+pub(crate) fn node_name<'a>(parser: &Parser<'a>, node_id: NodeID) -> Option<&'a str> {
+    parser
+        .names
+        .iter()
+        .find(|(_, id)| **id == node_id)
+        .map(|(name, _)| *name)
+}
 
-            Ok(Options::new(toc))
-        } else {
-            Ok(Options::default())
+/// Collects the nodes under `node_id`, including itself, that match `pred`, in document order.
+pub(crate) fn collect_nodes(
+    parser: &Parser,
+    node_id: NodeID,
+    pred: &impl Fn(&Expr) -> bool,
+    nodes: &mut Vec<NodeID>,
+) {
+    let obj = &parser.pool[node_id].obj;
+    if pred(obj) {
+        nodes.push(node_id);
+    }
+    if let Some(children) = obj.children() {
+        for child_id in children {
+            collect_nodes(parser, *child_id, pred, nodes);
         }
     }
 }
@@ -49,31 +83,24 @@ impl Options {
 #[derive(Debug)]
 pub struct TocItem<'a> {
     pub name: &'a [NodeID],
-    pub level: u8,
+    pub level: usize,
     pub target: Rc<str>,
     pub children: Vec<TocItem<'a>>,
 }
 
-pub(crate) fn process_toc<'a>(
-    parser: &'a Parser,
-    opts: &Options,
-) -> Result<Vec<TocItem<'a>>, Box<dyn Error>> {
+pub(crate) fn process_toc<'a>(parser: &'a Parser, global_toc_level: usize) -> Vec<TocItem<'a>> {
     let mut tocs: Vec<TocItem> = Vec::new();
 
-    let Some(global_toc_level) = opts.toc else {
-        return Err("shruge".into());
-    };
-
     for sub_id in parser.pool[parser.pool.root_id()].obj.children().unwrap() {
         let node = &parser.pool[*sub_id];
         if let org_parser::Expr::Heading(heading) = &node.obj {
             if global_toc_level >= heading.heading_level.into() {
-                if let Some(properties) = &heading.properties {
-                    if let Some(val) = properties.get("unnumbered") {
-                        if val == "notoc" {
-                            continue;
-                        }
-                    }
+                if parser
+                    .property_with(*sub_id, "UNNUMBERED", false)
+                    .as_deref()
+                    == Some("notoc")
+                {
+                    continue;
                 }
                 tocs.push(handle_babies(
                     parser,
@@ -100,14 +127,14 @@ pub(crate) fn process_toc<'a>(
     //     }
     // }
 
-    Ok(tocs)
+    tocs
 }
 
 fn handle_babies<'a>(
     p: &'a Parser<'a>,
     heading: &'a Heading,
     target: Option<Rc<str>>,
-    global_toc_level: u8,
+    global_toc_level: usize,
 ) -> TocItem<'a> {
     let mut children_vec = Vec::new();
     if let Some(childs) = &heading.children {
@@ -115,12 +142,8 @@ fn handle_babies<'a>(
             let node = &p.pool[*child];
             if let org_parser::Expr::Heading(heading) = &node.obj {
                 if global_toc_level >= heading.heading_level.into() {
-                    if let Some(properties) = &heading.properties {
-                        if let Some(val) = properties.get("unnumbered") {
-                            if val == "notoc" {
-                                continue;
-                            }
-                        }
+                    if p.property_with(*child, "UNNUMBERED", false).as_deref() == Some("notoc") {
+                        continue;
                     }
                     children_vec.push(handle_babies(
                         p,
@@ -152,6 +175,7 @@ fn handle_babies<'a>(
 #[cfg(test)]
 mod tests {
     use crate::{ConfigOptions, Exporter, Html};
+    use std::error::Error;
 
     use super::*;
 
@@ -181,7 +205,8 @@ ul {
 
 "#,
             ConfigOptions::default(),
-        ).unwrap();
+        )
+        .unwrap();
         println!("{a}");
         Ok(())
     }
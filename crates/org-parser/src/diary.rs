@@ -0,0 +1,245 @@
+//! Evaluation of the common diary sexp functions.
+
+use crate::object::Date;
+
+/// Checks whether a diary sexp, such as `(diary-anniversary 10 31 1948)`, matches `date`.
+///
+/// Dates are written month first, following Emacs' default `calendar-date-style`.
+/// Supported functions are:
+///
+/// - `(diary-anniversary MONTH DAY [YEAR])`
+/// - `(diary-cyclic N MONTH DAY YEAR)`, every N days starting from the date
+/// - `(diary-block MONTH1 DAY1 YEAR1 MONTH2 DAY2 YEAR2)`, every day within the range
+/// - `(diary-float MONTH DAYNAME N [DAY])`, the Nth DAYNAME (0 is Sunday) of MONTH,
+///   counting from the end when N is negative. MONTH can be `t` or a list of months.
+///   N is between -5 and 5, as a month never has more of a given weekday.
+///
+/// Returns `None` for any other function or for invalid arguments.
+pub fn diary_sexp_matches(sexp: &str, date: &Date) -> Option<bool> {
+    let inner = sexp.trim().strip_prefix('(')?.strip_suffix(')')?;
+    let (func, args) = inner
+        .trim_start()
+        .split_once(char::is_whitespace)
+        .unwrap_or((inner.trim(), ""));
+    let args = parse_args(args)?;
+
+    let int = |i: usize| -> Option<i64> {
+        match args.get(i)? {
+            Arg::Int(num) => Some(*num),
+            _ => None,
+        }
+    };
+    let day_num = |month: i64, day: i64, year: i64| -> Option<i64> {
+        Some(make_date(year, month, day)?.to_days())
+    };
+    let today = date.to_days();
+
+    match func {
+        "diary-anniversary" => {
+            let (month, day) = (int(0)?, int(1)?);
+            if let Some(year) = args.get(2) {
+                let Arg::Int(year) = year else {
+                    return None;
+                };
+                if i64::from(date.year) <= *year {
+                    return Some(false);
+                }
+            }
+            // february 29th anniversaries fall on march 1st in common years
+            if month == 2 && day == 29 && !is_leap(i64::from(date.year)) {
+                Some(date.month == 3 && date.day == 1)
+            } else {
+                Some(i64::from(date.month) == month && i64::from(date.day) == day)
+            }
+        }
+        "diary-cyclic" => {
+            let every = int(0)?;
+            let start = day_num(int(1)?, int(2)?, int(3)?)?;
+            let diff = today - start;
+            Some(every > 0 && diff >= 0 && diff % every == 0)
+        }
+        "diary-block" => {
+            let start = day_num(int(0)?, int(1)?, int(2)?)?;
+            let end = day_num(int(3)?, int(4)?, int(5)?)?;
+            Some((start..=end).contains(&today))
+        }
+        "diary-float" => {
+            let month_matches = match args.first()? {
+                Arg::True => true,
+                Arg::Int(month) => i64::from(date.month) == *month,
+                Arg::List(months) => months.contains(&i64::from(date.month)),
+            };
+            let dayname = int(1)?;
+            let n = int(2)?;
+            if !month_matches || !(0..7).contains(&dayname) || n == 0 || !(-5..=5).contains(&n) {
+                return Some(false);
+            }
+
+            let year = i64::from(date.year);
+            let month = i64::from(date.month);
+            let last_day = days_in_month(year, month);
+            let base = match args.get(3) {
+                Some(Arg::Int(day)) => *day,
+                Some(_) => return None,
+                None if n > 0 => 1,
+                None => last_day,
+            };
+            let base_days = day_num(month, base, year)?;
+            let base_weekday = weekday(base_days);
+
+            let target = if n > 0 {
+                base_days + (dayname - base_weekday).rem_euclid(7) + 7 * (n - 1)
+            } else {
+                base_days - (base_weekday - dayname).rem_euclid(7) + 7 * (n + 1)
+            };
+            Some(target == today)
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Arg {
+    Int(i64),
+    /// `t`
+    True,
+    /// `'(1 2 3)`
+    List(Vec<i64>),
+}
+
+fn parse_args(args: &str) -> Option<Vec<Arg>> {
+    let mut ret = Vec::new();
+    let mut rest = args.trim();
+    while !rest.is_empty() {
+        if let Some(list) = rest.strip_prefix("'(") {
+            let (list, after) = list.split_once(')')?;
+            let nums = list
+                .split_whitespace()
+                .map(|num| num.parse().ok())
+                .collect::<Option<Vec<i64>>>()?;
+            ret.push(Arg::List(nums));
+            rest = after.trim_start();
+        } else {
+            let (token, after) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            ret.push(if token == "t" {
+                Arg::True
+            } else {
+                Arg::Int(token.parse().ok()?)
+            });
+            rest = after.trim_start();
+        }
+    }
+    Some(ret)
+}
+
+fn make_date(year: i64, month: i64, day: i64) -> Option<Date<'static>> {
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(Date {
+        year: year.try_into().ok()?,
+        month: month as u8,
+        day: day as u8,
+        dayname: None,
+    })
+}
+
+fn is_leap(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// 0 is Sunday
+fn weekday(days: i64) -> i64 {
+    // 1970-01-01 was a Thursday
+    (days + 4).rem_euclid(7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diary_sexp_matches;
+    use crate::object::Date;
+
+    fn date(year: u16, month: u8, day: u8) -> Date<'static> {
+        Date {
+            year,
+            month,
+            day,
+            dayname: None,
+        }
+    }
+
+    #[test]
+    fn anniversary() {
+        let sexp = "(diary-anniversary 10 31 1948)";
+        assert_eq!(diary_sexp_matches(sexp, &date(2023, 10, 31)), Some(true));
+        assert_eq!(diary_sexp_matches(sexp, &date(2023, 10, 30)), Some(false));
+        assert_eq!(diary_sexp_matches(sexp, &date(1948, 10, 31)), Some(false));
+
+        let leap = "(diary-anniversary 2 29)";
+        assert_eq!(diary_sexp_matches(leap, &date(2023, 3, 1)), Some(true));
+        assert_eq!(diary_sexp_matches(leap, &date(2024, 2, 29)), Some(true));
+    }
+
+    #[test]
+    fn cyclic_and_block() {
+        let cyclic = "(diary-cyclic 14 8 1 2023)";
+        assert_eq!(diary_sexp_matches(cyclic, &date(2023, 8, 15)), Some(true));
+        assert_eq!(diary_sexp_matches(cyclic, &date(2023, 8, 16)), Some(false));
+        assert_eq!(diary_sexp_matches(cyclic, &date(2023, 7, 18)), Some(false));
+
+        let block = "(diary-block 12 30 2023 1 2 2024)";
+        assert_eq!(diary_sexp_matches(block, &date(2024, 1, 1)), Some(true));
+        assert_eq!(diary_sexp_matches(block, &date(2024, 1, 3)), Some(false));
+    }
+
+    #[test]
+    fn float() {
+        // fourth thursday of november
+        let thanksgiving = "(diary-float 11 4 4)";
+        assert_eq!(
+            diary_sexp_matches(thanksgiving, &date(2023, 11, 23)),
+            Some(true)
+        );
+        assert_eq!(
+            diary_sexp_matches(thanksgiving, &date(2023, 11, 16)),
+            Some(false)
+        );
+
+        // last monday of may
+        let memorial = "(diary-float 5 1 -1)";
+        assert_eq!(diary_sexp_matches(memorial, &date(2023, 5, 29)), Some(true));
+
+        // first friday of any month
+        let any = "(diary-float t 5 1)";
+        assert_eq!(diary_sexp_matches(any, &date(2023, 9, 1)), Some(true));
+        let some = "(diary-float '(1 3) 5 1)";
+        assert_eq!(diary_sexp_matches(some, &date(2023, 9, 1)), Some(false));
+
+        // there is no sixth friday
+        for n in ["6", "-6", "9223372036854775807", "-9223372036854775808"] {
+            let sexp = format!("(diary-float t 5 {n})");
+            assert_eq!(diary_sexp_matches(&sexp, &date(2023, 9, 1)), Some(false));
+        }
+    }
+
+    #[test]
+    fn unsupported() {
+        assert_eq!(
+            diary_sexp_matches("(org-calendar-holiday)", &date(2023, 1, 1)),
+            None
+        );
+        assert_eq!(
+            diary_sexp_matches("(diary-block 1 2)", &date(2023, 1, 1)),
+            None
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt::Display;
+
+use crate::constants::{LBRACK, LPAREN, NEWLINE, RBRACK, RPAREN};
+use crate::node_pool::NodeID;
+use crate::object::InlineSrc;
+use crate::parse::parse_element;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+use crate::utils::bytes_to_str;
+
+/// A call to a named source block.
+///
+/// ```example
+/// #+call: plot[:session none](data=tbl, x=2) :exports results
+///
+/// #+RESULTS:
+/// : 42
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BabelCall<'a> {
+    pub name: &'a str,
+    /// Header arguments applied to the called block, e.g. `:session none`
+    pub inside_header: Option<&'a str>,
+    /// The raw argument list, e.g. `data=tbl, x=2`
+    pub arguments: Option<&'a str>,
+    /// Header arguments applied to the call itself as written after the arguments,
+    /// e.g. ` :exports results` or `[:results raw]`, see [`Self::end_header_args`]
+    pub end_header: Option<&'a str>,
+    /// The element following a `#+RESULTS:` line directly after the call
+    pub results: Option<NodeID>,
+    /// The hash in `#+RESULTS[hash]:`
+    pub results_hash: Option<&'a str>,
+}
+
+impl<'a> Parseable<'a> for BabelCall<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("#+")?;
+        let key = cursor.fn_until(|chr: u8| chr == b':' || chr.is_ascii_whitespace())?;
+        if !key.obj.eq_ignore_ascii_case("call") {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.move_to(key.end);
+        cursor.word(":")?;
+        cursor.skip_ws();
+
+        let name = cursor
+            .fn_until(|chr: u8| matches!(chr, LBRACK | LPAREN) || chr.is_ascii_whitespace())?;
+        if name.obj.is_empty() {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.move_to(name.end);
+
+        let inside_header = if let Ok(LBRACK) = cursor.try_curr() {
+            let header = InlineSrc::parse_src(cursor, LBRACK, RBRACK)?;
+            cursor.move_to(header.end);
+            Some(header.obj)
+        } else {
+            None
+        };
+
+        let arguments = if let Ok(LPAREN) = cursor.try_curr() {
+            let args = InlineSrc::parse_src(cursor, LPAREN, RPAREN)?;
+            cursor.move_to(args.end);
+            Some(args.obj)
+        } else {
+            None
+        };
+
+        let end_start = cursor.index;
+        cursor.adv_till_byte(NEWLINE);
+        // the whitespace before the header is kept, so the call is written as it was
+        let end_header = cursor.clamp_backwards(end_start).trim_end();
+        let end_header = (!end_header.trim_start().is_empty()).then_some(end_header);
+        // skip past the newline
+        cursor.next();
+
+        let reserved_id = parser.pool.reserve_id();
+        // a call on the last line has nothing after it
+        let (results, results_hash) = if cursor.index >= cursor.byte_arr.len() {
+            (None, None)
+        } else if let Ok((results_id, hash, results_end)) =
+            parse_results(parser, cursor, Some(reserved_id), parse_opts)
+        {
+            cursor.move_to(results_end);
+            (Some(results_id), hash)
+        } else {
+            (None, None)
+        };
+
+        Ok(parser.alloc_with_id(
+            Self {
+                name: name.obj,
+                inside_header,
+                arguments,
+                end_header,
+                results,
+                results_hash,
+            },
+            start,
+            cursor.index.min(cursor.byte_arr.len()),
+            parent,
+            reserved_id,
+        ))
+    }
+}
+
+impl<'a> BabelCall<'a> {
+    /// The header arguments of [`Self::end_header`], without the whitespace and
+    /// brackets around them.
+    pub fn end_header_args(&self) -> Option<&'a str> {
+        let header = self.end_header?.trim();
+        let unbracketed = header.strip_prefix('[').and_then(|h| h.strip_suffix(']'));
+        Some(unbracketed.unwrap_or(header))
+    }
+}
+
+impl Display for BabelCall<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#+call: {}", self.name)?;
+        if let Some(inside_header) = self.inside_header {
+            write!(f, "[{inside_header}]")?;
+        }
+        if let Some(arguments) = self.arguments {
+            write!(f, "({arguments})")?;
+        }
+        if let Some(end_header) = self.end_header {
+            write!(f, "{end_header}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `#+RESULTS:` line and the element after it, which may be
+/// preceded by blank lines.
+///
+/// Returns the id of the element, the hash in `#+RESULTS[hash]:` and the end of the results.
+pub(crate) fn parse_results<'a>(
+    parser: &mut Parser<'a>,
+    mut cursor: Cursor<'a>,
+    parent: Option<NodeID>,
+    parse_opts: ParseOpts,
+) -> Result<(NodeID, Option<&'a str>, usize)> {
+    // blank lines between the call and its results
+    loop {
+        let line_start = cursor.index;
+        cursor.skip_ws();
+        if let Ok(NEWLINE) = cursor.try_curr() {
+            cursor.next();
+        } else {
+            cursor.index = line_start;
+            break;
+        }
+    }
+
+    cursor.skip_ws();
+    cursor.word("#+")?;
+    let key = cursor.fn_until(|chr: u8| chr == b':' || chr == LBRACK || chr == NEWLINE)?;
+    if !key.obj.eq_ignore_ascii_case("results") {
+        return Err(MatchError::InvalidLogic);
+    }
+    cursor.adv_till_byte(NEWLINE);
+    let rest = bytes_to_str(&cursor.byte_arr[key.end..cursor.index]);
+    if !rest.contains(':') {
+        return Err(MatchError::InvalidLogic);
+    }
+    let hash = rest
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(hash, _)| hash.trim())
+        .filter(|hash| !hash.is_empty());
+    cursor.next();
+
+    let results_id = parse_element(parser, cursor, parent, parse_opts)?;
+    Ok((results_id, hash, parser.pool[results_id].end))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_call() {
+        let parsed = parse_org("#+call: plot(data=tbl)\n");
+        let call = expr_in_pool!(parsed, BabelCall).unwrap();
+
+        assert_eq!(call.name, "plot");
+        assert_eq!(call.inside_header, None);
+        assert_eq!(call.arguments, Some("data=tbl"));
+        assert_eq!(call.end_header, None);
+        assert_eq!(call.results, None);
+        assert!(expr_in_pool!(parsed, Keyword).is_none());
+    }
+
+    #[test]
+    fn call_headers() {
+        let parsed =
+            parse_org("#+CALL: plot[:session none :exports code](x=(1 2)) :exports both\n");
+        let call = expr_in_pool!(parsed, BabelCall).unwrap();
+
+        assert_eq!(call.inside_header, Some(":session none :exports code"));
+        assert_eq!(call.arguments, Some("x=(1 2)"));
+        assert_eq!(call.end_header, Some(" :exports both"));
+        assert_eq!(call.end_header_args(), Some(":exports both"));
+        assert_eq!(
+            call.to_string(),
+            "#+call: plot[:session none :exports code](x=(1 2)) :exports both"
+        );
+    }
+
+    #[test]
+    fn bracketed_end_header() {
+        let input = "#+call: plot[:session](x=1)[:results raw]";
+        let parsed = parse_org(input);
+        let call = expr_in_pool!(parsed, BabelCall).unwrap();
+
+        assert_eq!(call.end_header, Some("[:results raw]"));
+        assert_eq!(call.end_header_args(), Some(":results raw"));
+        assert_eq!(call.to_string(), input);
+    }
+
+    #[test]
+    fn call_at_eof() {
+        let parsed = parse_org("#+call: f()");
+        let call = expr_in_pool!(parsed, BabelCall).unwrap();
+
+        assert_eq!(call.name, "f");
+        assert_eq!(call.arguments, Some(""));
+        assert_eq!(call.results, None);
+    }
+
+    #[test]
+    fn call_results() {
+        let input = r"
+#+call: answer()
+
+#+RESULTS[3f2a]:
+: 42
+
+after
+";
+        let parsed = parse_org(input);
+        let call = expr_in_pool!(parsed, BabelCall).unwrap();
+        let results = call.results.unwrap();
+        assert!(matches!(parsed.pool[results].obj, Expr::FixedWidth(_)));
+        assert_eq!(call.results_hash, Some("3f2a"));
+        assert!(expr_in_pool!(parsed, Keyword).is_none());
+    }
+}
@@ -1,11 +1,17 @@
 use std::collections::HashMap;
+use std::fmt::Display;
 
+use crate::constants::{COLON, NEWLINE};
 use crate::node_pool::NodeID;
-use crate::parse::parse_element;
-use crate::types::{process_attrs, Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+use crate::parse::{parse_element, parse_object};
+use crate::types::{
+    process_attrs, Cursor, MarkupKind, MatchError, ParseOpts, Parseable, Parser, Result,
+};
 use lazy_static::lazy_static;
 use regex::bytes::Regex;
 
+use super::babel_call::parse_results;
+
 // regexes that search for various ending tokens on a line that only contains whitespace
 #[rustfmt::skip]
 lazy_static! {
@@ -41,8 +47,10 @@ pub enum Block<'a> {
         contents: &'a str,
     },
     Example {
+        switches: BlockSwitches<'a>,
         parameters: HashMap<&'a str, &'a str>,
         contents: &'a str,
+        coderefs: Vec<Coderef<'a>>,
     },
     Export {
         backend: Option<&'a str>,
@@ -51,12 +59,22 @@ pub enum Block<'a> {
     },
     Src {
         language: Option<&'a str>,
+        switches: BlockSwitches<'a>,
         parameters: HashMap<&'a str, &'a str>,
+        /// The raw header arguments, which can repeat keys unlike `parameters`,
+        /// see [`crate::HeaderArgs`]
+        header: &'a str,
         contents: &'a str,
+        coderefs: Vec<Coderef<'a>>,
+        /// The element following a `#+RESULTS:` line directly after the block
+        results: Option<NodeID>,
+        /// The hash in `#+RESULTS[hash]:`, written for blocks with `:cache yes`
+        results_hash: Option<&'a str>,
     },
+    /// Contains objects rather than elements, keeping its line breaks and indentation
     Verse {
         parameters: HashMap<&'a str, &'a str>,
-        contents: &'a str,
+        contents: Vec<NodeID>,
     },
 }
 
@@ -113,8 +131,17 @@ impl<'a> Parseable<'a> for Block<'a> {
             }
             _ => (),
         }
-        // TODO: src switches
+        let switches = if matches!(block_kind, BlockKind::Src | BlockKind::Example) {
+            let (switch_cursor, switches) = BlockSwitches::parse(cursor)?;
+            cursor = switch_cursor;
+            switches
+        } else {
+            BlockSwitches::default()
+        };
+        let header_start = cursor.index;
         let (mut cursor, parameters) = process_attrs(cursor)?;
+        let parameters: HashMap<&str, &str> = parameters.into_iter().collect();
+        let header = cursor.clamp_backwards(header_start).trim();
         // skip newline
         cursor.next();
 
@@ -146,7 +173,7 @@ impl<'a> Parseable<'a> for Block<'a> {
         };
 
         let loc = ret.start() + cursor.index;
-        let end = ret.end() + cursor.index;
+        let mut end = ret.end() + cursor.index;
 
         // handle empty contents
         // if cursor.index > loc {
@@ -155,7 +182,31 @@ impl<'a> Parseable<'a> for Block<'a> {
 
         if block_kind.is_lesser() {
             let contents = cursor.clamp_forwards(loc);
-            Ok(parser.alloc(
+            let reserve_id = parser.pool.reserve_id();
+            let mut verse = Vec::new();
+            if let BlockKind::Verse = block_kind {
+                let mut verse_opts = ParseOpts::default();
+                verse_opts.markup.insert(MarkupKind::Verse);
+                let mut temp_cursor = cursor.cut_off(loc);
+                while let Ok(id) = parse_object(parser, temp_cursor, Some(reserve_id), verse_opts) {
+                    verse.push(id);
+                    temp_cursor.index = parser.pool[id].end;
+                }
+            }
+            let mut results = None;
+            let mut results_hash = None;
+            if let BlockKind::Src = block_kind {
+                cursor.move_to(end);
+                if let Ok((results_id, hash, results_end)) =
+                    parse_results(parser, cursor, Some(reserve_id), parse_opts)
+                {
+                    results = Some(results_id);
+                    results_hash = hash;
+                    end = results_end;
+                }
+            }
+
+            Ok(parser.alloc_with_id(
                 match block_kind {
                     BlockKind::Center | BlockKind::Quote | BlockKind::Special(_) => unreachable!(),
                     BlockKind::Comment => Block::Comment {
@@ -163,6 +214,8 @@ impl<'a> Parseable<'a> for Block<'a> {
                         contents,
                     },
                     BlockKind::Example => Block::Example {
+                        coderefs: switches.coderefs(contents),
+                        switches,
                         parameters,
                         contents,
                     },
@@ -173,17 +226,23 @@ impl<'a> Parseable<'a> for Block<'a> {
                     },
                     BlockKind::Src => Block::Src {
                         language,
+                        coderefs: switches.coderefs(contents),
+                        switches,
                         parameters,
+                        header,
                         contents,
+                        results,
+                        results_hash,
                     },
                     BlockKind::Verse => Block::Verse {
                         parameters,
-                        contents,
+                        contents: verse,
                     },
                 },
                 start,
                 end,
                 parent,
+                reserve_id,
             ))
         } else {
             let mut contents: Vec<NodeID> = Vec::new();
@@ -229,6 +288,175 @@ impl<'a> Parseable<'a> for Block<'a> {
     }
 }
 
+/// Switches placed before the parameters of src and example blocks.
+///
+/// ```example
+/// #+begin_src rust -n 10 -r -l "[%s]" :tangle main.rs
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockSwitches<'a> {
+    /// `-n` or `+n`, followed by an optional number
+    pub number_lines: Option<LineNumbers>,
+    /// `-r`: remove coderef labels from the exported code
+    pub remove_labels: bool,
+    /// `-k`: keep coderef labels in the code, but link to line numbers
+    pub keep_labels: bool,
+    /// `-i`: preserve the indentation of the contents
+    pub preserve_indent: bool,
+    /// `-l "FORMAT"`: the format of coderef labels, see [`Self::label_format`]
+    pub label_format: Option<&'a str>,
+}
+
+/// How the lines of a block are numbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineNumbers {
+    /// `-n N`: numbering starts at N, defaulting to 1
+    New(usize),
+    /// `+n N`: numbering continues from the previous numbered block, skipping N
+    /// numbers, defaulting to 0
+    Continued(usize),
+}
+
+/// A label marking a line of a src or example block, e.g. `(ref:main)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coderef<'a> {
+    pub label: &'a str,
+    /// The line the label is on, starting from 0
+    pub line: usize,
+}
+
+impl<'a> BlockSwitches<'a> {
+    const DEFAULT_LABEL_FORMAT: &'static str = "(ref:%s)";
+
+    /// Parses switches until the start of the parameters or the end of the line.
+    ///
+    /// Unrecognized words are skipped.
+    fn parse(mut cursor: Cursor<'a>) -> Result<(Cursor<'a>, Self)> {
+        let mut switches = Self::default();
+        loop {
+            cursor.skip_ws();
+            if matches!(cursor.try_curr()?, NEWLINE | COLON) {
+                break;
+            }
+            let word = cursor.fn_until(|chr| chr.is_ascii_whitespace())?;
+            cursor.move_to(word.end);
+
+            match word.obj {
+                "-n" | "+n" => {
+                    let mut num = None;
+                    let num_start = cursor.index;
+                    cursor.skip_ws();
+                    let num_match = cursor.fn_while(|chr| chr.is_ascii_digit())?;
+                    if let Ok(val) = num_match.obj.parse() {
+                        num = Some(val);
+                        cursor.move_to(num_match.end);
+                    } else {
+                        cursor.move_to(num_start);
+                    }
+                    switches.number_lines = Some(if word.obj == "-n" {
+                        LineNumbers::New(num.unwrap_or(1))
+                    } else {
+                        LineNumbers::Continued(num.unwrap_or(0))
+                    });
+                }
+                "-r" => switches.remove_labels = true,
+                "-k" => switches.keep_labels = true,
+                "-i" => switches.preserve_indent = true,
+                "-l" => {
+                    cursor.skip_ws();
+                    if let Ok(b'"') = cursor.try_curr() {
+                        cursor.next();
+                        let format = cursor.fn_until(|chr| chr == b'"' || chr == NEWLINE)?;
+                        cursor.move_to(format.end);
+                        if let Ok(b'"') = cursor.try_curr() {
+                            cursor.next();
+                            switches.label_format = Some(format.obj);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok((cursor, switches))
+    }
+
+    /// The format of coderef labels, where `%s` is replaced by the label.
+    ///
+    /// Defaults to `(ref:%s)`.
+    pub fn label_format(&self) -> &'a str {
+        self.label_format.unwrap_or(Self::DEFAULT_LABEL_FORMAT)
+    }
+
+    /// Whether labels are kept in the exported code.
+    pub fn retain_labels(&self) -> bool {
+        !self.remove_labels || (self.number_lines.is_some() && self.keep_labels)
+    }
+
+    /// Whether links to coderefs are described by their label rather than
+    /// their line number.
+    pub fn use_labels(&self) -> bool {
+        self.retain_labels() && !self.keep_labels
+    }
+
+    /// Splits a line into its code and the coderef label at its end, if any.
+    pub fn split_label<'b>(&self, line: &'b str) -> (&'b str, Option<&'b str>) {
+        let Some((prefix, suffix)) = self.label_format().split_once("%s") else {
+            return (line, None);
+        };
+        let Some(before) = line.trim_end().strip_suffix(suffix) else {
+            return (line, None);
+        };
+        let Some(label_start) = before.rfind(prefix) else {
+            return (line, None);
+        };
+
+        let label = &before[label_start + prefix.len()..];
+        let valid_char = |chr: char| chr.is_ascii_alphanumeric() || matches!(chr, '-' | '_');
+        let mut chars = label.chars();
+        if chars.next().is_some_and(valid_char) && chars.all(|chr| valid_char(chr) || chr == ' ') {
+            (before[..label_start].trim_end(), Some(label))
+        } else {
+            (line, None)
+        }
+    }
+
+    fn coderefs(&self, contents: &'a str) -> Vec<Coderef<'a>> {
+        contents
+            .lines()
+            .enumerate()
+            .filter_map(|(line, text)| {
+                let label = self.split_label(text).1?;
+                Some(Coderef { label, line })
+            })
+            .collect()
+    }
+}
+
+impl Display for BlockSwitches<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.number_lines {
+            Some(LineNumbers::New(1)) => f.write_str(" -n")?,
+            Some(LineNumbers::New(start)) => write!(f, " -n {start}")?,
+            Some(LineNumbers::Continued(0)) => f.write_str(" +n")?,
+            Some(LineNumbers::Continued(skip)) => write!(f, " +n {skip}")?,
+            None => {}
+        }
+        if self.remove_labels {
+            f.write_str(" -r")?;
+        }
+        if self.keep_labels {
+            f.write_str(" -k")?;
+        }
+        if self.preserve_indent {
+            f.write_str(" -i")?;
+        }
+        if let Some(format) = self.label_format {
+            write!(f, " -l \"{format}\"")?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum BlockKind<'a> {
     // Greater
@@ -305,7 +533,7 @@ impl<'a> From<BlockKind<'a>> for &'a str {
 mod tests {
     use std::collections::HashMap;
 
-    use crate::element::Block;
+    use crate::element::{Block, BlockSwitches, Coderef, LineNumbers};
     use crate::types::Expr;
     use crate::{expr_in_pool, parse_org};
 
@@ -354,8 +582,13 @@ mod tests {
             l,
             &Block::Src {
                 language: Some("python"),
+                switches: BlockSwitches::default(),
                 parameters: HashMap::new(),
-                contents: ""
+                header: "",
+                contents: "",
+                coderefs: Vec::new(),
+                results: None,
+                results_hash: None,
             }
         )
     }
@@ -370,8 +603,10 @@ mod tests {
         assert_eq!(
             l,
             &Block::Example {
+                switches: BlockSwitches::default(),
                 parameters: HashMap::from([("gotta", ""), ("love", ""), ("examples", "")]),
-                contents: ""
+                contents: "",
+                coderefs: Vec::new(),
             }
         )
     }
@@ -386,9 +621,11 @@ mod tests {
         assert_eq!(
             l,
             &Block::Example {
+                switches: BlockSwitches::default(),
                 parameters: HashMap::new(),
                 contents: "smallexp
-"
+",
+                coderefs: Vec::new(),
             }
         )
     }
@@ -411,6 +648,7 @@ one two three
         assert_eq!(
             l,
             &Block::Example {
+                switches: BlockSwitches::default(),
                 parameters: HashMap::new(),
                 contents: r"this is a larger example gotta love examples
 to demonstrate that it works
@@ -419,7 +657,8 @@ big
 one two three
 /formatted text? no such thing!/
 *abc*
-"
+",
+                coderefs: Vec::new(),
             }
         )
     }
@@ -476,10 +715,15 @@ here is after
             l,
             &Block::Src {
                 language: Some("python"),
+                switches: BlockSwitches::default(),
                 parameters: HashMap::new(),
+                header: "",
                 contents: r"
 here is some text
-"
+",
+                coderefs: Vec::new(),
+                results: None,
+                results_hash: None,
             }
         )
     }
@@ -498,9 +742,11 @@ here is some text
         assert_eq!(
             l,
             &Block::Example {
+                switches: BlockSwitches::default(),
                 parameters: HashMap::new(),
                 contents: r"             we are eating so good?
-"
+",
+                coderefs: Vec::new(),
             }
         )
     }
@@ -552,13 +798,18 @@ text
             l,
             &Block::Src {
                 language: None,
+                switches: BlockSwitches::default(),
                 parameters: HashMap::new(),
+                header: "",
                 contents: r"
 
 hiiiiiiiiiiiiiiiiiii
 
 text
-"
+",
+                coderefs: Vec::new(),
+                results: None,
+                results_hash: None,
             }
         )
     }
@@ -571,16 +822,46 @@ text
 #+END_VERSE
 ";
         let parsed = parse_org(input);
-        let l = expr_in_pool!(parsed, Block).unwrap();
+        let Block::Verse {
+            parameters,
+            contents,
+        } = expr_in_pool!(parsed, Block).unwrap()
+        else {
+            unreachable!()
+        };
 
-        assert_eq!(
-            &Block::Verse {
-                parameters: HashMap::new(),
-                contents: r"text
-"
-            },
-            l
-        )
+        assert!(parameters.is_empty());
+        assert_eq!(contents.len(), 2);
+        assert!(matches!(parsed.pool[contents[0]].obj, Expr::Plain("text")));
+        assert!(matches!(parsed.pool[contents[1]].obj, Expr::SoftBreak));
+    }
+
+    #[test]
+    fn verse_objects() {
+        let input = r"#+begin_verse
+Great /clouds/
+   - not a list
+
+| not a table
+#+end_verse
+";
+        let parsed = parse_org(input);
+        let Block::Verse { contents, .. } = expr_in_pool!(parsed, Block).unwrap() else {
+            unreachable!()
+        };
+
+        let objs: Vec<&Expr> = contents.iter().map(|id| &parsed.pool[*id].obj).collect();
+        assert!(matches!(objs[0], Expr::Plain("Great ")));
+        assert!(matches!(objs[1], Expr::Italic(_)));
+        assert!(matches!(objs[2], Expr::SoftBreak));
+        assert!(matches!(objs[3], Expr::Plain("   - not a list")));
+        assert!(matches!(objs[4], Expr::SoftBreak));
+        assert!(matches!(objs[5], Expr::SoftBreak));
+        assert!(matches!(objs[6], Expr::Plain("| not a table")));
+        assert!(matches!(objs[7], Expr::SoftBreak));
+        assert_eq!(objs.len(), 8);
+        assert!(expr_in_pool!(parsed, PlainList).is_none());
+        assert!(expr_in_pool!(parsed, Table).is_none());
     }
     #[test]
     fn caps_space() {
@@ -601,4 +882,135 @@ text
             l
         )
     }
+
+    #[test]
+    fn block_switches() {
+        let input = r#"#+begin_src rust -n 10 -r -l "[%s]" :tangle main.rs
+fn main() { [entry]
+    run();
+}
+#+end_src
+"#;
+        let parsed = parse_org(input);
+        let l = expr_in_pool!(parsed, Block).unwrap();
+
+        assert_eq!(
+            l,
+            &Block::Src {
+                language: Some("rust"),
+                switches: BlockSwitches {
+                    number_lines: Some(LineNumbers::New(10)),
+                    remove_labels: true,
+                    label_format: Some("[%s]"),
+                    ..Default::default()
+                },
+                parameters: HashMap::from([("tangle", "main.rs")]),
+                header: ":tangle main.rs",
+                contents: "fn main() { [entry]\n    run();\n}\n",
+                coderefs: vec![Coderef {
+                    label: "entry",
+                    line: 0
+                }],
+                results: None,
+                results_hash: None,
+            }
+        );
+        let Block::Src { switches, .. } = l else {
+            unreachable!()
+        };
+        assert_eq!(switches.to_string(), r#" -n 10 -r -l "[%s]""#);
+        assert!(!switches.retain_labels());
+    }
+
+    #[test]
+    fn default_coderefs() {
+        let input = r"#+begin_example +n -k
+first (ref:one)
+second(ref:not valid!)
+third   (ref:two words)  
+#+end_example
+";
+        let parsed = parse_org(input);
+        let Block::Example {
+            switches, coderefs, ..
+        } = expr_in_pool!(parsed, Block).unwrap()
+        else {
+            unreachable!()
+        };
+
+        assert_eq!(switches.number_lines, Some(LineNumbers::Continued(0)));
+        assert!(switches.retain_labels());
+        assert!(!switches.use_labels());
+        assert_eq!(
+            coderefs,
+            &[
+                Coderef {
+                    label: "one",
+                    line: 0
+                },
+                Coderef {
+                    label: "two words",
+                    line: 2
+                }
+            ]
+        );
+        assert_eq!(
+            switches.split_label("third   (ref:two words)  "),
+            ("third", Some("two words"))
+        );
+    }
+
+    #[test]
+    fn src_block_results() {
+        let input = r"#+begin_src python :var x=1 :var x=2
+print(x)
+#+end_src
+
+#+RESULTS:
+: 2
+
+after
+";
+        let parsed = parse_org(input);
+        let Block::Src {
+            header,
+            parameters,
+            results,
+            ..
+        } = expr_in_pool!(parsed, Block).unwrap()
+        else {
+            unreachable!()
+        };
+
+        assert_eq!(header, &":var x=1 :var x=2");
+        assert_eq!(parameters.len(), 1);
+        assert!(matches!(
+            parsed.pool[results.unwrap()].obj,
+            Expr::FixedWidth(_)
+        ));
+        assert!(expr_in_pool!(parsed, Keyword).is_none());
+    }
+
+    #[test]
+    fn src_block_results_hash() {
+        let input = r"#+begin_src sh :cache yes
+echo 1
+#+end_src
+
+#+RESULTS[3f2a1c]:
+: 1
+";
+        let parsed = parse_org(input);
+        let Block::Src {
+            results,
+            results_hash,
+            ..
+        } = expr_in_pool!(parsed, Block).unwrap()
+        else {
+            unreachable!()
+        };
+
+        assert!(results.is_some());
+        assert_eq!(results_hash, &Some("3f2a1c"));
+    }
 }
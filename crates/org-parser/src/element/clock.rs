@@ -0,0 +1,247 @@
+use std::fmt::Display;
+
+use crate::constants::{COLON, NEWLINE};
+use crate::node_pool::NodeID;
+use crate::object::{parse_timestamp, DateTime, RangeKind, Timestamp};
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+/// A clock line, typically found in a `:LOGBOOK:` drawer.
+///
+/// ```example
+/// CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30
+/// CLOCK: [2023-08-17 Thu 09:00]
+/// ```
+///
+/// A clock without an end is still running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clock<'a> {
+    pub start: DateTime<'a>,
+    pub end: Option<DateTime<'a>>,
+    /// The `=> H:MM` duration as written, in minutes.
+    pub duration: Option<u32>,
+}
+
+impl<'a> Parseable<'a> for Clock<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("CLOCK:")?;
+        cursor.skip_ws();
+
+        let stamp_match = parse_timestamp(cursor)?;
+        let stamp = stamp_match.obj;
+        if stamp.active {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.move_to(stamp_match.end);
+        cursor.skip_ws();
+
+        let duration = if cursor.word("=>").is_ok() {
+            cursor.skip_ws();
+            let hours = digits(cursor);
+            cursor.index += hours.len();
+            if hours.is_empty() || cursor.try_curr()? != COLON {
+                return Err(MatchError::InvalidLogic);
+            }
+            cursor.next();
+            let minutes = digits(cursor);
+            if minutes.len() != 2 {
+                return Err(MatchError::InvalidLogic);
+            }
+            cursor.index += minutes.len();
+            cursor.skip_ws();
+
+            let hours: u32 = hours.parse().or(Err(MatchError::InvalidLogic))?;
+            let minutes: u32 = minutes.parse().or(Err(MatchError::InvalidLogic))?;
+            let duration = hours
+                .checked_mul(60)
+                .and_then(|hours| hours.checked_add(minutes))
+                .ok_or(MatchError::InvalidLogic)?;
+            Some(duration)
+        } else {
+            None
+        };
+
+        let end = match cursor.try_curr() {
+            Ok(NEWLINE) => cursor.index + 1,
+            Ok(_) => return Err(MatchError::InvalidLogic),
+            Err(_) => cursor.index,
+        };
+
+        Ok(parser.alloc(
+            Self {
+                start: stamp.start,
+                end: stamp.end,
+                duration,
+            },
+            start,
+            end,
+            parent,
+        ))
+    }
+}
+
+/// The digits at the cursor, which may run up to the end of the input.
+fn digits<'a>(cursor: Cursor<'a>) -> &'a str {
+    let len = cursor
+        .rest()
+        .iter()
+        .position(|chr| !chr.is_ascii_digit())
+        .unwrap_or(cursor.rest().len());
+    cursor.clamp_forwards(cursor.index + len)
+}
+
+impl<'a> Clock<'a> {
+    /// The amount of clocked minutes.
+    ///
+    /// Uses the written duration if it exists, otherwise computes it from the range.
+    /// Running clocks have no duration.
+    pub fn minutes(&self) -> Option<u32> {
+        self.duration.or_else(|| {
+            let end = self.end?;
+            u32::try_from(end.to_minutes() - self.start.to_minutes()).ok()
+        })
+    }
+
+    /// The clocked range as an inactive [`Timestamp`].
+    pub fn timestamp(&self) -> Timestamp<'a> {
+        Timestamp {
+            active: false,
+            start: self.start,
+            end: self.end,
+            range_kind: RangeKind::Dates,
+            repeater: None,
+            warning: None,
+        }
+    }
+}
+
+impl Display for Clock<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CLOCK: [{}]", self.start)?;
+        if let Some(end) = self.end {
+            write!(f, "--[{end}]")?;
+        }
+        if let Some(duration) = self.duration {
+            write!(f, " => {:>2}:{:02}", duration / 60, duration % 60)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::Time;
+    use crate::test_utils::heading_id;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+
+    #[test]
+    fn basic_clock() {
+        let input = "CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30\n";
+        let parsed = parse_org(input);
+        let clock = expr_in_pool!(parsed, Clock).unwrap();
+
+        assert_eq!(clock.start.time, Some(Time { hour: 9, minute: 0 }));
+        assert_eq!(
+            clock.end.unwrap().time,
+            Some(Time {
+                hour: 10,
+                minute: 30
+            })
+        );
+        assert_eq!(clock.duration, Some(90));
+        assert_eq!(format!("{clock}\n"), input);
+    }
+
+    #[test]
+    fn running_clock() {
+        let input = "CLOCK: [2023-08-16 Wed 09:00]";
+        let parsed = parse_org(input);
+        let clock = expr_in_pool!(parsed, Clock).unwrap();
+
+        assert_eq!(clock.end, None);
+        assert_eq!(clock.minutes(), None);
+    }
+
+    #[test]
+    fn closed_clock_at_eof() {
+        let input = "CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30";
+        let parsed = parse_org(input);
+        let clock = expr_in_pool!(parsed, Clock).unwrap();
+
+        assert_eq!(clock.duration, Some(90));
+    }
+
+    #[test]
+    fn clock_duration_overflow() {
+        for input in [
+            "CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  99999999999999:00\n",
+            "CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  99999999:00\n",
+            "CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  71582788:59\n",
+        ] {
+            let parsed = parse_org(input);
+            assert!(expr_in_pool!(parsed, Clock).is_none(), "{input}");
+        }
+    }
+
+    #[test]
+    fn clock_computed_duration() {
+        let input = "CLOCK: [2023-08-16 Wed 23:00]--[2023-08-17 Thu 01:15]\n";
+        let parsed = parse_org(input);
+        let clock = expr_in_pool!(parsed, Clock).unwrap();
+
+        assert_eq!(clock.duration, None);
+        assert_eq!(clock.minutes(), Some(135));
+    }
+
+    #[test]
+    fn clock_in_logbook() {
+        let input = r"
+* heading
+:LOGBOOK:
+CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30
+:END:
+";
+        let parsed = parse_org(input);
+        let drawer = expr_in_pool!(parsed, Drawer).unwrap();
+
+        assert!(matches!(
+            parsed.pool[drawer.children[0]].obj,
+            Expr::Clock(_)
+        ));
+    }
+
+    #[test]
+    fn active_clock_invalid() {
+        let input = "CLOCK: <2023-08-16 Wed 09:00>\n";
+        let parsed = parse_org(input);
+
+        assert!(expr_in_pool!(parsed, Clock).is_none());
+    }
+
+    #[test]
+    fn clocked_subtree() {
+        let input = r"
+* parent
+:LOGBOOK:
+CLOCK: [2023-08-16 Wed 09:00]--[2023-08-16 Wed 10:30] =>  1:30
+:END:
+** child
+CLOCK: [2023-08-16 Wed 11:00]--[2023-08-16 Wed 11:45]
+CLOCK: [2023-08-16 Wed 12:00]
+* sibling
+CLOCK: [2023-08-16 Wed 13:00]--[2023-08-16 Wed 15:00] =>  2:00
+";
+        let parsed = parse_org(input);
+
+        assert_eq!(parsed.clocked_minutes(heading_id(&parsed, "parent")), 135);
+        assert_eq!(parsed.clocked_minutes(heading_id(&parsed, "child")), 45);
+        assert_eq!(parsed.clocked_minutes(heading_id(&parsed, "sibling")), 120);
+        assert_eq!(parsed.clocked_minutes(parsed.pool.root_id()), 255);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::constants::{LPAREN, NEWLINE, RPAREN};
+use crate::diary::diary_sexp_matches;
+use crate::node_pool::NodeID;
+use crate::object::{Date, InlineSrc};
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+/// A diary entry described by an Emacs Lisp expression.
+///
+/// ```example
+/// %%(diary-anniversary 10 31 1948) Arthur's birthday
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiarySexp<'a> {
+    /// The expression, including its parentheses
+    pub sexp: &'a str,
+    /// Any text following the expression
+    pub description: Option<&'a str>,
+}
+
+impl<'a> Parseable<'a> for DiarySexp<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("%%")?;
+        if cursor.try_curr()? != LPAREN {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        let sexp_start = cursor.index;
+        let sexp = InlineSrc::parse_src(cursor, LPAREN, RPAREN)?;
+        cursor.move_to(sexp.end);
+
+        let desc_start = cursor.index;
+        cursor.adv_till_byte(NEWLINE);
+        let description = cursor.clamp_backwards(desc_start).trim();
+
+        Ok(parser.alloc(
+            Self {
+                sexp: cursor.clamp(sexp_start, sexp.end),
+                description: (!description.is_empty()).then_some(description),
+            },
+            start,
+            cursor.index + 1,
+            parent,
+        ))
+    }
+}
+
+impl DiarySexp<'_> {
+    /// Whether the entry occurs on `date`, see [`diary_sexp_matches`].
+    pub fn matches(&self, date: &Date) -> Option<bool> {
+        diary_sexp_matches(self.sexp, date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::DiarySexp;
+    use crate::object::Date;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_diary_sexp() {
+        let input = "%%(diary-anniversary 10 31 1948) Arthur's (birthday)\n";
+        let parsed = parse_org(input);
+        let diary = expr_in_pool!(parsed, DiarySexp).unwrap();
+
+        assert_eq!(
+            diary,
+            &DiarySexp {
+                sexp: "(diary-anniversary 10 31 1948)",
+                description: Some("Arthur's (birthday)"),
+            }
+        );
+        let date = Date {
+            year: 2000,
+            month: 10,
+            day: 31,
+            dayname: None,
+        };
+        assert_eq!(diary.matches(&date), Some(true));
+    }
+
+    #[test]
+    fn not_diary_sexp() {
+        for input in ["%%diary\n", " %%(diary-float t 1 1)\n", "%%(unbalanced\n"] {
+            let parsed = parse_org(input);
+            assert!(expr_in_pool!(parsed, DiarySexp).is_none(), "{input}");
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::node_pool::NodeID;
+use crate::parse::parse_element;
+use crate::types::{process_attrs, Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+lazy_static! {
+    static ref END_RE: Regex = Regex::new(r"(?mi)^[ \t]*#\+end:[\t ]*$").unwrap();
+}
+
+/// A block whose contents are generated by a function, such as a clock table.
+///
+/// ```example
+/// #+BEGIN: clocktable :scope file :maxlevel 2
+/// | Headline | Time |
+/// #+END:
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicBlock<'a> {
+    pub name: &'a str,
+    /// The `:key value` pairs after the name, in the order they were written
+    pub parameters: Vec<(&'a str, &'a str)>,
+    pub children: Vec<NodeID>,
+}
+
+impl<'a> DynamicBlock<'a> {
+    /// Looks up a parameter by key. When a key is repeated, the last value wins.
+    pub fn parameter(&self, key: &str) -> Option<&'a str> {
+        self.parameters
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == key)
+            .map(|(_, val)| *val)
+    }
+}
+
+impl<'a> Parseable<'a> for DynamicBlock<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("#+")?;
+        let key = cursor.fn_until(|chr: u8| chr == b':' || chr.is_ascii_whitespace())?;
+        if !key.obj.eq_ignore_ascii_case("begin") {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.move_to(key.end);
+        cursor.word(":")?;
+        cursor.skip_ws();
+
+        let name = cursor.fn_until(|chr: u8| chr.is_ascii_whitespace())?;
+        if name.obj.is_empty() {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.move_to(name.end);
+
+        let (mut cursor, parameters) = process_attrs(cursor)?;
+        // skip past the newline
+        cursor.next();
+
+        let matched_reg = END_RE.find(cursor.rest()).ok_or(MatchError::InvalidLogic)?;
+        let loc = matched_reg.start() + cursor.index;
+        // include the newline after #+END:
+        let end = (matched_reg.end() + cursor.index + 1).min(cursor.byte_arr.len());
+
+        let mut children: Vec<NodeID> = Vec::new();
+        let reserve_id = parser.pool.reserve_id();
+        let mut temp_cursor = cursor.cut_off(loc);
+
+        while let Ok(element_id) =
+            parse_element(parser, temp_cursor, Some(reserve_id), ParseOpts::default())
+        {
+            children.push(element_id);
+            temp_cursor.index = parser.pool[element_id].end;
+        }
+
+        Ok(parser.alloc_with_id(
+            Self {
+                name: name.obj,
+                parameters,
+                children,
+            },
+            start,
+            end,
+            parent,
+            reserve_id,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_dynamic_block() {
+        let input = r"
+#+BEGIN: clocktable :scope file :maxlevel 2
+| Headline | Time |
+#+END:
+";
+        let parsed = parse_org(input);
+        let block = expr_in_pool!(parsed, DynamicBlock).unwrap();
+
+        assert_eq!(block.name, "clocktable");
+        assert_eq!(block.parameters, [("scope", "file"), ("maxlevel", "2")]);
+        assert_eq!(block.parameter("maxlevel"), Some("2"));
+        assert_eq!(block.parameter("id"), None);
+        assert_eq!(block.children.len(), 1);
+        assert!(matches!(parsed.pool[block.children[0]].obj, Expr::Table(_)));
+        assert!(expr_in_pool!(parsed, Keyword).is_none());
+    }
+
+    #[test]
+    fn empty_dynamic_block() {
+        let input = "#+begin: columnview\n#+end:\n";
+        let parsed = parse_org(input);
+        let block = expr_in_pool!(parsed, DynamicBlock).unwrap();
+
+        assert_eq!(block.name, "columnview");
+        assert!(block.parameters.is_empty());
+        assert!(block.children.is_empty());
+    }
+
+    #[test]
+    fn unclosed_dynamic_block() {
+        let input = "#+BEGIN: clocktable\nno end\n";
+        let parsed = parse_org(input);
+
+        assert!(expr_in_pool!(parsed, DynamicBlock).is_none());
+    }
+}
@@ -0,0 +1,99 @@
+use crate::constants::{COLON, NEWLINE, SPACE};
+use crate::node_pool::NodeID;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+/// Consecutive lines of literal text, each starting with a colon.
+///
+/// ```example
+/// : $ cargo build
+/// :    Compiling org-rust-parser
+/// ```
+///
+/// Holds every line with the leading `: ` removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedWidth<'a>(pub Vec<&'a str>);
+
+impl<'a> Parseable<'a> for FixedWidth<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        let mut lines: Vec<&str> = Vec::new();
+        // following lines can't be less indented than the first, so that
+        // the area doesn't leak out of a list item
+        let mut indentation = 0;
+        while let Ok(SPACE) = cursor.peek_rev(indentation + 1) {
+            indentation += 1;
+        }
+
+        loop {
+            let line_start = cursor.index;
+            cursor.skip_ws();
+            let too_shallow = !lines.is_empty() && cursor.index - line_start < indentation;
+            if too_shallow || !matches!(cursor.try_curr(), Ok(COLON)) {
+                cursor.index = line_start;
+                break;
+            }
+            cursor.next();
+            match cursor.try_curr() {
+                Ok(SPACE) => cursor.next(),
+                Ok(NEWLINE) | Err(_) => {}
+                Ok(_) => {
+                    cursor.index = line_start;
+                    break;
+                }
+            }
+
+            let content_start = cursor.index;
+            cursor.adv_till_byte(NEWLINE);
+            lines.push(cursor.clamp_backwards(content_start));
+            // eat the newline, if it exists
+            cursor.index = (cursor.index + 1).min(cursor.byte_arr.len());
+        }
+
+        if lines.is_empty() {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        Ok(parser.alloc(Self(lines), start, cursor.index, parent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_fixed_width() {
+        let input = r"
+: one
+:
+:   indented
+after
+";
+        let parsed = parse_org(input);
+        let fixed = expr_in_pool!(parsed, FixedWidth).unwrap();
+        assert_eq!(fixed.0, ["one", "", "  indented"]);
+
+        let root = expr_in_pool!(parsed, Root).unwrap();
+        assert!(matches!(parsed.pool[root[2]].obj, Expr::Paragraph(_)));
+    }
+
+    #[test]
+    fn fixed_width_eof() {
+        let parsed = parse_org(": no newline");
+        let fixed = expr_in_pool!(parsed, FixedWidth).unwrap();
+        assert_eq!(fixed.0, ["no newline"]);
+    }
+
+    #[test]
+    fn not_fixed_width() {
+        let parsed = parse_org(":not fixed width\n");
+        assert!(expr_in_pool!(parsed, FixedWidth).is_none());
+    }
+}
@@ -3,28 +3,35 @@ use std::rc::Rc;
 use crate::constants::{COLON, NEWLINE, RBRACK, SPACE, STAR};
 use crate::node_pool::NodeID;
 use crate::parse::{parse_element, parse_object};
+use crate::settings::{TodoKeywords, TodoState};
 use crate::types::{Cursor, Expr, MatchError, ParseOpts, Parseable, Parser, Result};
 use crate::utils::{bytes_to_str, Match};
 
-use super::{parse_property, PropertyDrawer};
-
-const ORG_TODO_KEYWORDS: [&str; 2] = ["TODO", "DONE"];
+use super::{parse_planning, parse_property, Planning, PropertyDrawer};
 
 // STARS KEYWORD PRIORITY TITLE TAGS
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Heading<'a> {
     pub heading_level: HeadingLevel,
     // Org-Todo type stuff
-    pub keyword: Option<&'a str>,
+    pub keyword: Option<TodoKeyword<'a>>,
     pub priority: Option<Priority>,
     // plain text of title + parsed nodeIDs
     // store both to accomdote targets
     pub title: Option<(&'a str, Vec<NodeID>)>,
     pub tags: Option<Vec<Tag<'a>>>,
+    pub planning: Option<Box<Planning<'a>>>,
     pub properties: Option<PropertyDrawer<'a>>,
     pub children: Option<Vec<NodeID>>,
 }
 
+/// A headline's TODO keyword, along with the state it represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TodoKeyword<'a> {
+    pub name: &'a str,
+    pub state: TodoState,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Priority {
     A,
@@ -47,43 +54,29 @@ pub enum Tag<'a> {
     Loc(NodeID),
 }
 
-/// Enum of possible headline levels
+/// The depth of a headline, i.e. its number of stars.
+///
+/// Always at least 1, with no upper bound.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub enum HeadingLevel {
-    One,
-    Two,
-    Three,
-    Four,
-    Five,
-    Six,
-}
+pub struct HeadingLevel(usize);
+
+impl HeadingLevel {
+    /// Returns `None` if `level` is 0.
+    pub fn new(level: usize) -> Option<Self> {
+        (level != 0).then_some(Self(level))
+    }
 
-// Implemented not via `TryFrom` so that `MatchError` can be private
-// while keeping the struct Public
-fn try_heading_levelfrom(value: usize) -> Result<HeadingLevel> {
-    match value {
-        1 => Ok(HeadingLevel::One),
-        2 => Ok(HeadingLevel::Two),
-        3 => Ok(HeadingLevel::Three),
-        4 => Ok(HeadingLevel::Four),
-        5 => Ok(HeadingLevel::Five),
-        6 => Ok(HeadingLevel::Six),
-        _ => Err(MatchError::InvalidLogic),
+    pub fn get(self) -> usize {
+        self.0
     }
 }
 
-impl From<HeadingLevel> for u8 {
+impl From<HeadingLevel> for usize {
     fn from(value: HeadingLevel) -> Self {
-        match value {
-            HeadingLevel::One => 1,
-            HeadingLevel::Two => 2,
-            HeadingLevel::Three => 3,
-            HeadingLevel::Four => 4,
-            HeadingLevel::Five => 5,
-            HeadingLevel::Six => 6,
-        }
+        value.0
     }
 }
+
 impl<'a> Parseable<'a> for Heading<'a> {
     fn parse(
         parser: &mut Parser<'a>,
@@ -100,12 +93,13 @@ impl<'a> Parseable<'a> for Heading<'a> {
         // guaranteed to allocate since this is a valid headline. Setup the id
         let reserved_id = parser.pool.reserve_id();
 
-        let keyword: Option<&str> = if let Ok(keyword_match) = Heading::parse_keyword(cursor) {
-            cursor.move_to(keyword_match.end);
-            Some(keyword_match.obj)
-        } else {
-            None
-        };
+        let keyword =
+            if let Ok(keyword_match) = Heading::parse_keyword(&parser.todo_keywords, cursor) {
+                cursor.move_to(keyword_match.end);
+                Some(keyword_match.obj)
+            } else {
+                None
+            };
 
         let priority: Option<Priority> = if let Ok(prio_match) = Heading::parse_priority(cursor) {
             cursor.move_to(prio_match.end);
@@ -144,6 +138,13 @@ impl<'a> Parseable<'a> for Heading<'a> {
 
         // Handle subelements
 
+        let planning = if let Ok(ret) = parse_planning(cursor) {
+            cursor.index = ret.end;
+            Some(Box::new(ret.obj))
+        } else {
+            None
+        };
+
         let properties = if let Ok(ret) = parse_property(cursor) {
             cursor.index = ret.end;
             Some(ret.obj)
@@ -155,7 +156,7 @@ impl<'a> Parseable<'a> for Heading<'a> {
 
         while let Ok(element_id) = parse_element(parser, cursor, Some(reserved_id), parse_opts) {
             if let Expr::Heading(ref mut heading) = parser.pool[element_id].obj {
-                if u8::from(heading_level) < u8::from(heading.heading_level) {
+                if heading_level < heading.heading_level {
                     if let Some(tag_vec) = &mut heading.tags {
                         tag_vec.push(Tag::Loc(reserved_id));
                     } else {
@@ -166,6 +167,9 @@ impl<'a> Parseable<'a> for Heading<'a> {
                 }
             }
 
+            // headings are cached while parsing a previous sibling, with that sibling as
+            // their parent
+            parser.pool[element_id].parent = Some(reserved_id);
             section_vec.push(element_id);
             cursor.move_to(parser.pool[element_id].end);
         }
@@ -184,6 +188,7 @@ impl<'a> Parseable<'a> for Heading<'a> {
                 title,
                 tags,
                 children,
+                planning,
                 properties,
             },
             start,
@@ -197,13 +202,14 @@ impl<'a> Parseable<'a> for Heading<'a> {
 }
 
 impl<'a> Heading<'a> {
-    fn parse_stars(cursor: Cursor) -> Result<Match<HeadingLevel>> {
+    pub(crate) fn parse_stars(cursor: Cursor) -> Result<Match<HeadingLevel>> {
         let ret = cursor.fn_while(|chr: u8| chr == STAR)?;
 
         if cursor[ret.end] != SPACE {
             Err(MatchError::InvalidLogic)
         } else {
-            let heading_level: HeadingLevel = try_heading_levelfrom(ret.end - cursor.index)?;
+            let heading_level =
+                HeadingLevel::new(ret.end - cursor.index).ok_or(MatchError::InvalidLogic)?;
             Ok(Match {
                 start: cursor.index,
                 end: ret.end,
@@ -213,29 +219,26 @@ impl<'a> Heading<'a> {
         }
     }
 
-    fn parse_keyword(mut cursor: Cursor) -> Result<Match<&str>> {
+    pub(crate) fn parse_keyword(
+        todo_keywords: &TodoKeywords,
+        mut cursor: Cursor<'a>,
+    ) -> Result<Match<TodoKeyword<'a>>> {
         let start = cursor.index;
         cursor.skip_ws();
 
-        for (i, val) in ORG_TODO_KEYWORDS.iter().enumerate() {
-            // TODO: read up to a whitespace and determine if it's in phf set for keywords
-            // this is currently O(n), we can make it O(1)
-            if cursor.word(val).is_ok() {
-                // keep going in if not whitespace
-                // because a keyword might be a subset of another,,,
-                if cursor.try_curr()?.is_ascii_whitespace() {
-                    return Ok(Match {
-                        start,
-                        end: cursor.index, // don't move 1 ahead, in case it's a newline
-                        obj: val,
-                    });
-                } else {
-                    cursor.index -= val.len();
-                }
-            }
-        }
+        let word = cursor.fn_until(|chr: u8| chr.is_ascii_whitespace())?;
+        let state = todo_keywords
+            .state(word.obj)
+            .ok_or(MatchError::InvalidLogic)?;
 
-        Err(MatchError::InvalidLogic)
+        Ok(Match {
+            start,
+            end: word.end, // don't move 1 ahead, in case it's a newline
+            obj: TodoKeyword {
+                name: word.obj,
+                state,
+            },
+        })
     }
 
     // Recognizes the following patterns:
@@ -243,7 +246,7 @@ impl<'a> Heading<'a> {
     // [#1]
     // [#12]
     // TODO: we don't respect the 65 thing for numbers
-    fn parse_priority(mut cursor: Cursor) -> Result<Match<Priority>> {
+    pub(crate) fn parse_priority(mut cursor: Cursor) -> Result<Match<Priority>> {
         let start = cursor.index;
         cursor.skip_ws();
         // TODO: check if this is true
@@ -283,7 +286,7 @@ impl<'a> Heading<'a> {
         })
     }
 
-    fn parse_tag(mut cursor: Cursor) -> Match<Option<Vec<Tag>>> {
+    pub(crate) fn parse_tag(mut cursor: Cursor) -> Match<Option<Vec<Tag>>> {
         // we parse tags backwards
         let start = cursor.index;
         cursor.adv_till_byte(NEWLINE);
@@ -338,7 +341,7 @@ impl<'a> Heading<'a> {
         // we reached the start element, without hitting a space. no tags
     }
 
-    fn parse_title(
+    pub(crate) fn parse_title(
         parser: &mut Parser<'a>,
         cursor: Cursor<'a>,
         mut title_end: usize,
@@ -378,7 +381,23 @@ impl<'a> Heading<'a> {
             }
 
             let title_entry = cursor.clamp(title_start, top_off);
-            let target = Some(parser.generate_target(title_entry));
+            // statistics cookies change as tasks are done, so they're left out of the id,
+            // along with the space separating them from the rest of the title
+            let mut id_source = String::new();
+            let mut prev_end = title_start;
+            for id in &title_vec {
+                let node = &parser.pool[*id];
+                if let Expr::StatisticsCookie(_) = node.obj {
+                    let before = cursor.clamp(prev_end, node.start);
+                    id_source.push_str(before.strip_suffix(' ').unwrap_or(before));
+                    prev_end = node.end;
+                    if node.start == title_start && cursor.get(prev_end) == Some(&SPACE) {
+                        prev_end += 1;
+                    }
+                }
+            }
+            id_source.push_str(cursor.clamp(prev_end.min(top_off), top_off));
+            let target = Some(parser.generate_target_from(title_entry, &id_source));
 
             Ok((Some((title_entry, title_vec)), target))
         }
@@ -389,8 +408,10 @@ impl<'a> Heading<'a> {
 mod tests {
     use std::borrow::Cow;
 
-    use crate::element::{HeadingLevel, PropertyDrawer, Tag};
+    use crate::element::{HeadingLevel, PropertyDrawer, Tag, TodoKeyword};
     use crate::node_pool::make_node_id;
+    use crate::settings::TodoState;
+    use crate::test_utils::heading_id;
     use crate::types::Expr;
     use crate::{expr_in_pool, parse_org};
     use pretty_assertions::assert_eq;
@@ -419,11 +440,12 @@ mod tests {
         assert_eq!(
             head,
             Heading {
-                heading_level: crate::element::HeadingLevel::One,
+                heading_level: HeadingLevel::new(1).unwrap(),
                 keyword: None,
                 priority: None,
                 title: None,
                 tags: None,
+                planning: None,
                 properties: None,
                 children: None,
             }
@@ -438,11 +460,12 @@ mod tests {
         assert_eq!(
             head,
             Heading {
-                heading_level: crate::element::HeadingLevel::Four,
+                heading_level: HeadingLevel::new(4).unwrap(),
                 keyword: None,
                 priority: None,
                 title: None,
                 tags: None,
+                planning: None,
                 properties: None,
                 children: None,
             }
@@ -450,12 +473,11 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
-    fn headline_too_many_stars() {
-        // panics because we'd unwrap on the case of no headings
+    fn headline_many_stars() {
         let input = "*********  \n";
 
         let head = get_head(input);
+        assert_eq!(head.heading_level.get(), 9);
     }
 
     #[test]
@@ -465,7 +487,7 @@ mod tests {
         assert_eq!(
             item,
             Heading {
-                heading_level: HeadingLevel::One,
+                heading_level: HeadingLevel::new(1).unwrap(),
                 keyword: None,
                 priority: None,
                 title: Some((
@@ -473,6 +495,7 @@ mod tests {
                     vec![make_node_id(2)]
                 )),
                 tags: None,
+                planning: None,
                 properties: None,
                 children: None
             }
@@ -487,11 +510,15 @@ mod tests {
         assert_eq!(
             head,
             Heading {
-                heading_level: crate::element::HeadingLevel::One,
-                keyword: Some("TODO"),
+                heading_level: HeadingLevel::new(1).unwrap(),
+                keyword: Some(TodoKeyword {
+                    name: "TODO",
+                    state: TodoState::Active
+                }),
                 priority: None,
                 title: None,
                 tags: None,
+                planning: None,
                 properties: None,
                 children: None,
             }
@@ -506,11 +533,12 @@ mod tests {
         assert_eq!(
             head,
             Heading {
-                heading_level: crate::element::HeadingLevel::One,
+                heading_level: HeadingLevel::new(1).unwrap(),
                 keyword: None,
                 priority: Some(crate::element::Priority::A),
                 title: None,
                 tags: None,
+                planning: None,
                 properties: None,
                 children: None,
             }
@@ -525,11 +553,12 @@ mod tests {
         assert_eq!(
             head,
             Heading {
-                heading_level: crate::element::HeadingLevel::One,
+                heading_level: HeadingLevel::new(1).unwrap(),
                 keyword: None,
                 priority: None,
                 title: Some(("cat", vec![make_node_id(2)])),
                 tags: Some(vec![Tag::Raw("tagone")]),
+                planning: None,
                 properties: None,
                 children: None,
             }
@@ -544,11 +573,12 @@ mod tests {
         assert_eq!(
             head,
             Heading {
-                heading_level: crate::element::HeadingLevel::One,
+                heading_level: HeadingLevel::new(1).unwrap(),
                 keyword: None,
                 priority: None,
                 title: Some(("test", vec![make_node_id(2)])),
                 tags: Some(vec![Tag::Raw("tagtwo"), Tag::Raw("tagone")]),
+                planning: None,
                 properties: None,
                 children: None,
             }
@@ -583,11 +613,15 @@ mod tests {
         assert_eq!(
             head,
             Heading {
-                heading_level: crate::element::HeadingLevel::One,
-                keyword: Some("TODO"),
+                heading_level: HeadingLevel::new(1).unwrap(),
+                keyword: Some(TodoKeyword {
+                    name: "TODO",
+                    state: TodoState::Active
+                }),
                 priority: Some(crate::element::Priority::A),
                 title: None,
                 tags: None,
+                planning: None,
                 properties: None,
                 children: None,
             }
@@ -660,6 +694,22 @@ more subcontent
             got_prop,
             &PropertyDrawer::from([("name", Cow::from("val val again"))])
         );
+
+        let input = r"
+* a
+:properties:
+:NAME+: first
+:name: val
+:Name+: again
+:end:
+
+";
+        let head = get_head(input);
+        let got_prop = head.properties.as_ref().unwrap();
+        assert_eq!(
+            got_prop,
+            &PropertyDrawer::from([("name", Cow::from("val first again"))])
+        );
     }
 
     #[test]
@@ -680,7 +730,7 @@ aaaa";
         let p = parse_org(input);
         let item = expr_in_pool!(p, Heading).unwrap();
 
-        assert_eq!(item.heading_level, HeadingLevel::Three);
+        assert_eq!(item.heading_level, HeadingLevel::new(3).unwrap());
     }
 
     #[test]
@@ -692,14 +742,35 @@ aaaa";
         assert_eq!(
             item,
             &Heading {
-                heading_level: HeadingLevel::Three,
+                heading_level: HeadingLevel::new(3).unwrap(),
                 keyword: None,
                 priority: None,
                 title: Some(("g", vec![make_node_id(2)])),
                 tags: None,
+                planning: None,
                 properties: None,
                 children: None
             }
         );
     }
+
+    #[test]
+    fn sibling_heading_parent() {
+        let input = r"
+* one
+** two
+** two again
+* one again
+";
+        let parsed = parse_org(input);
+
+        assert_eq!(
+            parsed.pool[heading_id(&parsed, "two again")].parent,
+            Some(heading_id(&parsed, "one"))
+        );
+        assert_eq!(
+            parsed.pool[heading_id(&parsed, "one again")].parent,
+            Some(parsed.pool.root_id())
+        );
+    }
 }
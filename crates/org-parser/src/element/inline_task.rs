@@ -0,0 +1,225 @@
+use crate::constants::{NEWLINE, SPACE, STAR};
+use crate::node_pool::NodeID;
+use crate::parse::parse_element;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+use crate::utils::bytes_to_str;
+
+use super::{Heading, HeadingLevel, Priority, Tag, TodoKeyword};
+
+/// A task nested within a section, which does not start a section of its own.
+///
+/// ```example
+/// *************** TODO call back :phone:
+/// Optional contents
+/// *************** END
+/// ```
+///
+/// Without a closing `END` line, the task is only made up of its headline.
+/// The number of stars it takes is set by
+/// [`ParseSettings::inline_task_min_level`](crate::ParseSettings::inline_task_min_level).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineTask<'a> {
+    pub heading_level: HeadingLevel,
+    pub keyword: Option<TodoKeyword<'a>>,
+    pub priority: Option<Priority>,
+    pub title: Option<(&'a str, Vec<NodeID>)>,
+    pub tags: Option<Vec<Tag<'a>>>,
+    /// `None` when there is no `END` line
+    pub children: Option<Vec<NodeID>>,
+}
+
+impl<'a> Parseable<'a> for InlineTask<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+
+        let stars = Heading::parse_stars(cursor)?;
+        let heading_level = stars.obj;
+        let min_level = parser.inline_task_min_level;
+        if heading_level.get() < min_level {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.move_to(stars.end);
+
+        let reserved_id = parser.pool.reserve_id();
+
+        let keyword =
+            if let Ok(keyword_match) = Heading::parse_keyword(&parser.todo_keywords, cursor) {
+                cursor.move_to(keyword_match.end);
+                Some(keyword_match.obj)
+            } else {
+                None
+            };
+
+        let priority = if let Ok(prio_match) = Heading::parse_priority(cursor) {
+            cursor.move_to(prio_match.end);
+            Some(prio_match.obj)
+        } else {
+            None
+        };
+
+        let tag_match = Heading::parse_tag(cursor);
+        let tags = tag_match.obj;
+
+        let (title, target) = if let Ok((title, target)) =
+            Heading::parse_title(parser, cursor, tag_match.start, reserved_id, parse_opts)
+        {
+            (title, target)
+        } else {
+            (None, None)
+        };
+
+        // tag_match.end is past the newline, which may not exist on the last line
+        cursor.move_to(tag_match.end.min(cursor.byte_arr.len()));
+
+        let children = if let Some((end_start, end)) = find_end(cursor, min_level) {
+            let mut children: Vec<NodeID> = Vec::new();
+            let mut temp_cursor = cursor.cut_off(end_start);
+            while let Ok(element_id) =
+                parse_element(parser, temp_cursor, Some(reserved_id), ParseOpts::default())
+            {
+                children.push(element_id);
+                temp_cursor.move_to(parser.pool[element_id].end);
+            }
+            cursor.move_to(end);
+            Some(children)
+        } else {
+            None
+        };
+
+        let ret_id = parser.alloc_with_id(
+            Self {
+                heading_level,
+                keyword,
+                priority,
+                title,
+                tags,
+                children,
+            },
+            start,
+            cursor.index,
+            parent,
+            reserved_id,
+        );
+        parser.pool[ret_id].id_target = target;
+        Ok(ret_id)
+    }
+}
+
+/// Finds the `END` line closing the task, returning its start and end.
+///
+/// Any headline in between means the task has no contents.
+fn find_end(cursor: Cursor, min_level: usize) -> Option<(usize, usize)> {
+    let mut line_start = cursor.index;
+    for line in cursor.rest().split_inclusive(|&chr| chr == NEWLINE) {
+        let stars = line.iter().take_while(|&&chr| chr == STAR).count();
+        if stars >= min_level && bytes_to_str(&line[stars..]).trim() == "END" {
+            return Some((line_start, line_start + line.len()));
+        } else if stars > 0 && line.get(stars) == Some(&SPACE) {
+            return None;
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org, parse_org_with, ParseSettings};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_inline_task() {
+        let input = "*************** TODO [#A] call back :phone:\n";
+        let parsed = parse_org(input);
+        let task = expr_in_pool!(parsed, InlineTask).unwrap();
+
+        assert_eq!(task.heading_level.get(), 15);
+        assert_eq!(task.keyword.unwrap().name, "TODO");
+        assert_eq!(task.title.as_ref().unwrap().0, "call back");
+        assert_eq!(task.children, None);
+        assert!(expr_in_pool!(parsed, Heading).is_none());
+    }
+
+    #[test]
+    fn inline_task_with_end() {
+        let input = r"
+* parent
+before
+*************** TODO task
+contents
+*************** END
+after
+** child
+";
+        let parsed = parse_org(input);
+        let task = expr_in_pool!(parsed, InlineTask).unwrap();
+        let children = task.children.as_ref().unwrap();
+        assert_eq!(children.len(), 1);
+        assert!(matches!(parsed.pool[children[0]].obj, Expr::Paragraph(_)));
+
+        // the task doesn't end the parent's section
+        let parent = expr_in_pool!(parsed, Heading).unwrap();
+        let kinds: Vec<&str> = parent
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|id| match parsed.pool[*id].obj {
+                Expr::Paragraph(_) => "paragraph",
+                Expr::InlineTask(_) => "task",
+                Expr::Heading(_) => "heading",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["paragraph", "task", "paragraph", "heading"]);
+    }
+
+    #[test]
+    fn inline_task_unclosed() {
+        let input = r"
+*************** first
+not contents
+** heading
+*************** END
+";
+        let parsed = parse_org(input);
+        let task = expr_in_pool!(parsed, InlineTask).unwrap();
+        assert_eq!(task.children, None);
+        assert_eq!(task.title.as_ref().unwrap().0, "first\n");
+    }
+
+    #[test]
+    fn inline_task_at_eof() {
+        let parsed = parse_org("*************** task");
+        let task = expr_in_pool!(parsed, InlineTask).unwrap();
+        assert_eq!(task.children, None);
+
+        let parsed = parse_org("*************** END");
+        assert!(expr_in_pool!(parsed, InlineTask).is_some());
+
+        let parsed = parse_org("*************** task\ncontents\n*************** END");
+        let task = expr_in_pool!(parsed, InlineTask).unwrap();
+        assert_eq!(task.children.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn inline_task_min_level() {
+        let settings = ParseSettings {
+            inline_task_min_level: 4,
+            ..Default::default()
+        };
+        let parsed = parse_org_with("* parent\n**** TODO task\ncontents\n**** END\n", settings);
+        let task = expr_in_pool!(parsed, InlineTask).unwrap();
+        assert_eq!(task.heading_level.get(), 4);
+        assert_eq!(task.children.as_ref().unwrap().len(), 1);
+
+        let parsed = parse_org("**** TODO task\n");
+        assert!(expr_in_pool!(parsed, InlineTask).is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::constants::{COLON, HYPHEN, LBRACK, NEWLINE, PERIOD, PLUS, RBRACK, RPAREN, SPACE, STAR};
 use crate::node_pool::NodeID;
+use crate::object::{parse_timestamp, Timestamp};
 use crate::parse::parse_element;
 use crate::types::{Cursor, Expr, MatchError, ParseOpts, Parseable, Parser, Result};
 use crate::utils::Match;
@@ -11,6 +12,8 @@ pub struct Item<'a> {
     pub counter_set: Option<&'a str>,
     pub check_box: Option<CheckBox>,
     pub tag: Option<&'a str>,
+    /// Set when the item is a state change note
+    pub state_change: Option<Box<StateChange<'a>>>,
     pub children: Vec<NodeID>,
 }
 
@@ -54,6 +57,15 @@ impl<'a> Parseable<'a> for Item<'a> {
             None
         };
 
+        // the note's text is still parsed as regular contents
+        let state_change = if let BulletKind::Unordered = bullet {
+            parse_state_change(cursor)
+                .ok()
+                .map(|state_match| Box::new(state_match.obj))
+        } else {
+            None
+        };
+
         let reserve_id = parser.pool.reserve_id();
         let mut children: Vec<NodeID> = Vec::new();
         let mut blank_obj: Option<NodeID> = None;
@@ -106,6 +118,7 @@ impl<'a> Parseable<'a> for Item<'a> {
                 counter_set,
                 check_box,
                 tag,
+                state_change,
                 children,
             },
             start,
@@ -263,6 +276,56 @@ fn parse_tag(mut cursor: Cursor) -> Result<Match<&str>> {
     })
 }
 
+/// A state change note, typically found in a `:LOGBOOK:` drawer.
+///
+/// ```example
+/// - State "DONE"       from "TODO"       [2023-08-16 Wed 10:22]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChange<'a> {
+    pub to: &'a str,
+    /// `None` when the heading had no previous keyword
+    pub from: Option<&'a str>,
+    pub timestamp: Timestamp<'a>,
+}
+
+fn parse_state_change(mut cursor: Cursor) -> Result<Match<StateChange>> {
+    let start = cursor.index;
+    cursor.skip_ws();
+    cursor.word("State")?;
+    cursor.skip_ws();
+
+    let to = parse_quoted(&mut cursor)?;
+    cursor.skip_ws();
+    cursor.word("from")?;
+    cursor.skip_ws();
+    let from = if cursor.try_curr()? == b'"' {
+        Some(parse_quoted(&mut cursor)?).filter(|from| !from.is_empty())
+    } else {
+        None
+    };
+    cursor.skip_ws();
+
+    let stamp_match = parse_timestamp(cursor)?;
+    Ok(Match {
+        start,
+        end: stamp_match.end,
+        obj: StateChange {
+            to,
+            from,
+            timestamp: stamp_match.obj,
+        },
+    })
+}
+
+fn parse_quoted<'a>(cursor: &mut Cursor<'a>) -> Result<&'a str> {
+    cursor.word("\"")?;
+    let inner = cursor.fn_until(|chr| chr == b'"' || chr == NEWLINE)?;
+    cursor.move_to(inner.end);
+    cursor.word("\"")?;
+    Ok(inner.obj)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CheckBox {
     /// [-]
@@ -379,4 +442,26 @@ mod tests {
         let item = expr_in_pool!(ret, Item).unwrap();
         assert!(matches!(item.bullet, BulletKind::Ordered(CounterKind::Letter(b'a'))));
     }
+
+    #[test]
+    fn state_change() {
+        let input = r#"- State "DONE"       from "TODO"       [2023-08-16 Wed 10:22]"#;
+        let ret = parse_org(input);
+        let item = expr_in_pool!(ret, Item).unwrap();
+        let state = item.state_change.as_ref().unwrap();
+
+        assert_eq!(state.to, "DONE");
+        assert_eq!(state.from, Some("TODO"));
+        assert_eq!(state.timestamp.start.date.day, 16);
+        // the note is still regular text
+        assert!(!item.children.is_empty());
+
+        let input = r#"- State "TODO"       from              [2023-08-16 Wed 10:22]"#;
+        let ret = parse_org(input);
+        let item = expr_in_pool!(ret, Item).unwrap();
+        let state = item.state_change.as_ref().unwrap();
+
+        assert_eq!(state.to, "TODO");
+        assert_eq!(state.from, None);
+    }
 }
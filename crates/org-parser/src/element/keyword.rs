@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::constants::{DOLLAR, HYPHEN, NEWLINE, UNDERSCORE};
 use crate::node_pool::NodeID;
 use crate::parse::parse_element;
@@ -43,6 +45,7 @@ impl<'a> Parseable<'a> for Keyword<'a> {
             // :key val :key val :key val
             let val_start_ind = cursor.index;
             let (mut cursor, new_attrs) = process_attrs(cursor)?;
+            let new_attrs: HashMap<&str, &str> = new_attrs.into_iter().collect();
             let val = cursor.clamp_backwards(val_start_ind);
             // skip past newline
             cursor.next();
@@ -119,6 +122,7 @@ impl<'a> Parseable<'a> for Keyword<'a> {
                             cursor.index = node.end;
                         } else {
                             parser.pool[child_id].id_target = Some(parser.generate_target(val));
+                            parser.names.insert(val, child_id);
                             break Some(child_id);
                         }
                     } else {
@@ -167,7 +171,7 @@ impl<'a> Parseable<'a> for Keyword<'a> {
         let val = cursor.fn_until(|chr: u8| chr == b'\n')?;
         let trimmed = val.obj.trim_ascii();
 
-        parser.keywords.insert(key_word.obj, trimmed);
+        parser.keywords.push(key_word.obj, trimmed);
         Ok(parser.alloc(
             Keyword {
                 key: key_word.obj,
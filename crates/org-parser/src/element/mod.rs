@@ -3,32 +3,47 @@
 //! Elements are typically larger and comprise broader sections of text than objects.
 //! They include structues such as: [`Heading`], [`PlainList`], etc...
 
+mod babel_call;
 mod block;
+mod clock;
 mod comment;
+mod diary_sexp;
 mod drawer;
+mod dynamic_block;
+mod fixed_width;
 mod footnote_def;
 mod heading;
+mod inline_task;
 mod item;
 mod keyword;
 mod latex_env;
 mod paragraph;
 mod plain_list;
+mod planning;
 mod table;
 
-pub use block::Block;
+pub use babel_call::BabelCall;
+pub use block::{Block, BlockSwitches, Coderef, LineNumbers};
+pub use clock::Clock;
 pub use comment::Comment;
+pub use diary_sexp::DiarySexp;
 pub(crate) use drawer::parse_property;
 pub use drawer::Drawer;
 pub use drawer::PropertyDrawer;
+pub use dynamic_block::DynamicBlock;
+pub use fixed_width::FixedWidth;
 pub use footnote_def::FootnoteDef;
 pub use heading::Heading;
 pub use heading::HeadingLevel;
 pub use heading::Priority;
 pub use heading::Tag;
+pub use heading::TodoKeyword;
+pub use inline_task::InlineTask;
 pub use item::BulletKind;
 pub use item::CheckBox;
 pub use item::CounterKind;
 pub use item::Item;
+pub use item::StateChange;
 pub use keyword::Affiliated;
 pub use keyword::ArgNumOrText;
 pub use keyword::Keyword;
@@ -37,5 +52,9 @@ pub use latex_env::LatexEnv;
 pub use paragraph::Paragraph;
 pub use plain_list::ListKind;
 pub use plain_list::PlainList;
+pub(crate) use planning::parse_planning;
+pub use planning::Planning;
+pub use table::ColumnAlign;
 pub use table::Table;
+pub use table::TableColumn;
 pub use table::TableRow;
@@ -0,0 +1,130 @@
+use crate::constants::NEWLINE;
+use crate::object::{parse_timestamp, Timestamp};
+use crate::types::{Cursor, MatchError, Result};
+use crate::utils::Match;
+
+/// The planning line directly following a [`Heading`](crate::element::Heading)'s headline.
+///
+/// ```example
+/// * TODO write report
+/// DEADLINE: <2023-08-20 Sun> SCHEDULED: <2023-08-16 Wed>
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Planning<'a> {
+    pub scheduled: Option<Timestamp<'a>>,
+    pub deadline: Option<Timestamp<'a>>,
+    pub closed: Option<Timestamp<'a>>,
+}
+
+pub(crate) fn parse_planning(mut cursor: Cursor) -> Result<Match<Planning>> {
+    cursor.curr_valid()?;
+    let start = cursor.index;
+    let mut planning = Planning::default();
+
+    loop {
+        cursor.skip_ws();
+        match cursor.try_curr() {
+            Ok(NEWLINE) => {
+                cursor.next();
+                break;
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+
+        let slot = if cursor.word("SCHEDULED:").is_ok() {
+            &mut planning.scheduled
+        } else if cursor.word("DEADLINE:").is_ok() {
+            &mut planning.deadline
+        } else if cursor.word("CLOSED:").is_ok() {
+            &mut planning.closed
+        } else {
+            return Err(MatchError::InvalidLogic);
+        };
+
+        cursor.skip_ws();
+        let stamp_match = parse_timestamp(cursor)?;
+        cursor.move_to(stamp_match.end);
+        *slot = Some(stamp_match.obj);
+    }
+
+    if planning == Planning::default() {
+        return Err(MatchError::InvalidLogic);
+    }
+
+    Ok(Match {
+        start,
+        end: cursor.index,
+        obj: planning,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::TimeUnit;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+
+    #[test]
+    fn basic_planning() {
+        let input = r"
+* TODO one
+  DEADLINE: <2023-08-20 Sun +1w> SCHEDULED: <2023-08-16 Wed>
+text
+";
+        let parsed = parse_org(input);
+        let head = expr_in_pool!(parsed, Heading).unwrap();
+        let planning = head.planning.as_ref().unwrap();
+
+        assert_eq!(planning.deadline.as_ref().unwrap().start.date.day, 20);
+        assert_eq!(
+            planning.deadline.as_ref().unwrap().repeater.unwrap().unit,
+            TimeUnit::Week
+        );
+        assert_eq!(planning.scheduled.as_ref().unwrap().start.date.day, 16);
+        assert_eq!(planning.closed, None);
+        // the planning line isn't part of the section
+        assert!(expr_in_pool!(parsed, Timestamp).is_none());
+    }
+
+    #[test]
+    fn planning_with_properties() {
+        let input = r"
+* DONE one
+CLOSED: [2023-08-16 Wed 10:22]
+:properties:
+:name: val
+:end:
+";
+        let parsed = parse_org(input);
+        let head = expr_in_pool!(parsed, Heading).unwrap();
+
+        assert!(head.planning.as_ref().unwrap().closed.is_some());
+        assert!(head.properties.is_some());
+    }
+
+    #[test]
+    fn planning_not_first_line() {
+        let input = r"
+* one
+text
+SCHEDULED: <2023-08-16 Wed>
+";
+        let parsed = parse_org(input);
+        let head = expr_in_pool!(parsed, Heading).unwrap();
+
+        assert_eq!(head.planning, None);
+    }
+
+    #[test]
+    fn planning_trailing_text() {
+        let input = r"
+* one
+SCHEDULED: <2023-08-16 Wed> and more
+";
+        let parsed = parse_org(input);
+        let head = expr_in_pool!(parsed, Heading).unwrap();
+
+        assert_eq!(head.planning, None);
+    }
+}
@@ -1,17 +1,64 @@
 use crate::constants::{HYPHEN, NEWLINE};
 use crate::node_pool::NodeID;
 use crate::object::TableCell;
+use crate::tblfm::{FormulaError, TableFormula};
 use crate::types::{Cursor, Expr, MarkupKind, ParseOpts, Parseable, Parser, Result};
 
 /// A table consisting of a collection of [`TableRow`]s
 ///
 /// | one | two |
 /// | three | four |
+/// #+TBLFM: $2=$1*2
 #[derive(Debug, Clone)]
-pub struct Table {
+pub struct Table<'a> {
     pub rows: usize,
     pub cols: usize,
     pub children: Vec<NodeID>,
+    /// The formulas of the first `#+TBLFM:` line, or why they couldn't be parsed
+    pub formulas: std::result::Result<Vec<TableFormula<'a>>, FormulaError>,
+    /// The value of every `#+TBLFM:` line following the table
+    pub tblfm: Vec<&'a str>,
+    /// The rows between rules, leaving out rows of cookies and rows marked by the special
+    /// column as not being exported. When there are several groups, the first is the header.
+    pub groups: Vec<Vec<NodeID>>,
+    /// The alignment, width and grouping of each column, not counting the special column
+    pub columns: Vec<TableColumn>,
+    /// Whether the first column only holds markers such as `!`, `^`, `#` or `/`
+    /// that describe its row
+    pub special_column: bool,
+}
+
+impl Table<'_> {
+    /// The rows above the first rule, provided the table continues below it.
+    pub fn header(&self) -> Option<&[NodeID]> {
+        (self.groups.len() > 1).then(|| &self.groups[0][..])
+    }
+}
+
+/// The horizontal alignment of a [`TableColumn`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnAlign {
+    Left,
+    Center,
+    Right,
+}
+
+/// The layout of a column, set by a row of cookies such as `| <l> | <r10> |`.
+///
+/// Column groups are set by a row starting with `/`, where `<` starts a group,
+/// `>` ends one and `<>` is a group of its own. The row can also hold cookies:
+///
+/// ```text
+/// | / | <  | <r> | >  | <> |
+/// |   | a  | b   | c  | d  |
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableColumn {
+    /// Without a cookie, columns that are mostly numbers are aligned right
+    pub align: ColumnAlign,
+    pub width: Option<usize>,
+    /// Whether a column group starts at this column
+    pub group_start: bool,
 }
 
 /// A row of a [`Table`] consisting of [`TableCell`]s or a [`TableRow::Rule`].
@@ -37,7 +84,7 @@ pub enum TableRow {
     Standard(Vec<NodeID>),
 }
 
-impl<'a> Parseable<'a> for Table {
+impl<'a> Parseable<'a> for Table<'a> {
     fn parse(
         parser: &mut Parser<'a>,
         mut cursor: Cursor<'a>,
@@ -64,11 +111,27 @@ impl<'a> Parseable<'a> for Table {
             cursor.index = parser.pool[row_id].end;
         }
 
+        let mut tblfm = Vec::new();
+        // a rule row on the last line ends past the input
+        while let Some((line, end)) = parser.source.get(cursor.index..).and_then(tblfm_line) {
+            tblfm.push(line);
+            cursor.index += end;
+        }
+        let formulas = tblfm
+            .first()
+            .map_or(Ok(Vec::new()), |line| TableFormula::parse_line(line));
+        let (groups, columns, special_column) = table_layout(parser, &children, cols);
+
         Ok(parser.alloc_with_id(
             Self {
                 rows,
                 cols,
                 children,
+                formulas,
+                tblfm,
+                groups,
+                columns,
+                special_column,
             },
             start,
             cursor.index,
@@ -78,6 +141,170 @@ impl<'a> Parseable<'a> for Table {
     }
 }
 
+/// Markers in the special column for rows that aren't exported
+const HIDDEN_MARKERS: [&str; 5] = ["!", "^", "_", "$", "/"];
+
+/// Splits the rows of a table into groups and finds the layout of its columns.
+fn table_layout(
+    parser: &Parser,
+    rows: &[NodeID],
+    cols: usize,
+) -> (Vec<Vec<NodeID>>, Vec<TableColumn>, bool) {
+    let cells = rows
+        .iter()
+        .map(|id| match &parser.pool[*id].obj {
+            Expr::TableRow(TableRow::Standard(cells)) => {
+                Some(cells.iter().map(|id| cell_text(parser, *id)).collect())
+            }
+            _ => None,
+        })
+        .collect::<Vec<Option<Vec<&str>>>>();
+
+    let is_marker = |cell: &str| matches!(cell, "" | "#" | "*") || HIDDEN_MARKERS.contains(&cell);
+    let first_cells = || {
+        cells
+            .iter()
+            .flatten()
+            .filter_map(|row| row.first().copied())
+    };
+    let special_column =
+        cols > 1 && first_cells().all(is_marker) && first_cells().any(|cell| !cell.is_empty());
+    let skip = usize::from(special_column);
+
+    let mut columns = vec![
+        TableColumn {
+            align: ColumnAlign::Left,
+            width: None,
+            group_start: false,
+        };
+        cols - skip
+    ];
+    let mut aligned = vec![false; columns.len()];
+    let mut set_cookie = |i: usize, cell: &str| {
+        if let Some((align, width)) = parse_cookie(cell) {
+            if let Some(align) = align {
+                columns[i].align = align;
+                aligned[i] = true;
+            }
+            columns[i].width = width.or(columns[i].width);
+        }
+    };
+    // column groups, set in rows marked with `/`
+    let mut group_starts = vec![false; cols - skip];
+    // indices of rows within each group
+    let mut groups = vec![Vec::new()];
+    for (index, row) in cells.iter().enumerate() {
+        let Some(row) = row else {
+            groups.push(Vec::new());
+            continue;
+        };
+        let marker = if special_column {
+            row.first().copied()
+        } else {
+            None
+        };
+        if marker == Some("/") {
+            for (i, cell) in row.iter().skip(1).enumerate() {
+                // `<` starts a group and `>` ends one, `<>` is a group of its own
+                if matches!(*cell, "<" | "<>") {
+                    group_starts[i] = true;
+                }
+                if matches!(*cell, ">" | "<>") && i + 1 < group_starts.len() {
+                    group_starts[i + 1] = true;
+                }
+                set_cookie(i, cell);
+            }
+            continue;
+        }
+        if marker.is_some_and(|marker| HIDDEN_MARKERS.contains(&marker)) {
+            continue;
+        }
+
+        let row = &row[skip.min(row.len())..];
+        if row.iter().any(|cell| !cell.is_empty())
+            && row
+                .iter()
+                .all(|cell| cell.is_empty() || parse_cookie(cell).is_some())
+        {
+            for (i, cell) in row.iter().enumerate() {
+                set_cookie(i, cell);
+            }
+            continue;
+        }
+        groups.last_mut().unwrap().push(index);
+    }
+    groups.retain(|group| !group.is_empty());
+
+    // columns default to the right when most of their fields are numbers
+    let body = if groups.len() > 1 {
+        &groups[1..]
+    } else {
+        &groups[..]
+    };
+    for (i, column) in columns.iter_mut().enumerate() {
+        column.group_start = group_starts[i] || i == 0;
+        if aligned[i] {
+            continue;
+        }
+        let fields = body
+            .iter()
+            .flatten()
+            .filter_map(|index| cells[*index].as_ref()?.get(i + skip))
+            .filter(|cell| !cell.is_empty())
+            .collect::<Vec<_>>();
+        let numbers = fields.iter().filter(|cell| is_number(cell)).count();
+        if !fields.is_empty() && numbers * 2 >= fields.len() {
+            column.align = ColumnAlign::Right;
+        }
+    }
+
+    let groups = groups
+        .into_iter()
+        .map(|group| group.into_iter().map(|index| rows[index]).collect())
+        .collect();
+    (groups, columns, special_column)
+}
+
+/// The text of a cell, without surrounding whitespace.
+fn cell_text<'a>(parser: &Parser<'a>, id: NodeID) -> &'a str {
+    let node = &parser.pool[id];
+    parser.source[node.start..node.end]
+        .trim_end_matches('|')
+        .trim()
+}
+
+/// Parses a cookie such as `<l>`, `<10>` or `<r10>` into an alignment and width.
+fn parse_cookie(cell: &str) -> Option<(Option<ColumnAlign>, Option<usize>)> {
+    let inner = cell.strip_prefix('<')?.strip_suffix('>')?;
+    let (align, width) = match inner.as_bytes().first() {
+        Some(b'l') => (Some(ColumnAlign::Left), &inner[1..]),
+        Some(b'c') => (Some(ColumnAlign::Center), &inner[1..]),
+        Some(b'r') => (Some(ColumnAlign::Right), &inner[1..]),
+        _ => (None, inner),
+    };
+    if width.is_empty() {
+        return align.map(|align| (Some(align), None));
+    }
+    Some((align, Some(width.parse().ok()?)))
+}
+
+fn is_number(cell: &str) -> bool {
+    let cell = cell.strip_suffix('%').unwrap_or(cell).replace(',', "");
+    cell.parse::<f64>().is_ok()
+}
+
+/// Matches a `#+TBLFM:` line at the start of `rest`, returning its value
+/// and the length of the line.
+fn tblfm_line(rest: &str) -> Option<(&str, usize)> {
+    let end = rest.find('\n').map_or(rest.len(), |i| i + 1);
+    let line = rest[..end].trim();
+    let value = line
+        .get(..8)
+        .filter(|key| key.eq_ignore_ascii_case("#+tblfm:"))
+        .map(|_| line[8..].trim())?;
+    Some((value, end))
+}
+
 impl<'a> Parseable<'a> for TableRow {
     fn parse(
         parser: &mut Parser<'a>,
@@ -136,6 +363,7 @@ impl<'a> Parseable<'a> for TableRow {
 
 #[cfg(test)]
 mod tests {
+    use super::{ColumnAlign, TableColumn};
     use crate::{expr_in_pool, parse_org, Expr};
 
     #[test]
@@ -281,4 +509,126 @@ word
         let tab = expr_in_pool!(pool, Table).unwrap();
         assert_eq!(tab.rows, 1);
     }
+
+    #[test]
+    fn table_formulas() {
+        let input = r"
+| a | b |   |
+| 1 | 2 |   |
+#+TBLFM: $3=$1+$2::@1$3=vsum(@2..@>)
+#+tblfm: $3=$1*$2
+after
+";
+
+        let pool = parse_org(input);
+        let tab = expr_in_pool!(pool, Table).unwrap();
+        assert_eq!(tab.tblfm, ["$3=$1+$2::@1$3=vsum(@2..@>)", "$3=$1*$2"]);
+        let formulas = tab.formulas.as_ref().unwrap();
+        assert_eq!(formulas.len(), 2);
+        assert_eq!(formulas[1].raw, "@1$3=vsum(@2..@>)");
+        assert!(expr_in_pool!(pool, Keyword).is_none());
+    }
+
+    #[test]
+    fn table_eof_partial_rule() {
+        let pool = parse_org("| a |\n|-");
+        let tab = expr_in_pool!(pool, Table).unwrap();
+        assert_eq!(tab.rows, 2);
+        assert!(tab.tblfm.is_empty());
+    }
+
+    #[test]
+    fn table_groups() {
+        let input = r"
+| name  | qty |
+|-------+-----|
+| <l10> |     |
+| a     |   1 |
+| b     |  20 |
+|-------+-----|
+| total |  21 |
+";
+
+        let pool = parse_org(input);
+        let tab = expr_in_pool!(pool, Table).unwrap();
+        assert_eq!(tab.groups.len(), 3);
+        assert_eq!(tab.header().unwrap().len(), 1);
+        assert_eq!(tab.groups[1].len(), 2);
+        assert!(!tab.special_column);
+        assert_eq!(
+            tab.columns,
+            [
+                TableColumn {
+                    align: ColumnAlign::Left,
+                    width: Some(10),
+                    group_start: true,
+                },
+                TableColumn {
+                    align: ColumnAlign::Right,
+                    width: None,
+                    group_start: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn table_special_column() {
+        let input = r"
+| / | <  | >  |    |
+| ! | x  | y  | z  |
+| # | 1  | 2  | <c> |
+|   | 3  | 4  | 5  |
+";
+
+        let pool = parse_org(input);
+        let tab = expr_in_pool!(pool, Table).unwrap();
+        assert!(tab.special_column);
+        assert!(tab.header().is_none());
+        // the `!` row of names isn't exported
+        assert_eq!(tab.groups[0].len(), 2);
+        assert_eq!(
+            tab.columns
+                .iter()
+                .map(|col| col.group_start)
+                .collect::<Vec<_>>(),
+            [true, false, true]
+        );
+    }
+
+    #[test]
+    fn table_group_row_cookies() {
+        let input = r"
+| / | <l> | <r10> | <> | <c> |
+|   | a   | 1     | x  | y   |
+";
+
+        let pool = parse_org(input);
+        let tab = expr_in_pool!(pool, Table).unwrap();
+        assert_eq!(
+            tab.columns,
+            [
+                TableColumn {
+                    align: ColumnAlign::Left,
+                    width: None,
+                    group_start: true,
+                },
+                TableColumn {
+                    align: ColumnAlign::Right,
+                    width: Some(10),
+                    group_start: false,
+                },
+                TableColumn {
+                    align: ColumnAlign::Left,
+                    width: None,
+                    group_start: true,
+                },
+                TableColumn {
+                    align: ColumnAlign::Center,
+                    width: None,
+                    group_start: true,
+                },
+            ]
+        );
+    }
 }
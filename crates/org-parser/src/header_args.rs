@@ -0,0 +1,617 @@
+//! Header arguments of source blocks, resolved from every level they can be set at.
+
+use std::collections::HashMap;
+
+use crate::element::Block;
+use crate::node_pool::NodeID;
+use crate::noweb::{NowebAction, NowebContext};
+use crate::types::{Expr, Parser};
+
+/// The header arguments that apply to a source block.
+///
+/// Header arguments can be set at several levels, where each level overrides
+/// the ones before it:
+///
+/// 1. the system defaults, see [`HeaderArgs::default`]
+/// 2. the `header-args` property
+/// 3. the `header-args:LANG` property
+/// 4. `#+HEADER:` keywords directly before the block
+/// 5. the `#+begin_src` line of the block
+///
+/// Both properties are inherited from ancestor headings and `#+PROPERTY:` lines,
+/// see [`Parser::property_with`].
+///
+/// `:var` assignments accumulate across levels, and each category of `:results`
+/// is overridden separately, so `:results silent` keeps an inherited `table`.
+///
+/// ```rust
+/// use org_rust_parser as org_parser;
+///
+/// use org_parser::{parse_org, Exports, HeaderArgs};
+///
+/// let parsed = parse_org(
+///     r"#+PROPERTY: header-args :exports both :var x=1
+/// #+begin_src python :var y=2
+/// return x + y
+/// #+end_src
+/// ",
+/// );
+/// let root = &parsed.pool[parsed.pool.root_id()];
+/// let block_id = root.obj.children().unwrap()[1];
+/// let args = HeaderArgs::resolve(&parsed, block_id);
+/// assert_eq!(args.exports, Exports::Both);
+/// assert_eq!(args.vars.len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderArgs<'a> {
+    pub results: Results,
+    pub exports: Exports,
+    pub vars: Vec<Var<'a>>,
+    /// `None` when the block isn't evaluated in a session
+    pub session: Option<&'a str>,
+    pub tangle: Tangle<'a>,
+    pub noweb: Noweb,
+    pub dir: Option<&'a str>,
+    /// Every other header argument, keyed by its name without the colon
+    pub other: HashMap<&'a str, &'a str>,
+}
+
+impl Default for HeaderArgs<'_> {
+    /// The system defaults: `:session none :results replace :exports code :noweb no :tangle no`
+    fn default() -> Self {
+        Self {
+            results: Results {
+                handling: Some(ResultsHandling::Replace),
+                ..Default::default()
+            },
+            exports: Exports::Code,
+            vars: Vec::new(),
+            session: None,
+            tangle: Tangle::No,
+            noweb: Noweb::No,
+            dir: None,
+            other: HashMap::new(),
+        }
+    }
+}
+
+/// The `:results` header argument, where each category is optional.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Results {
+    pub collection: Option<ResultsCollection>,
+    pub kind: Option<ResultsType>,
+    pub format: Option<ResultsFormat>,
+    pub handling: Option<ResultsHandling>,
+}
+
+/// How results are collected: `value` or `output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultsCollection {
+    Value,
+    Output,
+}
+
+/// What kind of result is produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultsType {
+    /// `table` or `vector`
+    Table,
+    List,
+    /// `scalar` or `verbatim`
+    Scalar,
+    File,
+}
+
+/// How results are wrapped when inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultsFormat {
+    Code,
+    Drawer,
+    Html,
+    Latex,
+    /// `link` or `graphics`
+    Link,
+    Org,
+    Pp,
+    Raw,
+}
+
+/// What happens to previous results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultsHandling {
+    Replace,
+    Silent,
+    None,
+    Append,
+    Prepend,
+}
+
+/// The `:exports` header argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exports {
+    Code,
+    Results,
+    Both,
+    None,
+}
+
+impl Exports {
+    pub fn code(self) -> bool {
+        matches!(self, Exports::Code | Exports::Both)
+    }
+
+    pub fn results(self) -> bool {
+        matches!(self, Exports::Results | Exports::Both)
+    }
+}
+
+/// A `:var name=value` assignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Var<'a> {
+    pub name: &'a str,
+    /// The unevaluated value, e.g. `"text"`, `42` or a reference like `tbl[1,2]`
+    pub value: &'a str,
+}
+
+/// The `:tangle` header argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tangle<'a> {
+    No,
+    /// Tangle to a file named after the document
+    Yes,
+    File(&'a str),
+}
+
+/// The `:noweb` header argument, controlling when `<<references>>` are expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Noweb {
+    No,
+    Yes,
+    Tangle,
+    NoExport,
+    StripExport,
+    StripTangle,
+    Eval,
+}
+
+impl Noweb {
+    /// What happens to references in a block's body when it's used in `context`.
+    pub fn action(self, context: NowebContext) -> NowebAction {
+        match (context, self) {
+            (NowebContext::Export, Noweb::Yes | Noweb::StripTangle)
+            | (
+                NowebContext::Tangle,
+                Noweb::Yes | Noweb::Tangle | Noweb::NoExport | Noweb::StripExport,
+            )
+            | (
+                NowebContext::Eval,
+                Noweb::Yes
+                | Noweb::NoExport
+                | Noweb::StripExport
+                | Noweb::StripTangle
+                | Noweb::Eval,
+            ) => NowebAction::Expand,
+            (NowebContext::Export, Noweb::StripExport)
+            | (NowebContext::Tangle, Noweb::StripTangle) => NowebAction::Strip,
+            _ => NowebAction::Keep,
+        }
+    }
+}
+
+impl<'a> HeaderArgs<'a> {
+    /// Resolves the header arguments of a source block, inline source block or call.
+    ///
+    /// The header after a call's arguments takes precedence over the one before them.
+    /// Other nodes get the file and heading level arguments.
+    pub fn resolve(parser: &'a Parser<'_>, node_id: NodeID) -> Self {
+        let node = &parser.pool[node_id];
+        let (lang, own_headers) = match &node.obj {
+            Expr::Block(Block::Src {
+                language, header, ..
+            }) => (*language, vec![Some(*header)]),
+            Expr::InlineSrc(inner) => (Some(inner.lang), vec![inner.headers]),
+            Expr::BabelCall(inner) => (
+                None,
+                [inner.inside_header, inner.end_header_args()].to_vec(),
+            ),
+            Expr::InlineBabelCall(inner) => {
+                (None, [inner.inside_header, inner.end_header].to_vec())
+            }
+            _ => (None, Vec::new()),
+        };
+
+        let mut args = Self::default();
+        if let Expr::InlineSrc(_) | Expr::BabelCall(_) | Expr::InlineBabelCall(_) = node.obj {
+            args.exports = Exports::Results;
+        }
+
+        // inherited like any other property, so `header-args+` accumulates onto
+        // the nearest `header-args` of a heading or `#+PROPERTY:` line
+        for headers in parser.property_values(node_id, "header-args", true) {
+            args.apply(headers);
+        }
+        if let Some(lang) = lang {
+            let name = format!("header-args:{lang}");
+            for headers in parser.property_values(node_id, &name, true) {
+                args.apply(headers);
+            }
+        }
+
+        for headers in header_keywords(parser, node_id) {
+            args.apply(headers);
+        }
+        for headers in own_headers.into_iter().flatten() {
+            args.apply(headers);
+        }
+        args
+    }
+
+    /// Applies raw header arguments on top of the current ones,
+    /// e.g. `:results output :var x=1`.
+    pub fn apply(&mut self, headers: &'a str) {
+        for (key, val) in split_headers(headers) {
+            match key.to_ascii_lowercase().as_str() {
+                "results" => self.results.apply(val),
+                "exports" => {
+                    if let Some(exports) = Exports::from_str(val) {
+                        self.exports = exports;
+                    }
+                }
+                "var" => {
+                    for var in split_vars(val) {
+                        if let Some(old) = self.vars.iter_mut().find(|old| old.name == var.name) {
+                            *old = var;
+                        } else {
+                            self.vars.push(var);
+                        }
+                    }
+                }
+                "session" => {
+                    self.session = (!val.is_empty() && val != "none").then_some(val);
+                }
+                "tangle" => {
+                    self.tangle = match val {
+                        "no" => Tangle::No,
+                        "yes" => Tangle::Yes,
+                        "" => continue,
+                        file => Tangle::File(file),
+                    }
+                }
+                "noweb" => {
+                    if let Some(noweb) = Noweb::from_str(val) {
+                        self.noweb = noweb;
+                    }
+                }
+                "dir" => self.dir = (!val.is_empty()).then_some(val),
+                _ => {
+                    self.other.insert(key, val);
+                }
+            }
+        }
+    }
+
+    /// Looks up the value of a var by name.
+    pub fn var(&self, name: &str) -> Option<&'a str> {
+        self.vars
+            .iter()
+            .find(|var| var.name == name)
+            .map(|var| var.value)
+    }
+
+    /// Strips the double quotes around a header argument value, e.g. `"out file.py"`.
+    pub fn unquote(val: &str) -> &str {
+        val.strip_prefix('"')
+            .and_then(|val| val.strip_suffix('"'))
+            .unwrap_or(val)
+    }
+}
+
+impl Results {
+    fn apply(&mut self, val: &str) {
+        for word in val.split_ascii_whitespace() {
+            match word {
+                "value" => self.collection = Some(ResultsCollection::Value),
+                "output" => self.collection = Some(ResultsCollection::Output),
+                "table" | "vector" => self.kind = Some(ResultsType::Table),
+                "list" => self.kind = Some(ResultsType::List),
+                "scalar" | "verbatim" => self.kind = Some(ResultsType::Scalar),
+                "file" => self.kind = Some(ResultsType::File),
+                "code" => self.format = Some(ResultsFormat::Code),
+                "drawer" => self.format = Some(ResultsFormat::Drawer),
+                "html" => self.format = Some(ResultsFormat::Html),
+                "latex" => self.format = Some(ResultsFormat::Latex),
+                "link" | "graphics" => self.format = Some(ResultsFormat::Link),
+                "org" => self.format = Some(ResultsFormat::Org),
+                "pp" => self.format = Some(ResultsFormat::Pp),
+                "raw" => self.format = Some(ResultsFormat::Raw),
+                "replace" => self.handling = Some(ResultsHandling::Replace),
+                "silent" => self.handling = Some(ResultsHandling::Silent),
+                "none" => self.handling = Some(ResultsHandling::None),
+                "append" => self.handling = Some(ResultsHandling::Append),
+                "prepend" => self.handling = Some(ResultsHandling::Prepend),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Exports {
+    fn from_str(val: &str) -> Option<Self> {
+        Some(match val {
+            "code" => Exports::Code,
+            "results" => Exports::Results,
+            "both" => Exports::Both,
+            "none" => Exports::None,
+            _ => return None,
+        })
+    }
+}
+
+impl Noweb {
+    fn from_str(val: &str) -> Option<Self> {
+        Some(match val {
+            "no" => Noweb::No,
+            "yes" => Noweb::Yes,
+            "tangle" => Noweb::Tangle,
+            "no-export" => Noweb::NoExport,
+            "strip-export" => Noweb::StripExport,
+            "strip-tangle" => Noweb::StripTangle,
+            "eval" => Noweb::Eval,
+            _ => return None,
+        })
+    }
+}
+
+/// Collects `#+HEADER:` keywords directly before a node, in order.
+fn header_keywords<'a>(parser: &'a Parser<'_>, node_id: NodeID) -> Vec<&'a str> {
+    let Some(siblings) = parser.pool[node_id]
+        .parent
+        .and_then(|parent| parser.pool[parent].obj.children())
+    else {
+        return Vec::new();
+    };
+    let Some(pos) = siblings.iter().position(|&id| id == node_id) else {
+        return Vec::new();
+    };
+
+    let mut ret = Vec::new();
+    for id in siblings[..pos].iter().rev() {
+        match &parser.pool[*id].obj {
+            Expr::Keyword(keyword)
+                if keyword.key.eq_ignore_ascii_case("header")
+                    || keyword.key.eq_ignore_ascii_case("headers") =>
+            {
+                ret.push(keyword.val);
+            }
+            Expr::Affiliated(_) => {}
+            _ => break,
+        }
+    }
+    ret.reverse();
+    ret
+}
+
+/// Splits header arguments into `(key, value)` pairs.
+///
+/// A key starts at a colon following whitespace, so values can contain
+/// colons and spaces. Quoted values can also contain ` :`.
+fn split_headers(headers: &str) -> Vec<(&str, &str)> {
+    let bytes = headers.as_bytes();
+    let mut starts = Vec::new();
+    let mut in_quote = false;
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'"' => in_quote = !in_quote,
+            b':' if !in_quote && (i == 0 || bytes[i - 1].is_ascii_whitespace()) => {
+                starts.push(i);
+            }
+            _ => {}
+        }
+    }
+
+    let mut ret = Vec::new();
+    for (n, &start) in starts.iter().enumerate() {
+        let end = starts.get(n + 1).copied().unwrap_or(headers.len());
+        let arg = &headers[start + 1..end];
+        let (key, val) = arg.split_once(char::is_whitespace).unwrap_or((arg, ""));
+        if !key.is_empty() {
+            ret.push((key, val.trim()));
+        }
+    }
+    ret
+}
+
+/// Splits `a=1, b=(1 2)` into its assignments.
+fn split_vars(val: &str) -> Vec<Var<'_>> {
+    let mut assignments = Vec::new();
+    let mut depth = 0_i32;
+    let mut in_quote = false;
+    let mut start = 0;
+    for (i, chr) in val.char_indices() {
+        match chr {
+            '"' => in_quote = !in_quote,
+            '(' | '[' if !in_quote => depth += 1,
+            ')' | ']' if !in_quote => depth -= 1,
+            ',' if !in_quote && depth == 0 => {
+                assignments.push(&val[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    assignments.push(&val[start..]);
+
+    assignments
+        .into_iter()
+        .map(str::trim)
+        .filter(|assignment| !assignment.is_empty())
+        .map(|assignment| {
+            let (name, value) = assignment.split_once('=').unwrap_or((assignment, ""));
+            Var {
+                name: name.trim(),
+                value: value.trim(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_org;
+    use pretty_assertions::assert_eq;
+
+    fn resolve_block<'a>(parsed: &'a Parser<'_>) -> HeaderArgs<'a> {
+        let (id, _) = parsed
+            .pool
+            .iter_ids()
+            .find(|(_, node)| matches!(node.obj, Expr::Block(Block::Src { .. })))
+            .unwrap();
+        HeaderArgs::resolve(parsed, id)
+    }
+
+    #[test]
+    fn block_header_args() {
+        let input = r#"#+begin_src python :var x=1 :var y="a :b", z=(1 2) :results output table :dir /tmp :custom yes
+#+end_src
+"#;
+        let parsed = parse_org(input);
+        let args = resolve_block(&parsed);
+
+        assert_eq!(
+            args.vars,
+            [
+                Var {
+                    name: "x",
+                    value: "1"
+                },
+                Var {
+                    name: "y",
+                    value: "\"a :b\""
+                },
+                Var {
+                    name: "z",
+                    value: "(1 2)"
+                },
+            ]
+        );
+        assert_eq!(
+            args.results,
+            Results {
+                collection: Some(ResultsCollection::Output),
+                kind: Some(ResultsType::Table),
+                format: None,
+                handling: Some(ResultsHandling::Replace),
+            }
+        );
+        assert_eq!(args.exports, Exports::Code);
+        assert_eq!(args.dir, Some("/tmp"));
+        assert_eq!(args.other, HashMap::from([("custom", "yes")]));
+    }
+
+    #[test]
+    fn header_args_precedence() {
+        let input = r"#+PROPERTY: header-args :session main :var x=1 :results table
+#+PROPERTY: header-args:python :exports both
+#+PROPERTY: header-args:rust :exports none
+* Heading
+:PROPERTIES:
+:header-args+: :var y=2 :tangle yes
+:header-args:python+: :noweb strip-export
+:END:
+** Child
+:PROPERTIES:
+:header-args+: :var x=3
+:END:
+#+HEADER: :results silent
+#+begin_src python :session none :tangle out.py
+#+end_src
+";
+        let parsed = parse_org(input);
+        let args = resolve_block(&parsed);
+
+        assert_eq!(args.var("x"), Some("3"));
+        assert_eq!(args.var("y"), Some("2"));
+        assert_eq!(args.exports, Exports::Both);
+        assert_eq!(args.noweb, Noweb::StripExport);
+        assert_eq!(args.session, None);
+        assert_eq!(args.tangle, Tangle::File("out.py"));
+        assert_eq!(args.results.kind, Some(ResultsType::Table));
+        assert_eq!(args.results.handling, Some(ResultsHandling::Silent));
+    }
+
+    #[test]
+    fn header_args_replaced_by_heading() {
+        let input = r"#+PROPERTY: header-args :session main :var x=1
+* Heading
+:PROPERTIES:
+:header-args: :var y=2
+:END:
+#+begin_src python
+#+end_src
+";
+        let parsed = parse_org(input);
+        let args = resolve_block(&parsed);
+
+        assert_eq!(args.var("x"), None);
+        assert_eq!(args.var("y"), Some("2"));
+        assert_eq!(args.session, None);
+    }
+
+    #[test]
+    fn call_header_args() {
+        let input = r#"#+PROPERTY: header-args :exports code :session main
+* Heading
+:PROPERTIES:
+:header-args+: :var y=2
+:END:
+#+call: plot[:session none :exports none](x=1) :exports both :var z=" :exports none"
+call_plot[:var x=3](x=1)
+"#;
+        let parsed = parse_org(input);
+        let (call, _) = parsed
+            .pool
+            .iter_ids()
+            .find(|(_, node)| matches!(node.obj, Expr::BabelCall(_)))
+            .unwrap();
+        let args = HeaderArgs::resolve(&parsed, call);
+
+        assert_eq!(args.exports, Exports::Both);
+        assert_eq!(args.session, None);
+        assert_eq!(args.var("y"), Some("2"));
+        assert_eq!(args.var("z"), Some("\" :exports none\""));
+
+        let (inline_call, _) = parsed
+            .pool
+            .iter_ids()
+            .find(|(_, node)| matches!(node.obj, Expr::InlineBabelCall(_)))
+            .unwrap();
+        let args = HeaderArgs::resolve(&parsed, inline_call);
+        assert_eq!(args.exports, Exports::Code);
+        assert_eq!(args.session, Some("main"));
+        assert_eq!(args.var("x"), Some("3"));
+
+        // calls export their results by default
+        let parsed = parse_org("#+call: plot()\n");
+        let (call, _) = parsed
+            .pool
+            .iter_ids()
+            .find(|(_, node)| matches!(node.obj, Expr::BabelCall(_)))
+            .unwrap();
+        assert_eq!(HeaderArgs::resolve(&parsed, call).exports, Exports::Results);
+    }
+
+    #[test]
+    fn inline_src_defaults() {
+        let parsed = parse_org("src_python[:var x=2]{x}\n");
+        let (id, _) = parsed
+            .pool
+            .iter_ids()
+            .find(|(_, node)| matches!(node.obj, Expr::InlineSrc(_)))
+            .unwrap();
+        let args = HeaderArgs::resolve(&parsed, id);
+
+        assert_eq!(args.exports, Exports::Results);
+        assert_eq!(args.var("x"), Some("2"));
+    }
+}
@@ -0,0 +1,207 @@
+//! The in-buffer keywords of a document, such as `#+TITLE:`.
+
+/// Every keyword of a document, in the order they appear.
+///
+/// Keys are matched case-insensitively, so `#+TITLE:` and `#+title:` are the same keyword.
+/// Keywords that are handled while parsing, such as `#+NAME:`, `#+CAPTION:` and `#+MACRO:`,
+/// aren't stored.
+#[derive(Debug, Clone, Default)]
+pub struct Keywords<'a> {
+    entries: Vec<(&'a str, &'a str)>,
+}
+
+/// An abbreviation defined with `#+LINK: name url`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkAbbrev<'a> {
+    pub name: &'a str,
+    /// The replacement, which may contain `%s` or `%h` to place the rest of the link
+    pub url: &'a str,
+}
+
+/// A group of tags within `{ }` or `[ ]` in a `#+TAGS:` line.
+///
+/// ```example
+/// #+TAGS: { Context : @work @home } [ GTD : Control Persp ]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagGroup<'a> {
+    /// The group tag, written before the `:`, which matches every member
+    pub name: Option<&'a str>,
+    pub members: Vec<&'a str>,
+    /// Whether the tags are mutually exclusive, i.e. the group is within `{ }`
+    pub exclusive: bool,
+}
+
+impl<'a> Keywords<'a> {
+    pub(crate) fn push(&mut self, key: &'a str, val: &'a str) {
+        self.entries.push((key, val));
+    }
+
+    /// The value of the last occurrence of `key`.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.get_all(key).last()
+    }
+
+    /// The values of every occurrence of `key`, in order.
+    pub fn get_all<'s>(&'s self, key: &'s str) -> impl Iterator<Item = &'a str> + 's {
+        self.entries
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, val)| *val)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.get_all(key).next().is_some()
+    }
+
+    /// Every keyword as `(key, value)`, with keys as written.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// The `(name, value)` pairs of `#+PROPERTY:` lines.
+    ///
+    /// The value is empty when only a name is given.
+    pub fn properties(&self) -> impl Iterator<Item = (&'a str, &'a str)> + '_ {
+        self.get_all("property").filter_map(|val| {
+            let (name, value) = val.split_once(char::is_whitespace).unwrap_or((val, ""));
+            (!name.is_empty()).then(|| (name, value.trim()))
+        })
+    }
+
+    /// The abbreviations of `#+LINK:` lines.
+    pub fn links(&self) -> impl Iterator<Item = LinkAbbrev<'a>> + '_ {
+        self.get_all("link").filter_map(|val| {
+            let (name, url) = val.split_once(char::is_whitespace)?;
+            Some(LinkAbbrev {
+                name,
+                url: url.trim(),
+            })
+        })
+    }
+
+    /// The tags listed by `#+TAGS:` lines, without their fast selection keys.
+    pub fn tags(&self) -> Vec<&'a str> {
+        self.get_all("tags")
+            .flat_map(str::split_whitespace)
+            .filter(|word| !matches!(*word, "{" | "}" | "[" | "]" | ":" | "\\n"))
+            .map(strip_fast_key)
+            .collect()
+    }
+
+    /// The groups defined by `#+TAGS:` lines.
+    pub fn tag_groups(&self) -> Vec<TagGroup<'a>> {
+        let mut groups = Vec::new();
+        for line in self.get_all("tags") {
+            let mut group: Option<TagGroup> = None;
+            for word in line.split_whitespace() {
+                match word {
+                    "{" | "[" => {
+                        group = Some(TagGroup {
+                            name: None,
+                            members: Vec::new(),
+                            exclusive: word == "{",
+                        });
+                    }
+                    "}" | "]" => groups.extend(group.take()),
+                    ":" => {
+                        if let Some(group) = group.as_mut().filter(|group| group.name.is_none()) {
+                            group.name = group.members.pop();
+                        }
+                    }
+                    "\\n" => {}
+                    _ => {
+                        if let Some(group) = &mut group {
+                            group.members.push(strip_fast_key(word));
+                        }
+                    }
+                }
+            }
+        }
+        groups
+    }
+
+    /// The tags of `#+FILETAGS:` lines, which every headline inherits.
+    pub fn filetags(&self) -> Vec<&'a str> {
+        self.get_all("filetags")
+            .flat_map(|val| val.split(|chr: char| chr == ':' || chr.is_whitespace()))
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+
+    /// The options of `#+STARTUP:` lines, such as `overview` or `indent`.
+    pub fn startup(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.get_all("startup").flat_map(str::split_whitespace)
+    }
+}
+
+/// Removes the fast selection key of a tag, e.g. `(w)` in `@work(w)`.
+fn strip_fast_key(word: &str) -> &str {
+    match word.split_once('(') {
+        Some((name, rest)) if rest.ends_with(')') => name,
+        _ => word,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_org;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn case_insensitive_keys() {
+        let parser = parse_org(
+            r"#+TITLE: first
+#+author: one
+#+Author: two
+#+title: second
+",
+        );
+
+        assert_eq!(parser.keywords.get("title"), Some("second"));
+        assert_eq!(parser.keywords.get("TITLE"), Some("second"));
+        assert_eq!(
+            parser.keywords.get_all("AUTHOR").collect::<Vec<_>>(),
+            vec!["one", "two"]
+        );
+        assert!(!parser.keywords.contains("date"));
+    }
+
+    #[test]
+    fn structured_keywords() {
+        let parser = parse_org(
+            r"#+PROPERTY: header-args :results silent
+#+property: Effort_ALL 0 0:10 0:30
+#+LINK: gh https://github.com/%s
+#+TAGS: @work(w) @home(h) laptop
+#+TAGS: { Context : @work @home }
+#+STARTUP: overview indent
+#+startup: hidestars
+",
+        );
+
+        assert_eq!(
+            parser.keywords.properties().collect::<Vec<_>>(),
+            vec![
+                ("header-args", ":results silent"),
+                ("Effort_ALL", "0 0:10 0:30")
+            ]
+        );
+        assert_eq!(
+            parser.keywords.links().collect::<Vec<_>>(),
+            vec![LinkAbbrev {
+                name: "gh",
+                url: "https://github.com/%s"
+            }]
+        );
+        assert_eq!(
+            parser.keywords.tags(),
+            vec!["@work", "@home", "laptop", "Context", "@work", "@home"]
+        );
+        assert_eq!(
+            parser.keywords.startup().collect::<Vec<_>>(),
+            vec!["overview", "indent", "hidestars"]
+        );
+    }
+}
@@ -8,14 +8,41 @@ pub(crate) mod node_pool;
 pub(crate) mod types;
 pub(crate) mod utils;
 
+mod diary;
+mod header_args;
+mod keywords;
+mod noweb;
 mod parse;
-
+mod properties;
+mod results;
+mod settings;
+mod tags;
+mod tblfm;
+#[cfg(test)]
+mod test_utils;
+
+pub use diary::diary_sexp_matches;
+pub use header_args::{
+    Exports, HeaderArgs, Noweb, Results, ResultsCollection, ResultsFormat, ResultsHandling,
+    ResultsType, Tangle, Var,
+};
+pub use keywords::{Keywords, LinkAbbrev, TagGroup};
 pub use node_pool::{NodeID, NodePool};
+pub use noweb::{NowebAction, NowebContext, NowebError};
+pub use properties::is_inherited_property;
+pub use settings::{
+    ParseSettings, TodoEntry, TodoKeywords, TodoSequence, TodoSequenceKind, TodoState,
+};
+pub use tblfm::{
+    recalculate, BinaryOp, ColRef, FieldRef, FormulaError, FormulaExpr, FormulaTarget, RowRef,
+    TableFormula, VectorFunc,
+};
 pub use types::{Expr, Node, Parser};
 pub use utils::Match;
 
 use std::collections::HashMap;
 
+use object::link_radio_targets;
 use parse::{parse_element, parse_object};
 use types::{Cursor, NodeCache, ParseOpts};
 
@@ -52,6 +79,14 @@ pub(crate) mod constants {
 ///
 /// Repeatedly parses elements until EOF, then returns a [`Parser`].
 pub fn parse_org(input: &str) -> Parser<'_> {
+    parse_org_with(input, ParseSettings::default())
+}
+
+/// Like [`parse_org`], but with settings that aren't set in the document itself.
+///
+/// In-buffer settings (e.g. `#+TODO:`) are collected before any headings are parsed,
+/// and replace their counterpart in `settings`.
+pub fn parse_org_with<'a>(input: &'a str, settings: ParseSettings<'a>) -> Parser<'a> {
     let mut cursor = Cursor::new(input.as_bytes());
     let parse_opts = ParseOpts::default();
     let mut pool = NodePool::new();
@@ -64,17 +99,23 @@ pub fn parse_org(input: &str) -> Parser<'_> {
         cache,
         targets: HashMap::new(),
         macros: HashMap::new(),
-        keywords: HashMap::new(),
+        keywords: Keywords::default(),
         target_occurences: HashMap::new(),
         footnotes: HashMap::new(),
+        names: HashMap::new(),
+        todo_keywords: TodoKeywords::from_buffer(input).unwrap_or(settings.todo_keywords),
+        inline_task_min_level: settings.inline_task_min_level,
         source: input,
     };
     // main loop
     while let Ok(id) = parse_element(&mut parser, cursor, Some(parent), parse_opts) {
+        // the element could've been cached with a different parent
+        parser.pool[id].parent = Some(parent);
         content_vec.push(id);
         cursor.move_to(parser.pool[id].end);
     }
     parser.alloc_with_id(Expr::Root(content_vec), 0, cursor.index, None, parent);
+    link_radio_targets(&mut parser);
 
     parser
 }
@@ -95,9 +136,12 @@ pub fn parse_macro_call<'a>(input: &'a str) -> Parser<'a> {
         cache: NodeCache::new(),
         targets: HashMap::new(),
         macros: HashMap::new(),
-        keywords: HashMap::new(),
+        keywords: Keywords::default(),
         target_occurences: HashMap::new(),
         footnotes: HashMap::new(),
+        names: HashMap::new(),
+        todo_keywords: TodoKeywords::default(),
+        inline_task_min_level: ParseSettings::default().inline_task_min_level,
         source: input,
     };
     while let Ok(id) = parse_object(&mut parser, cursor, Some(parent), parse_opts) {
@@ -125,6 +125,14 @@ impl<'a> NodePool<'a> {
         self.inner_vec.iter()
     }
 
+    /// Iterates over the pool alongside each [`Node`]'s [`NodeID`].
+    pub(crate) fn iter_ids(&self) -> impl Iterator<Item = (NodeID, &Node<'a>)> {
+        self.inner_vec
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (NodeID(i as u32), node))
+    }
+
     pub fn iter_mut(
         &mut self,
     ) -> impl Iterator<Item = &mut Node<'a>> + DoubleEndedIterator<Item = &mut Node<'a>> {
@@ -0,0 +1,383 @@
+//! Expansion of noweb references, `<<name>>`, in source blocks.
+
+use std::fmt;
+
+use crate::element::Block;
+use crate::header_args::HeaderArgs;
+use crate::node_pool::NodeID;
+use crate::types::{Expr, Parser};
+
+/// Where the body of a source block is being used, which decides along with its
+/// `:noweb` header argument whether references are expanded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NowebContext {
+    Eval,
+    Tangle,
+    Export,
+}
+
+/// What happens to the references in a block's body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NowebAction {
+    /// References are left as is
+    Keep,
+    /// References are replaced with the bodies they refer to
+    Expand,
+    /// References are removed
+    Strip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NowebError {
+    /// A block refers back to itself, through the named reference
+    Cycle(String),
+    /// A `<<name(args)>>` reference could not be evaluated
+    Call(String),
+}
+
+impl fmt::Display for NowebError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NowebError::Cycle(name) => write!(f, "noweb reference <<{name}>> forms a cycle"),
+            NowebError::Call(name) => write!(f, "could not evaluate noweb reference <<{name}>>"),
+        }
+    }
+}
+
+impl std::error::Error for NowebError {}
+
+impl<'a> Parser<'a> {
+    /// Expands the noweb references in the body of a source block.
+    ///
+    /// A reference `<<name>>` is replaced with the body of the block named `name`, or
+    /// with the bodies of every block with `:noweb-ref name` joined by their `:noweb-sep`.
+    /// Referenced bodies are themselves expanded according to their own `:noweb` argument.
+    /// References that can't be resolved expand to nothing.
+    ///
+    /// `<<name(args)>>` references, which insert the results of calling a block,
+    /// fail with [`NowebError::Call`]; see [`Parser::expand_noweb_with`].
+    pub fn expand_noweb(
+        &self,
+        node_id: NodeID,
+        context: NowebContext,
+    ) -> Result<String, NowebError> {
+        self.expand_noweb_with(node_id, context, &mut |_, _| None)
+    }
+
+    /// Expands noweb references like [`Parser::expand_noweb`], evaluating
+    /// `<<name(args)>>` references with `call`.
+    ///
+    /// `call` receives the id of the named block and the arguments between the parentheses,
+    /// returning `None` if the block couldn't be evaluated.
+    pub fn expand_noweb_with(
+        &self,
+        node_id: NodeID,
+        context: NowebContext,
+        call: &mut dyn FnMut(NodeID, &str) -> Option<String>,
+    ) -> Result<String, NowebError> {
+        let mut stack = Vec::new();
+        Expander {
+            parser: self,
+            context,
+            call,
+        }
+        .expand(node_id, &mut stack)
+    }
+}
+
+struct Expander<'p, 'a, 'c> {
+    parser: &'p Parser<'a>,
+    context: NowebContext,
+    call: &'c mut dyn FnMut(NodeID, &str) -> Option<String>,
+}
+
+impl Expander<'_, '_, '_> {
+    fn expand(&mut self, node_id: NodeID, stack: &mut Vec<NodeID>) -> Result<String, NowebError> {
+        let Expr::Block(Block::Src { contents, .. }) = &self.parser.pool[node_id].obj else {
+            return Ok(String::new());
+        };
+        let action = HeaderArgs::resolve(self.parser, node_id)
+            .noweb
+            .action(self.context);
+        if action == NowebAction::Keep {
+            return Ok((*contents).to_owned());
+        }
+
+        stack.push(node_id);
+        let mut ret = String::new();
+        for line in contents.split_inclusive('\n') {
+            let mut rest = line;
+            while let Some((start, name, end)) = find_reference(rest) {
+                ret.push_str(&rest[..start]);
+                if action == NowebAction::Expand {
+                    // every line of a multi-line expansion repeats what precedes the reference
+                    let prefix = &line[..line.len() - rest.len() + start];
+                    let expanded = self.resolve(name, stack)?;
+                    let expanded = expanded.strip_suffix('\n').unwrap_or(&expanded);
+                    for (i, exp_line) in expanded.split('\n').enumerate() {
+                        if i > 0 {
+                            ret.push('\n');
+                            ret.push_str(prefix);
+                        }
+                        ret.push_str(exp_line);
+                    }
+                }
+                rest = &rest[end..];
+            }
+            ret.push_str(rest);
+        }
+        stack.pop();
+        Ok(ret)
+    }
+
+    /// The expansion of a single reference.
+    fn resolve(&mut self, reference: &str, stack: &mut Vec<NodeID>) -> Result<String, NowebError> {
+        let parser = self.parser;
+        let (name, args) = match reference.strip_suffix(')').and_then(|r| r.split_once('(')) {
+            Some((name, args)) => (name, Some(args)),
+            None => (reference, None),
+        };
+
+        let named = parser
+            .names
+            .get(name)
+            .copied()
+            .filter(|id| matches!(parser.pool[*id].obj, Expr::Block(Block::Src { .. })));
+
+        if let Some(args) = args {
+            let Some(id) = named else {
+                return Ok(String::new());
+            };
+            return (self.call)(id, args).ok_or_else(|| NowebError::Call(reference.to_owned()));
+        }
+
+        if let Some(id) = named {
+            return self.expand_ref(id, name, stack);
+        }
+
+        let mut blocks = parser
+            .pool
+            .iter_ids()
+            .filter(|(_, node)| matches!(node.obj, Expr::Block(Block::Src { .. })))
+            .filter_map(|(id, node)| {
+                let args = HeaderArgs::resolve(parser, id);
+                let noweb_ref = args
+                    .other
+                    .get("noweb-ref")
+                    .map(|val| HeaderArgs::unquote(val))?;
+                (noweb_ref == name).then(|| {
+                    // the separator is an elisp string, which can contain escaped newlines
+                    let sep = args.other.get("noweb-sep").map_or("\n".into(), |val| {
+                        HeaderArgs::unquote(val).replace("\\n", "\n")
+                    });
+                    (node.start, id, sep)
+                })
+            })
+            .collect::<Vec<_>>();
+        blocks.sort_by_key(|(start, ..)| *start);
+
+        let mut ret = String::new();
+        for (i, (_, id, sep)) in blocks.into_iter().enumerate() {
+            if i > 0 {
+                ret.push_str(&sep);
+            }
+            let body = self.expand_ref(id, name, stack)?;
+            ret.push_str(body.strip_suffix('\n').unwrap_or(&body));
+        }
+        Ok(ret)
+    }
+
+    fn expand_ref(
+        &mut self,
+        node_id: NodeID,
+        name: &str,
+        stack: &mut Vec<NodeID>,
+    ) -> Result<String, NowebError> {
+        if stack.contains(&node_id) {
+            return Err(NowebError::Cycle(name.to_owned()));
+        }
+        self.expand(node_id, stack)
+    }
+}
+
+/// Finds the next `<<reference>>` within a line, returning where it starts,
+/// its contents and where it ends.
+///
+/// References can't start or end with whitespace, which keeps shift operators
+/// such as `a << b >> c` from being mistaken for one.
+fn find_reference(line: &str) -> Option<(usize, &str, usize)> {
+    let mut offset = 0;
+    while let Some(start) = line[offset..].find("<<") {
+        let start = offset + start;
+        let inner_start = start + 2;
+        if let Some(len) = line[inner_start..].find(">>") {
+            let inner = &line[inner_start..inner_start + len];
+            if !inner.is_empty()
+                && !inner.contains('\n')
+                && !inner.starts_with(char::is_whitespace)
+                && !inner.ends_with(char::is_whitespace)
+            {
+                return Some((start, inner, inner_start + len + 2));
+            }
+        }
+        offset = inner_start;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NowebContext, NowebError};
+    use crate::types::Expr;
+    use crate::{parse_org, NodeID, Parser};
+    use pretty_assertions::assert_eq;
+
+    fn block_named(parsed: &Parser, name: &str) -> NodeID {
+        parsed.names[name]
+    }
+
+    #[test]
+    fn expand_named_blocks() {
+        let input = r"#+NAME: imports
+#+begin_src python
+import os
+import sys
+#+end_src
+
+#+NAME: main
+#+begin_src python :noweb yes
+<<imports>>
+def main():
+    # <<imports>>
+    pass
+#+end_src
+";
+        let parsed = parse_org(input);
+        let main = block_named(&parsed, "main");
+
+        assert_eq!(
+            parsed.expand_noweb(main, NowebContext::Export).unwrap(),
+            "import os\nimport sys\ndef main():\n    # import os\n    # import sys\n    pass\n"
+        );
+    }
+
+    #[test]
+    fn noweb_contexts() {
+        let input = r"#+NAME: part
+#+begin_src sh
+echo part
+#+end_src
+
+#+NAME: strip
+#+begin_src sh :noweb strip-export
+echo <<part>>
+#+end_src
+
+#+NAME: tangle
+#+begin_src sh :noweb tangle
+<<part>> << not a ref >>
+#+end_src
+
+#+NAME: strip-tangle
+#+begin_src sh :noweb strip-tangle
+echo <<part>>
+#+end_src
+";
+        let parsed = parse_org(input);
+        let strip = block_named(&parsed, "strip");
+        let tangle = block_named(&parsed, "tangle");
+        let strip_tangle = block_named(&parsed, "strip-tangle");
+
+        assert_eq!(
+            parsed.expand_noweb(strip, NowebContext::Export).unwrap(),
+            "echo \n"
+        );
+        assert_eq!(
+            parsed.expand_noweb(strip, NowebContext::Tangle).unwrap(),
+            "echo echo part\n"
+        );
+        assert_eq!(
+            parsed.expand_noweb(tangle, NowebContext::Export).unwrap(),
+            "<<part>> << not a ref >>\n"
+        );
+        assert_eq!(
+            parsed.expand_noweb(tangle, NowebContext::Tangle).unwrap(),
+            "echo part << not a ref >>\n"
+        );
+        assert_eq!(
+            parsed
+                .expand_noweb(strip_tangle, NowebContext::Export)
+                .unwrap(),
+            "echo echo part\n"
+        );
+        assert_eq!(
+            parsed
+                .expand_noweb(strip_tangle, NowebContext::Tangle)
+                .unwrap(),
+            "echo \n"
+        );
+    }
+
+    #[test]
+    fn noweb_ref_concatenation() {
+        let input = r#"#+begin_src sh :noweb-ref setup
+export A=1
+#+end_src
+
+#+begin_src sh :noweb-ref setup :noweb-sep "\n\n"
+export B=2
+#+end_src
+
+#+NAME: script
+#+begin_src sh :noweb yes
+<<setup>>
+<<missing>>
+#+end_src
+"#;
+        let parsed = parse_org(input);
+        let script = block_named(&parsed, "script");
+
+        assert_eq!(
+            parsed.expand_noweb(script, NowebContext::Tangle).unwrap(),
+            "export A=1\n\nexport B=2\n\n"
+        );
+    }
+
+    #[test]
+    fn noweb_cycles_and_calls() {
+        let input = r"#+NAME: a
+#+begin_src sh :noweb yes
+<<b>>
+#+end_src
+
+#+NAME: b
+#+begin_src sh :noweb yes
+<<a>>
+#+end_src
+
+#+NAME: call
+#+begin_src sh :noweb yes
+x=<<b(y=2)>>
+#+end_src
+";
+        let parsed = parse_org(input);
+        let a = block_named(&parsed, "a");
+        let call = block_named(&parsed, "call");
+
+        assert_eq!(
+            parsed.expand_noweb(a, NowebContext::Export),
+            Err(NowebError::Cycle("a".into()))
+        );
+        assert_eq!(
+            parsed.expand_noweb(call, NowebContext::Export),
+            Err(NowebError::Call("b(y=2)".into()))
+        );
+        let expanded = parsed
+            .expand_noweb_with(call, NowebContext::Export, &mut |id, args| {
+                assert!(matches!(parsed.pool[id].obj, Expr::Block(_)));
+                Some(format!("result of {args}"))
+            })
+            .unwrap();
+        assert_eq!(expanded, "x=result of y=2\n");
+    }
+}
@@ -0,0 +1,251 @@
+use std::fmt::Display;
+
+use crate::constants::{COLON, LBRACK, NEWLINE, RBRACK, SLASH};
+use crate::node_pool::NodeID;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+/// A citation of one or more references.
+///
+/// ```example
+/// [cite:@doe2020]
+/// [cite/t:see @doe2020 p. 3; @smith2019]
+/// [cite:Global prefix; @doe2020; global suffix]
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation<'a> {
+    /// The style and its variants, e.g. `t` or `author/caps`
+    pub style: Option<&'a str>,
+    /// Text placed before every reference
+    pub prefix: Option<&'a str>,
+    /// Text placed after every reference
+    pub suffix: Option<&'a str>,
+    pub references: Vec<CitationReference<'a>>,
+}
+
+/// A single `@key` within a [`Citation`], along with its own prefix and suffix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CitationReference<'a> {
+    pub key: &'a str,
+    pub prefix: Option<&'a str>,
+    pub suffix: Option<&'a str>,
+}
+
+impl<'a> Parseable<'a> for Citation<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("[cite")?;
+
+        let style = if cursor.try_curr()? == SLASH {
+            cursor.next();
+            let style_match = cursor.fn_while(|chr: u8| {
+                chr.is_ascii_alphanumeric() || matches!(chr, b'_' | b'-' | SLASH)
+            })?;
+            if style_match.obj.is_empty() {
+                return Err(MatchError::InvalidLogic);
+            }
+            cursor.move_to(style_match.end);
+            Some(style_match.obj)
+        } else {
+            None
+        };
+
+        if cursor.try_curr()? != COLON {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.next();
+
+        let contents = cursor.fn_until(|chr: u8| matches!(chr, RBRACK | LBRACK | NEWLINE))?;
+        cursor.move_to(contents.end);
+        if cursor.curr() != RBRACK {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        let mut prefix = None;
+        let mut suffix = None;
+        let mut references = Vec::new();
+        let segments: Vec<&str> = contents.obj.split(';').collect();
+        for (i, segment) in segments.iter().enumerate() {
+            if let Some(reference) = parse_reference(segment) {
+                // a global suffix has to be the last segment
+                if suffix.is_some() {
+                    return Err(MatchError::InvalidLogic);
+                }
+                references.push(reference);
+            } else if i == 0 {
+                prefix = non_empty(segment);
+            } else if i == segments.len() - 1 && !references.is_empty() {
+                suffix = non_empty(segment);
+            } else {
+                return Err(MatchError::InvalidLogic);
+            }
+        }
+
+        if references.is_empty() {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        Ok(parser.alloc(
+            Self {
+                style,
+                prefix,
+                suffix,
+                references,
+            },
+            start,
+            cursor.index + 1,
+            parent,
+        ))
+    }
+}
+
+/// Splits a segment of the form `prefix @key suffix`.
+fn parse_reference(segment: &str) -> Option<CitationReference<'_>> {
+    let bytes = segment.as_bytes();
+    let at = bytes
+        .iter()
+        .enumerate()
+        .position(|(i, &chr)| chr == b'@' && (i == 0 || bytes[i - 1].is_ascii_whitespace()))?;
+
+    let key_len = segment[at + 1..]
+        .find(|c: char| c.is_ascii_whitespace())
+        .unwrap_or(segment.len() - at - 1);
+    let key = &segment[at + 1..at + 1 + key_len];
+    if key.is_empty() {
+        return None;
+    }
+
+    Some(CitationReference {
+        key,
+        prefix: non_empty(&segment[..at]),
+        suffix: non_empty(&segment[at + 1 + key_len..]),
+    })
+}
+
+fn non_empty(text: &str) -> Option<&str> {
+    let text = text.trim();
+    (!text.is_empty()).then_some(text)
+}
+
+impl Display for Citation<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[cite")?;
+        if let Some(style) = self.style {
+            write!(f, "/{style}")?;
+        }
+        f.write_str(":")?;
+
+        if let Some(prefix) = self.prefix {
+            write!(f, "{prefix}; ")?;
+        }
+        for (i, reference) in self.references.iter().enumerate() {
+            if i != 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{reference}")?;
+        }
+        if let Some(suffix) = self.suffix {
+            write!(f, "; {suffix}")?;
+        }
+
+        f.write_str("]")
+    }
+}
+
+impl Display for CitationReference<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(prefix) = self.prefix {
+            write!(f, "{prefix} ")?;
+        }
+        write!(f, "@{}", self.key)?;
+        if let Some(suffix) = self.suffix {
+            write!(f, " {suffix}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CitationReference;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_citation() {
+        let input = "[cite:@doe2020]";
+        let parsed = parse_org(input);
+        let cite = expr_in_pool!(parsed, Citation).unwrap();
+
+        assert_eq!(cite.style, None);
+        assert_eq!(
+            cite.references,
+            vec![CitationReference {
+                key: "doe2020",
+                prefix: None,
+                suffix: None
+            }]
+        );
+    }
+
+    #[test]
+    fn citation_affixes() {
+        let input = "[cite/t/c:Global; see @doe2020 p. 3;@smith-2019; and more]";
+        let parsed = parse_org(input);
+        let cite = expr_in_pool!(parsed, Citation).unwrap();
+
+        assert_eq!(cite.style, Some("t/c"));
+        assert_eq!(cite.prefix, Some("Global"));
+        assert_eq!(cite.suffix, Some("and more"));
+        assert_eq!(
+            cite.references,
+            vec![
+                CitationReference {
+                    key: "doe2020",
+                    prefix: Some("see"),
+                    suffix: Some("p. 3")
+                },
+                CitationReference {
+                    key: "smith-2019",
+                    prefix: None,
+                    suffix: None
+                }
+            ]
+        );
+        assert_eq!(
+            cite.to_string(),
+            "[cite/t/c:Global; see @doe2020 p. 3; @smith-2019; and more]"
+        );
+    }
+
+    #[test]
+    fn citation_no_keys() {
+        let input = "[cite:nothing here]";
+        let parsed = parse_org(input);
+
+        assert!(expr_in_pool!(parsed, Citation).is_none());
+    }
+
+    #[test]
+    fn citation_misplaced_affix() {
+        let input = "[cite:@one; stray; @two]";
+        let parsed = parse_org(input);
+
+        assert!(expr_in_pool!(parsed, Citation).is_none());
+    }
+
+    #[test]
+    fn citation_in_paragraph() {
+        let input = "as shown in [cite:@doe2020], it works\n";
+        let parsed = parse_org(input);
+        let para = expr_in_pool!(parsed, Paragraph).unwrap();
+
+        assert_eq!(para.0.len(), 3);
+        assert!(expr_in_pool!(parsed, Citation).is_some());
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt::Display;
+
+use crate::constants::{LBRACK, LPAREN, RBRACK, RPAREN};
+use crate::node_pool::NodeID;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+use super::InlineSrc;
+
+/// A call to a named source block, within a paragraph.
+///
+/// ```example
+/// call_square[:session none](x=4)[:results raw]
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InlineBabelCall<'a> {
+    pub name: &'a str,
+    /// Header arguments applied to the called block
+    pub inside_header: Option<&'a str>,
+    /// The raw argument list, e.g. `x=4`
+    pub arguments: &'a str,
+    /// Header arguments applied to the call itself
+    pub end_header: Option<&'a str>,
+}
+
+impl<'a> Parseable<'a> for InlineBabelCall<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("call_")?;
+
+        let name = cursor
+            .fn_until(|chr: u8| chr == LBRACK || chr == LPAREN || chr.is_ascii_whitespace())?;
+        if name.obj.is_empty() {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.move_to(name.end);
+
+        let inside_header = if cursor.try_curr()? == LBRACK {
+            let header = InlineSrc::parse_src(cursor, LBRACK, RBRACK)?;
+            cursor.move_to(header.end);
+            Some(header.obj)
+        } else {
+            None
+        };
+
+        if cursor.try_curr()? != LPAREN {
+            return Err(MatchError::InvalidLogic);
+        }
+        let arguments = InlineSrc::parse_src(cursor, LPAREN, RPAREN)?;
+        cursor.move_to(arguments.end);
+
+        let end_header = if let Ok(LBRACK) = cursor.try_curr() {
+            let header = InlineSrc::parse_src(cursor, LBRACK, RBRACK)?;
+            cursor.move_to(header.end);
+            Some(header.obj)
+        } else {
+            None
+        };
+
+        Ok(parser.alloc(
+            Self {
+                name: name.obj,
+                inside_header,
+                arguments: arguments.obj,
+                end_header,
+            },
+            start,
+            cursor.index,
+            parent,
+        ))
+    }
+}
+
+impl Display for InlineBabelCall<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "call_{}", self.name)?;
+        if let Some(inside_header) = self.inside_header {
+            write!(f, "[{inside_header}]")?;
+        }
+        write!(f, "({})", self.arguments)?;
+        if let Some(end_header) = self.end_header {
+            write!(f, "[{end_header}]")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::object::InlineBabelCall;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_inline_call() {
+        let parsed = parse_org("the answer is call_square(x=4) today");
+        let call = expr_in_pool!(parsed, InlineBabelCall).unwrap();
+
+        assert_eq!(
+            call,
+            &InlineBabelCall {
+                name: "square",
+                inside_header: None,
+                arguments: "x=4",
+                end_header: None,
+            }
+        );
+    }
+
+    #[test]
+    fn inline_call_headers() {
+        let parsed = parse_org("call_square[:exports code](x=(1))[:exports both]");
+        let call = expr_in_pool!(parsed, InlineBabelCall).unwrap();
+
+        assert_eq!(call.inside_header, Some(":exports code"));
+        assert_eq!(call.arguments, "x=(1)");
+        assert_eq!(
+            call.to_string(),
+            "call_square[:exports code](x=(1))[:exports both]"
+        );
+    }
+
+    #[test]
+    fn not_inline_call() {
+        for input in ["call_square", "call_ (x)", "call_square[:a b]"] {
+            let parsed = parse_org(input);
+            assert!(expr_in_pool!(parsed, InlineBabelCall).is_none(), "{input}");
+        }
+    }
+}
@@ -74,7 +74,7 @@ impl<'a> InlineSrc<'a> {
         InlineSrc::parse_src(cursor, LBRACE, RBRACE)
     }
     #[inline(always)]
-    fn parse_src(mut cursor: Cursor, lperim: u8, rperim: u8) -> Result<Match<&str>> {
+    pub(crate) fn parse_src(mut cursor: Cursor, lperim: u8, rperim: u8) -> Result<Match<&str>> {
         // Brackets have to be balanced
         // -1 for left bracket
         // +1 for right bracket
@@ -82,7 +82,7 @@ impl<'a> InlineSrc<'a> {
 
         let start = cursor.index;
         loop {
-            match cursor.curr() {
+            match cursor.try_curr()? {
                 chr if chr == lperim => {
                     bracket_count -= 1;
                 }
@@ -1,24 +1,31 @@
 //! Module containing object structures
 //!
+mod citation;
 mod emoji;
 mod entity;
 mod export_snippet;
 mod footnote_ref;
+mod inline_babel_call;
 mod inline_src;
 mod latex_frag;
 mod link;
 mod markup;
 mod node_property;
 mod org_macro;
+mod sexp_timestamp;
+mod statistics_cookie;
 mod sup_sub;
 mod table_cell;
 mod target;
+mod timestamp;
 
+pub use citation::{Citation, CitationReference};
 pub use emoji::Emoji;
 pub(crate) use entity::parse_entity;
 pub use entity::Entity;
 pub use export_snippet::ExportSnippet;
 pub use footnote_ref::FootnoteRef;
+pub use inline_babel_call::InlineBabelCall;
 pub use inline_src::InlineSrc;
 pub use latex_frag::LatexFragment;
 pub(crate) use link::parse_angle_link;
@@ -30,8 +37,18 @@ pub use markup::*;
 pub(crate) use node_property::parse_node_property;
 pub use node_property::NodeProperty;
 pub use org_macro::MacroCall;
+pub use sexp_timestamp::SexpTimestamp;
+pub use statistics_cookie::StatisticsCookie;
 pub use sup_sub::PlainOrRec;
 pub use sup_sub::Subscript;
 pub use sup_sub::Superscript;
 pub use table_cell::TableCell;
+pub(crate) use target::link_radio_targets;
+pub use target::RadioLink;
+pub use target::RadioTarget;
 pub use target::Target;
+pub(crate) use timestamp::parse_timestamp;
+pub use timestamp::{
+    Date, DateTime, RangeKind, Repeater, RepeaterMark, Time, TimeUnit, Timestamp, Warning,
+    WarningMark,
+};
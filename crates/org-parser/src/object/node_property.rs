@@ -21,24 +21,52 @@ pub(crate) fn parse_node_property<'a>(
     cursor.skip_ws();
     cursor.word(":")?;
 
-    let name_match = cursor.fn_until(|chr| chr == COLON || chr.is_ascii_whitespace())?;
-    let name = name_match.obj;
-    cursor.index = name_match.end;
-    cursor.word(":")?;
+    // names can contain colons, e.g. :header-args:python:
+    let name_match = cursor.fn_until(|chr| chr.is_ascii_whitespace())?;
+    let name = if let Some(name) = name_match.obj.strip_suffix(':') {
+        cursor.index = name_match.end;
+        name
+    } else {
+        let name_match = cursor.fn_until(|chr| chr == COLON || chr.is_ascii_whitespace())?;
+        cursor.index = name_match.end;
+        cursor.word(":")?;
+        name_match.obj
+    };
 
     let val_match = cursor.fn_until(|chr: u8| chr == b'\n')?;
     let val = val_match.obj.trim();
-    if name.ends_with('+') {
-        let new_name = name.trim_end_matches('+');
+    if let Some(base_name) = name.strip_suffix('+') {
+        // without a value to append to, keep the `+` so it can be appended to an inherited one
+        let key = find_key(properties, |key| key.eq_ignore_ascii_case(base_name))
+            .or_else(|| find_key(properties, |key| key.eq_ignore_ascii_case(name)))
+            .unwrap_or(name);
         properties
-            .entry(new_name)
+            .entry(key)
             .and_modify(|n| {
                 write!(n.to_mut(), " {val}").unwrap(); // writing into a string is always safe
             })
             .or_insert(Cow::from(val));
     } else {
-        properties.insert(name, Cow::from(val));
+        if let Some(old) = find_key(properties, |key| key.eq_ignore_ascii_case(name)) {
+            properties.remove(old);
+        }
+        // a `name+` that came first is appended to this value
+        let appended = find_key(properties, |key| {
+            key.strip_suffix('+')
+                .is_some_and(|key| key.eq_ignore_ascii_case(name))
+        })
+        .and_then(|key| properties.remove(key));
+        let mut val = Cow::from(val);
+        if let Some(appended) = appended {
+            write!(val.to_mut(), " {appended}").unwrap();
+        }
+        properties.insert(name, val);
     }
 
     Ok(val_match.end + 1)
 }
+
+/// The key as written of the property matching `pred`.
+fn find_key<'a>(properties: &PropertyDrawer<'a>, pred: impl Fn(&str) -> bool) -> Option<&'a str> {
+    properties.keys().copied().find(|key| pred(key))
+}
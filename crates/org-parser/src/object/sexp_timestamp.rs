@@ -0,0 +1,93 @@
+use std::fmt::Display;
+
+use crate::constants::{LPAREN, RANGLE, RPAREN};
+use crate::diary::diary_sexp_matches;
+use crate::node_pool::NodeID;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+use super::{Date, InlineSrc};
+
+/// A timestamp whose dates are described by an Emacs Lisp expression.
+///
+/// ```example
+/// <%%(diary-float t 4 2)>
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SexpTimestamp<'a> {
+    /// The expression, including its parentheses
+    pub sexp: &'a str,
+}
+
+impl<'a> Parseable<'a> for SexpTimestamp<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("<%%")?;
+        if cursor.try_curr()? != LPAREN {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        let sexp_start = cursor.index;
+        let sexp = InlineSrc::parse_src(cursor, LPAREN, RPAREN)?;
+        cursor.move_to(sexp.end);
+        if cursor.try_curr()? != RANGLE {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        Ok(parser.alloc(
+            Self {
+                sexp: cursor.clamp(sexp_start, sexp.end),
+            },
+            start,
+            cursor.index + 1,
+            parent,
+        ))
+    }
+}
+
+impl SexpTimestamp<'_> {
+    /// Whether the timestamp occurs on `date`, see [`diary_sexp_matches`].
+    pub fn matches(&self, date: &Date) -> Option<bool> {
+        diary_sexp_matches(self.sexp, date)
+    }
+}
+
+impl Display for SexpTimestamp<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<%%{}>", self.sexp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn basic_sexp_timestamp() {
+        let input = "meeting <%%(diary-float t 4 2)> weekly";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, SexpTimestamp).unwrap();
+
+        assert_eq!(stamp.sexp, "(diary-float t 4 2)");
+        assert_eq!(stamp.to_string(), "<%%(diary-float t 4 2)>");
+        assert!(expr_in_pool!(parsed, Timestamp).is_none());
+    }
+
+    #[test]
+    fn not_sexp_timestamp() {
+        for input in [
+            "<%%(diary-float t 4 2)",
+            "<%%diary>",
+            "[%%(diary-float t 4 2)]",
+        ] {
+            let parsed = parse_org(input);
+            assert!(expr_in_pool!(parsed, SexpTimestamp).is_none(), "{input}");
+        }
+    }
+}
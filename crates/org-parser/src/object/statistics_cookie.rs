@@ -0,0 +1,261 @@
+use std::fmt::Display;
+
+use crate::constants::{RBRACK, SLASH};
+use crate::node_pool::NodeID;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+
+/// Progress of a [`Heading`](crate::element::Heading)'s children or an
+/// [`Item`](crate::element::Item)'s checkboxes.
+///
+/// ```example
+/// * TODO tasks [1/3]
+/// - [-] items [50%]
+///   - [X] one
+///   - [ ] two
+/// ```
+///
+/// The value written in the source can be stale, use [`Parser::statistics`] to recompute it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatisticsCookie {
+    /// `[2/5]`, either number can be missing: `[/]`
+    Fraction {
+        done: Option<u32>,
+        total: Option<u32>,
+    },
+    /// `[40%]`, `[%]`
+    Percent(Option<u32>),
+}
+
+impl<'a> Parseable<'a> for StatisticsCookie {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("[")?;
+
+        let done = parse_num(&mut cursor)?;
+        let cookie = match cursor.try_curr()? {
+            b'%' => {
+                cursor.next();
+                Self::Percent(done)
+            }
+            SLASH => {
+                cursor.next();
+                let total = parse_num(&mut cursor)?;
+                Self::Fraction { done, total }
+            }
+            _ => return Err(MatchError::InvalidLogic),
+        };
+
+        if cursor.try_curr()? != RBRACK {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        Ok(parser.alloc(cookie, start, cursor.index + 1, parent))
+    }
+}
+
+fn parse_num(cursor: &mut Cursor) -> Result<Option<u32>> {
+    let num_match = cursor.fn_while(|chr: u8| chr.is_ascii_digit())?;
+    cursor.move_to(num_match.end);
+    if num_match.obj.is_empty() {
+        Ok(None)
+    } else {
+        num_match
+            .obj
+            .parse()
+            .map(Some)
+            .or(Err(MatchError::InvalidLogic))
+    }
+}
+
+impl StatisticsCookie {
+    /// Creates a cookie of the same kind holding the given counts.
+    pub fn with_counts(self, done: u32, total: u32) -> Self {
+        match self {
+            Self::Fraction { .. } => Self::Fraction {
+                done: Some(done),
+                total: Some(total),
+            },
+            Self::Percent(_) => Self::Percent(Some((done * 100).checked_div(total).unwrap_or(0))),
+        }
+    }
+}
+
+impl Display for StatisticsCookie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let num = |val: Option<u32>| val.map(|v| v.to_string()).unwrap_or_default();
+        match self {
+            Self::Fraction { done, total } => write!(f, "[{}/{}]", num(*done), num(*total)),
+            Self::Percent(percent) => write!(f, "[{}%]", num(*percent)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatisticsCookie;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org, Parser};
+
+    fn cookie_id(parsed: &Parser) -> crate::NodeID {
+        let (id, _) = parsed
+            .pool
+            .iter_ids()
+            .find(|(_, node)| matches!(node.obj, Expr::StatisticsCookie(_)))
+            .unwrap();
+        id
+    }
+
+    #[test]
+    fn basic_cookies() {
+        let parsed = parse_org("[2/5]");
+        assert_eq!(
+            expr_in_pool!(parsed, StatisticsCookie),
+            Some(&StatisticsCookie::Fraction {
+                done: Some(2),
+                total: Some(5)
+            })
+        );
+
+        let parsed = parse_org("[40%]");
+        assert_eq!(
+            expr_in_pool!(parsed, StatisticsCookie),
+            Some(&StatisticsCookie::Percent(Some(40)))
+        );
+
+        let parsed = parse_org("[/] [%]");
+        assert_eq!(
+            expr_in_pool!(parsed, StatisticsCookie),
+            Some(&StatisticsCookie::Fraction {
+                done: None,
+                total: None
+            })
+        );
+    }
+
+    #[test]
+    fn not_cookies() {
+        for input in ["[2]", "[2/5", "[a/b]", "[2 /5]"] {
+            let parsed = parse_org(input);
+            assert!(expr_in_pool!(parsed, StatisticsCookie).is_none(), "{input}");
+        }
+    }
+
+    #[test]
+    fn heading_statistics() {
+        let input = r"
+* parent [0/0]
+** TODO one
+** DONE two
+** not counted
+*** DONE grandchild
+** DONE three
+";
+        let parsed = parse_org(input);
+        let cookie_id = cookie_id(&parsed);
+
+        assert_eq!(
+            parsed.statistics(cookie_id),
+            Some(StatisticsCookie::Fraction {
+                done: Some(2),
+                total: Some(3)
+            })
+        );
+    }
+
+    #[test]
+    fn heading_checkbox_statistics() {
+        let input = r"
+* parent [0/0]
+- [X] one
+- [ ] two
+  - [X] not counted
+- no checkbox
+- [X] three
+** child
+";
+        let parsed = parse_org(input);
+        assert_eq!(
+            parsed.statistics(cookie_id(&parsed)),
+            Some(StatisticsCookie::Fraction {
+                done: Some(2),
+                total: Some(3)
+            })
+        );
+    }
+
+    #[test]
+    fn heading_section_statistics() {
+        let input = r"
+* parent
+a cookie in the section [/]
+** DONE one
+";
+        let parsed = parse_org(input);
+        assert_eq!(parsed.statistics(cookie_id(&parsed)), None);
+    }
+
+    #[test]
+    fn nothing_to_count() {
+        let parsed = parse_org("* parent [3/4]\n** child\n");
+        assert_eq!(
+            parsed.statistics(cookie_id(&parsed)),
+            Some(StatisticsCookie::Fraction {
+                done: Some(0),
+                total: Some(0)
+            })
+        );
+
+        let parsed = parse_org("- parent [50%]\n  - child\n");
+        assert_eq!(
+            parsed.statistics(cookie_id(&parsed)),
+            Some(StatisticsCookie::Percent(Some(0)))
+        );
+    }
+
+    #[test]
+    fn heading_id_without_cookie() {
+        let parsed = parse_org("* a [1/3] task\n** DONE one\n");
+        let heading = parsed
+            .pool
+            .iter()
+            .find(|node| matches!(node.obj, Expr::Heading(_)))
+            .unwrap();
+        assert_eq!(heading.id_target.as_deref(), Some("a-task"));
+
+        // the rest of the title is kept as written
+        let parsed = parse_org("* second  heading [0/0]\n* [0/0] third\n");
+        let ids = parsed
+            .pool
+            .iter()
+            .filter_map(|node| match node.obj {
+                Expr::Heading(_) => node.id_target.as_deref(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["second--heading", "third"]);
+    }
+
+    #[test]
+    fn item_statistics() {
+        let input = r"
+- [-] parent [%]
+  - [X] one
+    - [ ] nested
+  - [ ] two
+  - not counted
+  - [X] three
+";
+        let parsed = parse_org(input);
+        let cookie_id = cookie_id(&parsed);
+
+        assert_eq!(
+            parsed.statistics(cookie_id),
+            Some(StatisticsCookie::Percent(Some(66)))
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::constants::{LANGLE, NEWLINE, RANGLE};
 use crate::node_pool::NodeID;
-use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+use crate::types::{Cursor, Expr, MatchError, ParseOpts, Parseable, Parser, Result};
 
 #[derive(Debug, Copy, Clone)]
 pub struct Target<'a>(pub &'a str);
@@ -29,3 +29,230 @@ impl<'a> Parseable<'a> for Target<'a> {
         Ok(ret_id)
     }
 }
+
+/// A target whose text is automatically linked to wherever it appears in the document.
+///
+/// ```example
+/// <<<radio>>>
+/// ```
+///
+/// Occurrences are turned into [`RadioLink`]s once the document is parsed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RadioTarget<'a>(pub &'a str);
+
+/// A plain-text occurrence of a [`RadioTarget`]'s text.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RadioLink<'a> {
+    /// The [`RadioTarget`] being linked to
+    pub target: NodeID,
+    /// The text as it appears in the document
+    pub contents: &'a str,
+}
+
+impl<'a> Parseable<'a> for RadioTarget<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        mut cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let start = cursor.index;
+        cursor.word("<<<")?;
+        if cursor.try_curr()?.is_ascii_whitespace() {
+            return Err(MatchError::InvalidLogic);
+        }
+
+        let inner_target_match =
+            cursor.fn_until(|chr: u8| chr == NEWLINE || chr == RANGLE || chr == LANGLE)?;
+        if inner_target_match
+            .obj
+            .ends_with(|c: char| c.is_ascii_whitespace())
+        {
+            return Err(MatchError::InvalidLogic);
+        }
+        cursor.index = inner_target_match.end;
+        cursor.word(">>>")?;
+
+        let ret_id = parser.alloc(Self(inner_target_match.obj), start, cursor.index, parent);
+
+        parser.pool[ret_id].id_target = Some(parser.generate_target(inner_target_match.obj));
+        Ok(ret_id)
+    }
+}
+
+/// Replaces occurrences of [`RadioTarget`] text in plain text with [`RadioLink`]s.
+///
+/// Matching is case-insensitive and only happens on word boundaries.
+/// Text that's already part of a link is left alone.
+pub(crate) fn link_radio_targets<'a>(parser: &mut Parser<'a>) {
+    let mut radio_targets: Vec<(NodeID, &'a str)> = Vec::new();
+    let mut plains: Vec<NodeID> = Vec::new();
+    for (node_id, node) in parser.pool.iter_ids() {
+        match node.obj {
+            Expr::RadioTarget(RadioTarget(text)) => radio_targets.push((node_id, text)),
+            Expr::Plain(_) => plains.push(node_id),
+            _ => {}
+        }
+    }
+    if radio_targets.is_empty() {
+        return;
+    }
+    // prefer the longest match when targets overlap
+    radio_targets.sort_by_key(|(_, text)| std::cmp::Reverse(text.len()));
+
+    for plain_id in plains {
+        if in_link(parser, plain_id) {
+            continue;
+        }
+        let node = &parser.pool[plain_id];
+        let (Expr::Plain(text), Some(parent)) = (&node.obj, node.parent) else {
+            continue;
+        };
+        let text = *text;
+        let node_start = node.start;
+
+        let mut new_ids = Vec::new();
+        let mut prev = 0;
+        let mut i = 0;
+        while i < text.len() {
+            let found = radio_targets.iter().find(|(_, target)| {
+                text.is_char_boundary(i)
+                    && text
+                        .get(i..i + target.len())
+                        .is_some_and(|cand| cand.eq_ignore_ascii_case(target))
+                    && !text[..i].ends_with(|c: char| c.is_alphanumeric())
+                    && !text[i + target.len()..].starts_with(|c: char| c.is_alphanumeric())
+            });
+
+            if let Some((target_id, target)) = found {
+                if prev < i {
+                    new_ids.push(parser.pool.alloc(
+                        &text[prev..i],
+                        node_start + prev,
+                        node_start + i,
+                        Some(parent),
+                    ));
+                }
+                new_ids.push(parser.pool.alloc(
+                    RadioLink {
+                        target: *target_id,
+                        contents: &text[i..i + target.len()],
+                    },
+                    node_start + i,
+                    node_start + i + target.len(),
+                    Some(parent),
+                ));
+                i += target.len();
+                prev = i;
+            } else {
+                i += 1;
+            }
+        }
+
+        if new_ids.is_empty() {
+            continue;
+        }
+        if prev < text.len() {
+            new_ids.push(parser.pool.alloc(
+                &text[prev..],
+                node_start + prev,
+                node_start + text.len(),
+                Some(parent),
+            ));
+        }
+
+        let parent_obj = &mut parser.pool[parent].obj;
+        let siblings = match parent_obj {
+            Expr::Heading(heading) => heading.title.as_mut().map(|title| &mut title.1),
+            _ => parent_obj.children_mut(),
+        };
+        if let Some(siblings) = siblings {
+            if let Some(pos) = siblings.iter().position(|&id| id == plain_id) {
+                siblings.splice(pos..=pos, new_ids);
+            }
+        }
+    }
+}
+
+fn in_link(parser: &Parser, node_id: NodeID) -> bool {
+    let mut curr = parser.pool[node_id].parent;
+    while let Some(parent_id) = curr {
+        if let Expr::RegularLink(_) | Expr::PlainLink(_) = parser.pool[parent_id].obj {
+            return true;
+        }
+        curr = parser.pool[parent_id].parent;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::node_pool::make_node_id;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org};
+
+    use super::*;
+
+    #[test]
+    fn basic_radio_target() {
+        let input = "<<<radio>>>";
+        let parsed = parse_org(input);
+        let target = expr_in_pool!(parsed, RadioTarget).unwrap();
+
+        assert_eq!(target, &RadioTarget("radio"));
+        assert!(parsed.targets.contains_key("radio"));
+    }
+
+    #[test]
+    fn invalid_radio_target() {
+        for input in [
+            "<<< radio>>>",
+            "<<<radio >>>",
+            "<<<radio>>",
+            "<<<ra\ndio>>>",
+        ] {
+            let parsed = parse_org(input);
+            assert!(expr_in_pool!(parsed, RadioTarget).is_none(), "{input}");
+        }
+    }
+
+    #[test]
+    fn radio_link() {
+        let input = r"
+We define <<<Org Mode>>> here.
+
+* using org mode
+
+Using ORG MODE, but not in org modes or [[https://orgmode.org][org mode]].
+";
+        let parsed = parse_org(input);
+        let target_id = parsed
+            .pool
+            .iter()
+            .position(|node| matches!(node.obj, Expr::RadioTarget(_)))
+            .unwrap();
+
+        let links: Vec<&RadioLink> = parsed
+            .pool
+            .iter()
+            .filter_map(|node| {
+                if let Expr::RadioLink(link) = &node.obj {
+                    Some(link)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].contents, "org mode");
+        assert_eq!(links[1].contents, "ORG MODE");
+        assert_eq!(links[0].target, make_node_id(target_id as u32));
+
+        // the links are spliced into the tree
+        let heading = expr_in_pool!(parsed, Heading).unwrap();
+        let title = &heading.title.as_ref().unwrap().1;
+        assert_eq!(title.len(), 2);
+        assert!(matches!(parsed.pool[title[1]].obj, Expr::RadioLink(_)));
+    }
+}
@@ -0,0 +1,694 @@
+use std::fmt::Display;
+
+use crate::constants::{COLON, HYPHEN, LANGLE, LBRACK, PERIOD, PLUS, RANGLE, RBRACK, SPACE};
+use crate::node_pool::NodeID;
+use crate::types::{Cursor, MatchError, ParseOpts, Parseable, Parser, Result};
+use crate::utils::Match;
+
+/// A point in time, or a range between two points in time.
+///
+/// ```text
+/// <2023-08-16 Wed 10:00 +1w -3d>
+/// [2023-08-16 Wed]--[2023-08-18 Fri]
+/// <2023-08-16 Wed 10:00-12:00>
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timestamp<'a> {
+    /// `<...>` timestamps are active, `[...]` are inactive.
+    pub active: bool,
+    pub start: DateTime<'a>,
+    /// Set for ranges, both `<a>--<b>` and same day `<a 10:00-12:00>`.
+    pub end: Option<DateTime<'a>>,
+    /// How the range is written, only meaningful when `end` is set.
+    pub range_kind: RangeKind,
+    pub repeater: Option<Repeater>,
+    pub warning: Option<Warning>,
+}
+
+/// The two ways of writing a [`Timestamp`] range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RangeKind {
+    /// `<2023-08-16 Wed 10:00>--<2023-08-16 Wed 12:00>`
+    #[default]
+    Dates,
+    /// `<2023-08-16 Wed 10:00-12:00>`, always on the same day
+    Times,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime<'a> {
+    pub date: Date<'a>,
+    pub time: Option<Time>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date<'a> {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub dayname: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Time {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Repeater {
+    pub mark: RepeaterMark,
+    pub value: u32,
+    pub unit: TimeUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeaterMark {
+    /// `+`
+    Cumulate,
+    /// `++`
+    CatchUp,
+    /// `.+`
+    Restart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Warning {
+    pub mark: WarningMark,
+    pub value: u32,
+    pub unit: TimeUnit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningMark {
+    /// `-`
+    All,
+    /// `--`, only warn for the first occurrence of a repeated timestamp
+    First,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Date<'_> {
+    /// Number of days since 1970-01-01, negative for earlier dates.
+    pub fn to_days(&self) -> i64 {
+        // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let month = i64::from(self.month);
+        let year = i64::from(self.year) - i64::from(month <= 2);
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+}
+
+impl DateTime<'_> {
+    /// Number of minutes since 1970-01-01 00:00, a missing time is treated as midnight.
+    pub fn to_minutes(&self) -> i64 {
+        let (hour, minute) = self.time.map_or((0, 0), |time| {
+            (i64::from(time.hour), i64::from(time.minute))
+        });
+        self.date.to_days() * 24 * 60 + hour * 60 + minute
+    }
+
+    /// The date and time a number of minutes after 1970-01-01 00:00, the inverse of
+    /// [`DateTime::to_minutes`].
+    pub fn from_minutes(minutes: i64) -> DateTime<'static> {
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = minutes.div_euclid(24 * 60);
+        let day_minutes = minutes.rem_euclid(24 * 60);
+        let shifted = days + 719468;
+        let era = shifted.div_euclid(146097);
+        let day_of_era = shifted - era * 146097;
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        // 1970-01-01 was a Thursday
+        const DAYNAMES: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+        DateTime {
+            date: Date {
+                year: year as u16,
+                month: month as u8,
+                day: day as u8,
+                dayname: Some(DAYNAMES[days.rem_euclid(7) as usize]),
+            },
+            time: Some(Time {
+                hour: (day_minutes / 60) as u8,
+                minute: (day_minutes % 60) as u8,
+            }),
+        }
+    }
+}
+
+impl<'a> Parseable<'a> for Timestamp<'a> {
+    fn parse(
+        parser: &mut Parser<'a>,
+        cursor: Cursor<'a>,
+        parent: Option<NodeID>,
+        parse_opts: ParseOpts,
+    ) -> Result<NodeID> {
+        let stamp_match = parse_timestamp(cursor)?;
+        Ok(parser.alloc(stamp_match.obj, stamp_match.start, stamp_match.end, parent))
+    }
+}
+
+/// Parses a timestamp without allocating it into the pool.
+///
+/// Used by elements which store timestamps directly, such as planning lines.
+pub(crate) fn parse_timestamp(mut cursor: Cursor) -> Result<Match<Timestamp>> {
+    let start = cursor.index;
+    let first = parse_stamp(cursor)?;
+    cursor.move_to(first.end);
+    let mut stamp = first.obj;
+
+    if let Some(end_time) = stamp.end_time {
+        let end = DateTime {
+            date: stamp.date_time.date,
+            time: Some(end_time),
+        };
+        return Ok(Match {
+            start,
+            end: cursor.index,
+            obj: stamp.into_timestamp(Some(end), RangeKind::Times),
+        });
+    }
+
+    let mut end = None;
+    if cursor.rest().starts_with(b"--") {
+        if let Ok(second) = parse_stamp(cursor.adv_copy(2)) {
+            if second.obj.active == stamp.active {
+                end = Some(second.obj.date_time);
+                cursor.move_to(second.end);
+                // the range is tied to the repeater/warning of its start,
+                // fall back to the end's if the start doesn't have any
+                stamp.repeater = stamp.repeater.or(second.obj.repeater);
+                stamp.warning = stamp.warning.or(second.obj.warning);
+            }
+        }
+    }
+
+    Ok(Match {
+        start,
+        end: cursor.index,
+        obj: stamp.into_timestamp(end, RangeKind::Dates),
+    })
+}
+
+/// A single bracketed timestamp: `<...>` or `[...]`
+struct Stamp<'a> {
+    active: bool,
+    date_time: DateTime<'a>,
+    end_time: Option<Time>,
+    repeater: Option<Repeater>,
+    warning: Option<Warning>,
+}
+
+impl<'a> Stamp<'a> {
+    fn into_timestamp(self, end: Option<DateTime<'a>>, range_kind: RangeKind) -> Timestamp<'a> {
+        Timestamp {
+            active: self.active,
+            start: self.date_time,
+            end,
+            range_kind,
+            repeater: self.repeater,
+            warning: self.warning,
+        }
+    }
+}
+
+fn parse_stamp(mut cursor: Cursor) -> Result<Match<Stamp>> {
+    let start = cursor.index;
+    let (active, closing) = match cursor.try_curr()? {
+        LANGLE => (true, RANGLE),
+        LBRACK => (false, RBRACK),
+        _ => return Err(MatchError::InvalidLogic),
+    };
+    cursor.next();
+
+    let date_match = parse_date(cursor)?;
+    cursor.move_to(date_match.end);
+
+    let mut time = None;
+    let mut end_time = None;
+    let mut repeater = None;
+    let mut warning = None;
+
+    loop {
+        let before_ws = cursor.index;
+        cursor.skip_ws();
+        let chr = cursor.try_curr()?;
+        if chr == closing {
+            cursor.next();
+            break;
+        } else if before_ws == cursor.index {
+            // every component is separated by whitespace
+            return Err(MatchError::InvalidLogic);
+        }
+
+        match chr {
+            chr if chr.is_ascii_digit() && time.is_none() && repeater.is_none() => {
+                let time_match = parse_time(cursor)?;
+                cursor.move_to(time_match.end);
+                time = Some(time_match.obj);
+                if cursor.curr() == HYPHEN {
+                    let end_match = parse_time(cursor.adv_copy(1))?;
+                    cursor.move_to(end_match.end);
+                    end_time = Some(end_match.obj);
+                }
+            }
+            PLUS | PERIOD if repeater.is_none() => {
+                let mark = if cursor.word(".+").is_ok() {
+                    RepeaterMark::Restart
+                } else if cursor.word("++").is_ok() {
+                    RepeaterMark::CatchUp
+                } else if cursor.word("+").is_ok() {
+                    RepeaterMark::Cumulate
+                } else {
+                    return Err(MatchError::InvalidLogic);
+                };
+                let (value, unit) = parse_interval(&mut cursor)?;
+                repeater = Some(Repeater { mark, value, unit });
+            }
+            HYPHEN if warning.is_none() => {
+                let mark = if cursor.word("--").is_ok() {
+                    WarningMark::First
+                } else {
+                    cursor.next();
+                    WarningMark::All
+                };
+                let (value, unit) = parse_interval(&mut cursor)?;
+                warning = Some(Warning { mark, value, unit });
+            }
+            _ => return Err(MatchError::InvalidLogic),
+        }
+    }
+
+    Ok(Match {
+        start,
+        end: cursor.index,
+        obj: Stamp {
+            active,
+            date_time: DateTime {
+                date: date_match.obj,
+                time,
+            },
+            end_time,
+            repeater,
+            warning,
+        },
+    })
+}
+
+/// YYYY-MM-DD DAYNAME
+fn parse_date(mut cursor: Cursor) -> Result<Match<Date>> {
+    let start = cursor.index;
+    let year = parse_digits(&mut cursor, 4, 4)?;
+    cursor.word("-")?;
+    let month = parse_digits(&mut cursor, 2, 2)?;
+    cursor.word("-")?;
+    let day = parse_digits(&mut cursor, 2, 2)?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return Err(MatchError::InvalidLogic);
+    }
+
+    let mut dayname = None;
+    if cursor.try_curr()? == SPACE {
+        let mut name_cursor = cursor;
+        name_cursor.skip_ws();
+        let name_match = name_cursor.fn_while(|chr| {
+            !(chr.is_ascii_whitespace()
+                || chr.is_ascii_digit()
+                || matches!(chr, PLUS | HYPHEN | RBRACK | RANGLE))
+        })?;
+        if !name_match.obj.is_empty() {
+            dayname = Some(name_match.obj);
+            cursor.move_to(name_match.end);
+        }
+    }
+
+    Ok(Match {
+        start,
+        end: cursor.index,
+        obj: Date {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            dayname,
+        },
+    })
+}
+
+/// H:MM or HH:MM
+fn parse_time(mut cursor: Cursor) -> Result<Match<Time>> {
+    let start = cursor.index;
+    let hour = parse_digits(&mut cursor, 1, 2)?;
+    if cursor.try_curr()? != COLON {
+        return Err(MatchError::InvalidLogic);
+    }
+    cursor.next();
+    let minute = parse_digits(&mut cursor, 2, 2)?;
+
+    if hour > 24 || minute > 59 {
+        return Err(MatchError::InvalidLogic);
+    }
+
+    Ok(Match {
+        start,
+        end: cursor.index,
+        obj: Time {
+            hour: hour as u8,
+            minute: minute as u8,
+        },
+    })
+}
+
+/// The VALUE UNIT portion of a repeater or warning delay, e.g. `3d`
+fn parse_interval(cursor: &mut Cursor) -> Result<(u32, TimeUnit)> {
+    let value = parse_digits(cursor, 1, 9)?;
+    let unit = match cursor.try_curr()? {
+        b'h' => TimeUnit::Hour,
+        b'd' => TimeUnit::Day,
+        b'w' => TimeUnit::Week,
+        b'm' => TimeUnit::Month,
+        b'y' => TimeUnit::Year,
+        _ => return Err(MatchError::InvalidLogic),
+    };
+    cursor.next();
+    Ok((value, unit))
+}
+
+fn parse_digits(cursor: &mut Cursor, min: usize, max: usize) -> Result<u32> {
+    let digit_match = cursor.fn_while(|chr| chr.is_ascii_digit())?;
+    if !(min..=max).contains(&digit_match.len()) {
+        return Err(MatchError::InvalidLogic);
+    }
+    cursor.move_to(digit_match.end);
+    // can't fail, we only captured ascii digits
+    Ok(digit_match.obj.parse().unwrap())
+}
+
+impl Display for Timestamp<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (open, close) = if self.active { ('<', '>') } else { ('[', ']') };
+
+        write!(f, "{open}{}", self.start)?;
+        if let Some(end) = self.end {
+            if let Some(end_time) = self.same_day_end_time() {
+                write!(f, "-{end_time}")?;
+                self.fmt_modifiers(f)?;
+                return write!(f, "{close}");
+            }
+            self.fmt_modifiers(f)?;
+            write!(f, "{close}--{open}{end}")?;
+        } else {
+            self.fmt_modifiers(f)?;
+        }
+        write!(f, "{close}")
+    }
+}
+
+impl Timestamp<'_> {
+    /// The end of a range written as `<a 10:00-12:00>`.
+    ///
+    /// `None` for other timestamps, including [`RangeKind::Times`] ranges whose
+    /// ends don't fit on a single day, which are written as `<a>--<b>` instead.
+    pub fn same_day_end_time(&self) -> Option<Time> {
+        let end = self.end?;
+        (self.range_kind == RangeKind::Times
+            && end.date == self.start.date
+            && self.start.time.is_some())
+        .then_some(end.time)
+        .flatten()
+    }
+
+    fn fmt_modifiers(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(repeater) = self.repeater {
+            write!(f, " {repeater}")?;
+        }
+        if let Some(warning) = self.warning {
+            write!(f, " {warning}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for DateTime<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.date)?;
+        if let Some(time) = self.time {
+            write!(f, " {time}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Date<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)?;
+        if let Some(dayname) = self.dayname {
+            write!(f, " {dayname}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+impl Display for Repeater {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mark = match self.mark {
+            RepeaterMark::Cumulate => "+",
+            RepeaterMark::CatchUp => "++",
+            RepeaterMark::Restart => ".+",
+        };
+        write!(f, "{mark}{}{}", self.value, self.unit)
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mark = match self.mark {
+            WarningMark::All => "-",
+            WarningMark::First => "--",
+        };
+        write!(f, "{mark}{}{}", self.value, self.unit)
+    }
+}
+
+impl Display for TimeUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TimeUnit::Hour => "h",
+            TimeUnit::Day => "d",
+            TimeUnit::Week => "w",
+            TimeUnit::Month => "m",
+            TimeUnit::Year => "y",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expr_in_pool, parse_org, types::Expr};
+
+    use super::*;
+
+    #[test]
+    fn basic_timestamp() {
+        let input = "<2023-08-16 Wed>";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        assert_eq!(
+            stamp,
+            &Timestamp {
+                active: true,
+                start: DateTime {
+                    date: Date {
+                        year: 2023,
+                        month: 8,
+                        day: 16,
+                        dayname: Some("Wed"),
+                    },
+                    time: None,
+                },
+                end: None,
+                range_kind: RangeKind::Dates,
+                repeater: None,
+                warning: None,
+            }
+        );
+    }
+
+    #[test]
+    fn inactive_timestamp_no_dayname() {
+        let input = "[2023-08-16 9:05]";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        assert!(!stamp.active);
+        assert_eq!(stamp.start.date.dayname, None);
+        assert_eq!(stamp.start.time, Some(Time { hour: 9, minute: 5 }));
+    }
+
+    #[test]
+    fn timestamp_repeater_warning() {
+        let input = "<2023-08-16 Wed 10:00 .+1w --3d>";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        assert_eq!(
+            stamp.repeater,
+            Some(Repeater {
+                mark: RepeaterMark::Restart,
+                value: 1,
+                unit: TimeUnit::Week,
+            })
+        );
+        assert_eq!(
+            stamp.warning,
+            Some(Warning {
+                mark: WarningMark::First,
+                value: 3,
+                unit: TimeUnit::Day,
+            })
+        );
+        assert_eq!(stamp.to_string(), input);
+    }
+
+    #[test]
+    fn timestamp_range() {
+        let input = "[2023-08-16 Wed]--[2023-08-18 Fri]";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        let end = stamp.end.unwrap();
+        assert_eq!(end.date.day, 18);
+        assert_eq!(end.date.dayname, Some("Fri"));
+        assert_eq!(stamp.to_string(), input);
+    }
+
+    #[test]
+    fn timestamp_same_day_range() {
+        let input = "<2023-08-16 Wed 10:00-12:30 ++1m>";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        let end = stamp.end.unwrap();
+        assert_eq!(end.date, stamp.start.date);
+        assert_eq!(
+            end.time,
+            Some(Time {
+                hour: 12,
+                minute: 30
+            })
+        );
+        assert_eq!(stamp.range_kind, RangeKind::Times);
+        assert_eq!(stamp.repeater.unwrap().mark, RepeaterMark::CatchUp);
+        assert_eq!(stamp.to_string(), input);
+
+        // written as two timestamps, even though they're on the same day
+        let input = "<2023-08-16 Wed 10:00>--<2023-08-16 Wed 12:30>";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        assert_eq!(stamp.range_kind, RangeKind::Dates);
+        assert_eq!(stamp.same_day_end_time(), None);
+        assert_eq!(stamp.to_string(), input);
+    }
+
+    #[test]
+    fn timestamp_mismatched_range() {
+        // different kinds don't form a range
+        let input = "<2023-08-16 Wed>--[2023-08-18 Fri]";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        assert_eq!(stamp.end, None);
+    }
+
+    #[test]
+    fn timestamp_in_paragraph() {
+        let input = "meeting on <2023-08-16 Wed 10:00>, don't forget";
+        let parsed = parse_org(input);
+        let stamp = expr_in_pool!(parsed, Timestamp).unwrap();
+
+        assert_eq!(
+            stamp.start.time,
+            Some(Time {
+                hour: 10,
+                minute: 0
+            })
+        );
+    }
+
+    #[test]
+    fn date_arithmetic() {
+        let date = |year, month, day| Date {
+            year,
+            month,
+            day,
+            dayname: None,
+        };
+        assert_eq!(date(1970, 1, 1).to_days(), 0);
+        assert_eq!(date(2000, 3, 1).to_days(), 11017);
+        assert_eq!(date(2024, 3, 1).to_days() - date(2024, 2, 28).to_days(), 2);
+
+        let start = DateTime {
+            date: date(2023, 8, 16),
+            time: Some(Time {
+                hour: 23,
+                minute: 15,
+            }),
+        };
+        let end = DateTime {
+            date: date(2023, 8, 17),
+            time: Some(Time { hour: 1, minute: 0 }),
+        };
+        assert_eq!(end.to_minutes() - start.to_minutes(), 105);
+        assert_eq!(
+            DateTime::from_minutes(end.to_minutes()).to_string(),
+            "2023-08-17 Thu 01:00"
+        );
+        assert_eq!(
+            DateTime::from_minutes(date(2000, 2, 29).to_days() * 24 * 60).to_string(),
+            "2000-02-29 Tue 00:00"
+        );
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        for input in [
+            "<2023-8-16 Wed>",
+            "<2023-13-16 Wed>",
+            "<2023-08-16 Wed",
+            "<2023-08-16 Wed 10:00 +1x>",
+            "[2023-08-16 Wed 1000]",
+        ] {
+            let parsed = parse_org(input);
+            assert!(expr_in_pool!(parsed, Timestamp).is_none(), "{input}");
+        }
+    }
+}
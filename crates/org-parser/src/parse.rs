@@ -5,12 +5,13 @@ use crate::constants::{
 use crate::node_pool::NodeID;
 
 use crate::element::{
-    Block, Comment, Drawer, FootnoteDef, Heading, Item, Keyword, LatexEnv, Paragraph, PlainList,
-    Table,
+    BabelCall, Block, Clock, Comment, DiarySexp, Drawer, DynamicBlock, FixedWidth, FootnoteDef,
+    Heading, InlineTask, Item, Keyword, LatexEnv, Paragraph, PlainList, Table,
 };
 use crate::object::{
-    parse_angle_link, parse_plain_link, Bold, Code, Emoji, ExportSnippet, FootnoteRef, InlineSrc,
-    Italic, LatexFragment, MacroCall, RegularLink, StrikeThrough, Subscript, Superscript, Target,
+    parse_angle_link, parse_plain_link, Bold, Citation, Code, Emoji, ExportSnippet, FootnoteRef,
+    InlineBabelCall, InlineSrc, Italic, LatexFragment, MacroCall, RadioTarget, RegularLink,
+    SexpTimestamp, StatisticsCookie, StrikeThrough, Subscript, Superscript, Target, Timestamp,
     Underline, Verbatim,
 };
 use crate::types::{Cursor, Expr, MarkupKind, MatchError, ParseOpts, Parseable, Parser, Result};
@@ -95,6 +96,10 @@ pub(crate) fn parse_element<'a>(
                 if let ret @ Ok(_) = PlainList::parse(parser, cursor, parent, new_opts) {
                     return ret;
                 }
+            } else if let ret @ Ok(_) =
+                InlineTask::parse(parser, cursor, parent, ParseOpts::default())
+            {
+                return ret;
             } else if let ret @ Ok(_) = Heading::parse(parser, cursor, parent, ParseOpts::default())
             {
                 return ret;
@@ -136,10 +141,16 @@ pub(crate) fn parse_element<'a>(
         chr if chr.is_ascii_alphanumeric() => {
             if let ret @ Ok(_) = PlainList::parse(parser, cursor, parent, new_opts) {
                 return ret;
+            } else if let ret @ Ok(_) = Clock::parse(parser, cursor, parent, no_para_opts) {
+                return ret;
             }
         }
         POUND => {
-            if let ret @ Ok(_) = Keyword::parse(parser, cursor, parent, no_para_opts) {
+            if let ret @ Ok(_) = BabelCall::parse(parser, cursor, parent, no_para_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = DynamicBlock::parse(parser, cursor, parent, no_para_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = Keyword::parse(parser, cursor, parent, no_para_opts) {
                 return ret;
             } else if let ret @ Ok(_) = Block::parse(parser, cursor, parent, no_para_opts) {
                 return ret;
@@ -158,7 +169,9 @@ pub(crate) fn parse_element<'a>(
             }
         }
         COLON => {
-            if let ret @ Ok(_) = Drawer::parse(parser, cursor, parent, no_para_opts) {
+            if let ret @ Ok(_) = FixedWidth::parse(parser, cursor, parent, no_para_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = Drawer::parse(parser, cursor, parent, no_para_opts) {
                 return ret;
             }
         }
@@ -169,6 +182,11 @@ pub(crate) fn parse_element<'a>(
                 }
             }
         }
+        b'%' if indentation_level == 0 => {
+            if let ret @ Ok(_) = DiarySexp::parse(parser, cursor, parent, no_para_opts) {
+                return ret;
+            }
+        }
         _ => {}
     }
 
@@ -231,10 +249,17 @@ pub(crate) fn parse_object<'a>(
             }
         }
         LBRACK => {
-            if let ret @ Ok(_) = RegularLink::parse(parser, cursor, parent, parse_opts) {
+            if let ret @ Ok(_) = Citation::parse(parser, cursor, parent, parse_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = RegularLink::parse(parser, cursor, parent, parse_opts) {
                 return ret;
             } else if let ret @ Ok(_) = FootnoteRef::parse(parser, cursor, parent, parse_opts) {
                 return ret;
+            } else if let ret @ Ok(_) = Timestamp::parse(parser, cursor, parent, parse_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = StatisticsCookie::parse(parser, cursor, parent, parse_opts)
+            {
+                return ret;
             }
         }
         RBRACK => {
@@ -274,6 +299,10 @@ pub(crate) fn parse_object<'a>(
             }
         }
         NEWLINE => {
+            // every line of a verse block is part of it, even ones that look like elements
+            if parse_opts.markup.contains(MarkupKind::Verse) {
+                return Ok(parser.alloc(Expr::SoftBreak, cursor.index, cursor.index + 1, parent));
+            }
             parse_opts.list_line = false;
             // REVIEW: added to make parsing  a table from a NEWLINE
             // work, not sure if needed elsewhere i.e. why didn't i catch
@@ -298,7 +327,13 @@ pub(crate) fn parse_object<'a>(
             }
         }
         LANGLE => {
-            if let ret @ Ok(_) = parse_angle_link(parser, cursor, parent, parse_opts) {
+            if let ret @ Ok(_) = Timestamp::parse(parser, cursor, parent, parse_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = SexpTimestamp::parse(parser, cursor, parent, parse_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = parse_angle_link(parser, cursor, parent, parse_opts) {
+                return ret;
+            } else if let ret @ Ok(_) = RadioTarget::parse(parser, cursor, parent, parse_opts) {
                 return ret;
             } else if let ret @ Ok(_) = Target::parse(parser, cursor, parent, parse_opts) {
                 return ret;
@@ -329,6 +364,11 @@ pub(crate) fn parse_object<'a>(
                 return ret;
             }
         }
+        b'c' => {
+            if let ret @ Ok(_) = InlineBabelCall::parse(parser, cursor, parent, parse_opts) {
+                return ret;
+            }
+        }
         LBRACE => {
             if let ret @ Ok(_) = MacroCall::parse(parser, cursor, parent, parse_opts) {
                 return ret;
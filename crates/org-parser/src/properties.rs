@@ -0,0 +1,213 @@
+//! Resolving the properties of a node, including inherited ones.
+
+use std::borrow::Cow;
+
+use crate::element::PropertyDrawer;
+use crate::node_pool::NodeID;
+use crate::types::{Expr, Parser};
+
+/// Properties that are inherited without being asked for.
+const INHERITED_PROPERTIES: [&str; 5] = [
+    "CATEGORY",
+    "ARCHIVE",
+    "COLUMNS",
+    "LOGGING",
+    "EXPORT_FILE_NAME",
+];
+
+/// Whether a property is inherited from ancestors and `#+PROPERTY:` lines by default.
+///
+/// These are `CATEGORY`, `ARCHIVE`, `COLUMNS`, `LOGGING`, `EXPORT_FILE_NAME`
+/// and every `header-args` property.
+pub fn is_inherited_property(name: &str) -> bool {
+    INHERITED_PROPERTIES
+        .iter()
+        .any(|inherited| inherited.eq_ignore_ascii_case(name))
+        || name
+            .get(.."header-args".len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("header-args"))
+}
+
+impl<'a> Parser<'a> {
+    /// The value of a property for a node, inherited if [`is_inherited_property`] says so.
+    ///
+    /// See [`Parser::property_with`].
+    pub fn property(&self, node_id: NodeID, name: &str) -> Option<Cow<'_, str>> {
+        self.property_with(node_id, name, is_inherited_property(name))
+    }
+
+    /// The value of a property for a node, looked up case-insensitively.
+    ///
+    /// A node that isn't a [`Heading`](crate::element::Heading) has the properties of the heading
+    /// it's in. With `inherit`, a property the heading doesn't set is taken from its ancestors,
+    /// and then from `#+PROPERTY:` lines.
+    ///
+    /// Values of `NAME+` are appended to the value found so far, with a space in between.
+    pub fn property_with(
+        &self,
+        node_id: NodeID,
+        name: &str,
+        inherit: bool,
+    ) -> Option<Cow<'_, str>> {
+        match self.property_values(node_id, name, inherit).as_slice() {
+            [] => None,
+            [val] => Some(Cow::Borrowed(val)),
+            values => Some(values.join(" ").into()),
+        }
+    }
+
+    /// The values [`Parser::property_with`] joins into a property, outermost first.
+    ///
+    /// The first is the value of `NAME`, if any, and the rest are those of `NAME+`.
+    pub fn property_values(&self, node_id: NodeID, name: &str, inherit: bool) -> Vec<&str> {
+        // appended values, innermost first
+        let mut appended: Vec<&str> = Vec::new();
+        let mut base: Option<&str> = None;
+
+        let mut curr = Some(node_id);
+        while let Some(curr_id) = curr {
+            let node = &self.pool[curr_id];
+            if let Expr::Heading(heading) = &node.obj {
+                if let Some(properties) = &heading.properties {
+                    if let Some(val) = find_property(properties, name, false) {
+                        base = Some(val);
+                        break;
+                    }
+                    appended.extend(find_property(properties, name, true));
+                }
+                if !inherit {
+                    break;
+                }
+            }
+            curr = node.parent;
+        }
+
+        let mut ret = Vec::new();
+        match base {
+            Some(base) => ret.push(base),
+            None if inherit => {
+                for (key, val) in self.keywords.properties() {
+                    if key.eq_ignore_ascii_case(name) {
+                        ret.clear();
+                        ret.push(val);
+                    } else if key
+                        .strip_suffix('+')
+                        .is_some_and(|key| key.eq_ignore_ascii_case(name))
+                    {
+                        ret.push(val);
+                    }
+                }
+            }
+            None => {}
+        }
+        ret.extend(appended.into_iter().rev());
+        ret
+    }
+}
+
+/// Finds `name`, or `name+` when `appended`, in a property drawer.
+fn find_property<'p>(
+    properties: &'p PropertyDrawer,
+    name: &str,
+    appended: bool,
+) -> Option<&'p str> {
+    properties.iter().find_map(|(key, val)| {
+        let key = if appended {
+            key.strip_suffix('+')?
+        } else {
+            key
+        };
+        key.eq_ignore_ascii_case(name).then_some(val.as_ref())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_org;
+    use crate::test_utils::heading_id;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn inherited_properties() {
+        let parser = parse_org(
+            r"#+PROPERTY: CATEGORY work
+#+PROPERTY: var foo=1
+* parent
+:PROPERTIES:
+:EXPORT_FILE_NAME: parent.html
+:Effort: 1:00
+:END:
+** child
+:PROPERTIES:
+:category: home
+:END:
+text
+",
+        );
+        let child = heading_id(&parser, "child");
+        let text = parser.pool[child].obj.children().unwrap()[0];
+
+        assert_eq!(parser.property(text, "CATEGORY").as_deref(), Some("home"));
+        assert_eq!(
+            parser.property(child, "export_file_name").as_deref(),
+            Some("parent.html")
+        );
+        assert_eq!(parser.property(child, "EFFORT"), None);
+        assert_eq!(
+            parser.property_with(child, "EFFORT", true).as_deref(),
+            Some("1:00")
+        );
+        assert_eq!(
+            parser
+                .property(heading_id(&parser, "parent"), "CATEGORY")
+                .as_deref(),
+            Some("work")
+        );
+        assert_eq!(
+            parser.property_with(child, "var", true).as_deref(),
+            Some("foo=1")
+        );
+        assert_eq!(parser.property_with(child, "var", false), None);
+    }
+
+    #[test]
+    fn accumulated_properties() {
+        let parser = parse_org(
+            r"#+PROPERTY: header-args :results silent
+#+PROPERTY: header-args+ :exports both
+* parent
+:PROPERTIES:
+:header-args+: :session one
+:END:
+** child
+:PROPERTIES:
+:header-args+: :var x=1
+:END:
+** reset
+:PROPERTIES:
+:header-args: :dir /tmp
+:header-args+: :cache yes
+:END:
+",
+        );
+
+        assert_eq!(
+            parser
+                .property(heading_id(&parser, "child"), "header-args")
+                .as_deref(),
+            Some(":results silent :exports both :session one :var x=1")
+        );
+        assert_eq!(
+            parser
+                .property(heading_id(&parser, "reset"), "header-args")
+                .as_deref(),
+            Some(":dir /tmp :cache yes")
+        );
+        assert_eq!(
+            parser
+                .property_with(heading_id(&parser, "child"), "header-args", false)
+                .as_deref(),
+            Some(":var x=1")
+        );
+    }
+}
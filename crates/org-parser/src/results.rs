@@ -0,0 +1,102 @@
+//! Replacing the results of source blocks in the AST.
+
+use std::ops::Range;
+
+use crate::element::Block;
+use crate::node_pool::NodeID;
+use crate::parse::parse_element;
+use crate::types::{Cursor, Expr, ParseOpts, Parser};
+
+impl<'a> Parser<'a> {
+    /// Replaces the source with `source`, which must start with the current source.
+    ///
+    /// The spans of every node stay valid. Text after the end of the document isn't
+    /// part of it, but can be parsed into the AST with [`Parser::set_results`].
+    ///
+    /// # Panics
+    ///
+    /// If `source` doesn't start with the current source.
+    pub fn extend_source(&mut self, source: &'a str) {
+        assert!(
+            source.starts_with(self.source),
+            "the extended source must start with the current source"
+        );
+        self.source = source;
+    }
+
+    /// Parses the element at `range` of the source as the results of the source block
+    /// at `block_id`, replacing its previous results.
+    ///
+    /// `hash` is written as `#+RESULTS[hash]:`, see `:cache`.
+    ///
+    /// Returns the id of the results, or `None` if the node isn't a source block
+    /// or no element starts at `range`.
+    pub fn set_results(
+        &mut self,
+        block_id: NodeID,
+        range: Range<usize>,
+        hash: Option<&'a str>,
+    ) -> Option<NodeID> {
+        if !matches!(self.pool[block_id].obj, Expr::Block(Block::Src { .. })) {
+            return None;
+        }
+        let mut cursor = Cursor::new(self.source.as_bytes()).cut_off(range.end);
+        cursor.move_to(range.start);
+        let results_id = parse_element(self, cursor, Some(block_id), ParseOpts::default()).ok()?;
+
+        if let Expr::Block(Block::Src {
+            results,
+            results_hash,
+            ..
+        }) = &mut self.pool[block_id].obj
+        {
+            *results = Some(results_id);
+            *results_hash = hash;
+        }
+        Some(results_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::element::Block;
+    use crate::parse_org;
+    use crate::types::Expr;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn replace_results() {
+        let input = "#+NAME: one\n#+begin_src sh\necho 1\n#+end_src\n\n#+RESULTS:\n: old\n";
+        let source = format!("{input}\n| a | b |\nabc");
+        let mut parsed = parse_org(input);
+        parsed.extend_source(&source);
+        let block_id = parsed.names["one"];
+
+        let table_start = input.len() + 1;
+        let results_id = parsed
+            .set_results(
+                block_id,
+                table_start..source.len() - 3,
+                Some(&source[source.len() - 3..]),
+            )
+            .unwrap();
+        assert!(matches!(parsed.pool[results_id].obj, Expr::Table(_)));
+        assert_eq!(parsed.pool[results_id].parent, Some(block_id));
+        let Expr::Block(Block::Src {
+            results,
+            results_hash,
+            ..
+        }) = &parsed.pool[block_id].obj
+        else {
+            unreachable!()
+        };
+        assert_eq!(*results, Some(results_id));
+        assert_eq!(*results_hash, Some("abc"));
+
+        // not a source block
+        assert_eq!(
+            parsed.set_results(results_id, table_start..source.len(), None),
+            None
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! In-buffer settings that change how the document is parsed.
+
+/// Settings provided to [`parse_org_with`](crate::parse_org_with).
+///
+/// In-buffer settings take priority: e.g. if the document contains any `#+TODO:` lines,
+/// [`ParseSettings::todo_keywords`] is ignored.
+#[derive(Debug, Clone)]
+pub struct ParseSettings<'a> {
+    pub todo_keywords: TodoKeywords<'a>,
+    /// The minimum number of stars of an [`InlineTask`](crate::element::InlineTask),
+    /// `org-inlinetask-min-level` in Emacs. Defaults to 15.
+    pub inline_task_min_level: usize,
+}
+
+impl Default for ParseSettings<'_> {
+    fn default() -> Self {
+        Self {
+            todo_keywords: TodoKeywords::default(),
+            inline_task_min_level: 15,
+        }
+    }
+}
+
+/// Whether a TODO keyword marks an unfinished or a finished task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoState {
+    Active,
+    Done,
+}
+
+/// Every recognized TODO keyword, defined through:
+///
+/// ```example
+/// #+TODO: TODO NEXT(n) WAIT(w@/!) | DONE(d!) CANCELLED(c@)
+/// #+SEQ_TODO: REPORT BUG KNOWNCAUSE | FIXED
+/// #+TYP_TODO: Fred Sara Lucy | DONE
+/// ```
+///
+/// Defaults to `TODO | DONE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoKeywords<'a> {
+    pub sequences: Vec<TodoSequence<'a>>,
+}
+
+/// A single line of TODO keywords.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoSequence<'a> {
+    pub kind: TodoSequenceKind,
+    pub active: Vec<TodoEntry<'a>>,
+    pub done: Vec<TodoEntry<'a>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoSequenceKind {
+    /// `#+TODO:` and `#+SEQ_TODO:`, states of a task's progress
+    Sequence,
+    /// `#+TYP_TODO:`, kinds of tasks
+    Type,
+}
+
+/// A keyword within a [`TodoSequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TodoEntry<'a> {
+    pub name: &'a str,
+    /// The key used to select the keyword, e.g. `w` in `WAIT(w)`
+    pub fast_key: Option<char>,
+}
+
+impl Default for TodoKeywords<'_> {
+    fn default() -> Self {
+        Self {
+            sequences: vec![TodoSequence {
+                kind: TodoSequenceKind::Sequence,
+                active: vec![TodoEntry {
+                    name: "TODO",
+                    fast_key: None,
+                }],
+                done: vec![TodoEntry {
+                    name: "DONE",
+                    fast_key: None,
+                }],
+            }],
+        }
+    }
+}
+
+impl<'a> TodoKeywords<'a> {
+    /// Collects the keywords from every `#+TODO:`, `#+SEQ_TODO:` and `#+TYP_TODO:` line.
+    ///
+    /// Lines within blocks, such as examples of these keywords, are skipped.
+    /// Returns `None` if there are no such lines.
+    pub fn from_buffer(input: &'a str) -> Option<Self> {
+        let lines: Vec<&str> = input.lines().collect();
+        let mut sequences = Vec::new();
+        let mut index = 0;
+        while let Some(line) = lines.get(index) {
+            index += 1;
+            // skip to the end of the block, unclosed blocks aren't blocks at all
+            if let Some(name) = block_delimiter(line, "begin_") {
+                if let Some(len) = lines[index..].iter().position(|line| {
+                    block_delimiter(line, "end_").is_some_and(|end| end.eq_ignore_ascii_case(name))
+                }) {
+                    index += len + 1;
+                    continue;
+                }
+            }
+
+            let Some((key, val)) = line
+                .trim_start()
+                .strip_prefix("#+")
+                .and_then(|rest| rest.split_once(':'))
+            else {
+                continue;
+            };
+            let kind = if key.eq_ignore_ascii_case("todo") || key.eq_ignore_ascii_case("seq_todo") {
+                TodoSequenceKind::Sequence
+            } else if key.eq_ignore_ascii_case("typ_todo") {
+                TodoSequenceKind::Type
+            } else {
+                continue;
+            };
+            sequences.extend(TodoSequence::parse(kind, val));
+        }
+
+        if sequences.is_empty() {
+            None
+        } else {
+            Some(Self { sequences })
+        }
+    }
+
+    /// The state of a keyword, or `None` if it isn't a TODO keyword.
+    pub fn state(&self, name: &str) -> Option<TodoState> {
+        self.sequences.iter().find_map(|seq| {
+            if seq.active.iter().any(|entry| entry.name == name) {
+                Some(TodoState::Active)
+            } else if seq.done.iter().any(|entry| entry.name == name) {
+                Some(TodoState::Done)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// The name of the block started or ended by `line`, depending on `prefix`,
+/// e.g. `src` for `#+begin_src python`.
+fn block_delimiter<'l>(line: &'l str, prefix: &str) -> Option<&'l str> {
+    let word = line
+        .trim_start()
+        .strip_prefix("#+")?
+        .split_whitespace()
+        .next()?;
+    word.get(..prefix.len())
+        .filter(|start| start.eq_ignore_ascii_case(prefix))
+        .map(|_| &word[prefix.len()..])
+        .filter(|name| !name.is_empty())
+}
+
+impl<'a> TodoSequence<'a> {
+    /// Parses the value of a TODO line, e.g. `TODO WAIT(w) | DONE`.
+    ///
+    /// Without a `|`, the last keyword is the only done state.
+    pub fn parse(kind: TodoSequenceKind, val: &'a str) -> Option<Self> {
+        let mut active = Vec::new();
+        let mut done = Vec::new();
+        let mut separated = false;
+
+        for word in val.split_whitespace() {
+            if word == "|" {
+                separated = true;
+            } else if separated {
+                done.push(TodoEntry::parse(word));
+            } else {
+                active.push(TodoEntry::parse(word));
+            }
+        }
+
+        if !separated {
+            done.extend(active.pop());
+        }
+
+        if active.is_empty() && done.is_empty() {
+            None
+        } else {
+            Some(Self { kind, active, done })
+        }
+    }
+}
+
+impl<'a> TodoEntry<'a> {
+    /// Parses `NAME` or `NAME(key)`, ignoring any logging settings such as `@` or `!`.
+    fn parse(word: &'a str) -> Self {
+        match word.split_once('(') {
+            Some((name, rest)) if rest.ends_with(')') => Self {
+                name,
+                fast_key: rest.chars().next().filter(|c| c.is_alphanumeric()),
+            },
+            _ => Self {
+                name: word,
+                fast_key: None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Expr;
+    use crate::{expr_in_pool, parse_org, parse_org_with};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn todo_line() {
+        let input = r"
+#+TODO: TODO NEXT(n) WAIT(w@/!) | DONE(d!) CANCELLED(c@)
+#+typ_todo: Fred Lucy
+";
+        let keywords = TodoKeywords::from_buffer(input).unwrap();
+
+        assert_eq!(
+            keywords.sequences[0],
+            TodoSequence {
+                kind: TodoSequenceKind::Sequence,
+                active: vec![
+                    TodoEntry {
+                        name: "TODO",
+                        fast_key: None
+                    },
+                    TodoEntry {
+                        name: "NEXT",
+                        fast_key: Some('n')
+                    },
+                    TodoEntry {
+                        name: "WAIT",
+                        fast_key: Some('w')
+                    },
+                ],
+                done: vec![
+                    TodoEntry {
+                        name: "DONE",
+                        fast_key: Some('d')
+                    },
+                    TodoEntry {
+                        name: "CANCELLED",
+                        fast_key: Some('c')
+                    },
+                ],
+            }
+        );
+        assert_eq!(keywords.sequences[1].kind, TodoSequenceKind::Type);
+        assert_eq!(keywords.state("Fred"), Some(TodoState::Active));
+        assert_eq!(keywords.state("Lucy"), Some(TodoState::Done));
+        assert_eq!(keywords.state("TODO"), Some(TodoState::Active));
+        assert_eq!(keywords.state("Sara"), None);
+    }
+
+    #[test]
+    fn todo_line_in_block() {
+        let input = r"
+#+begin_example
+#+TODO: EXAMPLE | SHOWN
+#+end_example
+#+BEGIN_SRC org
+#+todo: SRC | DONE
+#+END_SRC
+";
+        assert_eq!(TodoKeywords::from_buffer(input), None);
+
+        // without an end, there is no block
+        let keywords = TodoKeywords::from_buffer("#+begin_quote\n#+TODO: A | B\n").unwrap();
+        assert_eq!(keywords.state("A"), Some(TodoState::Active));
+
+        let input = format!("{input}#+TODO: NEXT | DONE\n");
+        let keywords = TodoKeywords::from_buffer(&input).unwrap();
+        assert_eq!(keywords.sequences.len(), 1);
+        assert_eq!(keywords.state("NEXT"), Some(TodoState::Active));
+    }
+
+    #[test]
+    fn custom_heading_keywords() {
+        let input = r"
+#+TODO: NEXT WAITING | CANCELLED
+* WAITING on it
+* TODO not a keyword anymore
+";
+        let parsed = parse_org(input);
+        let states: Vec<_> = parsed
+            .pool
+            .iter()
+            .filter_map(|node| {
+                if let Expr::Heading(heading) = &node.obj {
+                    Some(heading.keyword)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        assert_eq!(states[0].unwrap().name, "WAITING");
+        assert_eq!(states[0].unwrap().state, TodoState::Active);
+        assert_eq!(states[1], None);
+    }
+
+    #[test]
+    fn injected_keywords() {
+        let settings = ParseSettings {
+            todo_keywords: TodoKeywords {
+                sequences: vec![
+                    TodoSequence::parse(TodoSequenceKind::Sequence, "DOING | DONE").unwrap(),
+                ],
+            },
+            ..Default::default()
+        };
+        let parsed = parse_org_with("* DOING it\n", settings.clone());
+        let heading = expr_in_pool!(parsed, Heading).unwrap();
+        assert_eq!(heading.keyword.unwrap().state, TodoState::Active);
+
+        // in-buffer settings win
+        let parsed = parse_org_with("#+todo: DOING\n* DOING it\n", settings);
+        let heading = expr_in_pool!(parsed, Heading).unwrap();
+        assert_eq!(heading.keyword.unwrap().state, TodoState::Done);
+    }
+}
@@ -0,0 +1,141 @@
+//! Resolving the tags of a node, including inherited ones.
+
+use crate::element::Tag;
+use crate::node_pool::NodeID;
+use crate::types::{Expr, Parser};
+
+impl<'a> Parser<'a> {
+    /// The tags of a node: those of `#+FILETAGS:`, then those of each ancestor headline
+    /// from the outermost in, then its own.
+    ///
+    /// A node that isn't a headline or inline task has the tags of the headline it's in.
+    /// Each tag appears once.
+    pub fn tags(&self, node_id: NodeID) -> Vec<&'a str> {
+        // tags of each headline, innermost first
+        let mut levels: Vec<&[Tag<'a>]> = Vec::new();
+        let mut curr = Some(node_id);
+        while let Some(curr_id) = curr {
+            let node = &self.pool[curr_id];
+            match &node.obj {
+                Expr::Heading(heading) => levels.extend(heading.tags.as_deref()),
+                Expr::InlineTask(task) => levels.extend(task.tags.as_deref()),
+                _ => {}
+            }
+            curr = node.parent;
+        }
+
+        // tags are stored in reverse
+        let headline_tags = levels
+            .into_iter()
+            .rev()
+            .flat_map(|tags| tags.iter().rev())
+            .filter_map(|tag| match tag {
+                Tag::Raw(tag) => Some(*tag),
+                Tag::Loc(_) => None,
+            });
+
+        let mut ret: Vec<&str> = Vec::new();
+        for tag in self.keywords.filetags().into_iter().chain(headline_tags) {
+            if !ret.contains(&tag) {
+                ret.push(tag);
+            }
+        }
+        ret
+    }
+
+    /// Whether a node has `tag`, inherited or not.
+    ///
+    /// A group tag from `#+TAGS:` matches any of its members, including
+    /// the members of groups nested within it.
+    pub fn has_tag(&self, node_id: NodeID, tag: &str) -> bool {
+        let groups = self.keywords.tag_groups();
+        let mut wanted = vec![tag];
+        let mut i = 0;
+        while let Some(&curr) = wanted.get(i) {
+            for group in groups.iter().filter(|group| group.name == Some(curr)) {
+                for member in &group.members {
+                    if !wanted.contains(member) {
+                        wanted.push(member);
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        self.tags(node_id).iter().any(|tag| wanted.contains(tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keywords::TagGroup;
+    use crate::parse_org;
+    use crate::test_utils::heading_id;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn inherited_tags() {
+        let parser = parse_org(
+            r"#+FILETAGS: :project:
+* parent :work:urgent:
+** middle
+*** child :home:work:
+text
+* other
+",
+        );
+        let child = heading_id(&parser, "child");
+        let text = parser.pool[child].obj.children().unwrap()[0];
+
+        assert_eq!(
+            parser.tags(child),
+            vec!["project", "work", "urgent", "home"]
+        );
+        assert_eq!(parser.tags(text), parser.tags(child));
+        assert_eq!(
+            parser.tags(heading_id(&parser, "middle")),
+            vec!["project", "work", "urgent"]
+        );
+        assert_eq!(parser.tags(heading_id(&parser, "other")), vec!["project"]);
+    }
+
+    #[test]
+    fn tag_groups() {
+        let parser = parse_org(
+            r"#+TAGS: { Context : @work(w) @home(h) } laptop
+#+TAGS: [ GTD : Control Persp ] [ Control : Context Task ]
+* one :@home:
+* two :laptop:
+",
+        );
+
+        assert_eq!(
+            parser.keywords.tag_groups(),
+            vec![
+                TagGroup {
+                    name: Some("Context"),
+                    members: vec!["@work", "@home"],
+                    exclusive: true,
+                },
+                TagGroup {
+                    name: Some("GTD"),
+                    members: vec!["Control", "Persp"],
+                    exclusive: false,
+                },
+                TagGroup {
+                    name: Some("Control"),
+                    members: vec!["Context", "Task"],
+                    exclusive: false,
+                },
+            ]
+        );
+
+        let one = heading_id(&parser, "one");
+        let two = heading_id(&parser, "two");
+        assert!(parser.has_tag(one, "@home"));
+        assert!(parser.has_tag(one, "Context"));
+        assert!(parser.has_tag(one, "GTD"));
+        assert!(!parser.has_tag(one, "laptop"));
+        assert!(!parser.has_tag(two, "GTD"));
+    }
+}
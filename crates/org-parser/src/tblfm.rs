@@ -0,0 +1,879 @@
+//! Table formulas, written in `#+TBLFM:` lines below a table, and their evaluation.
+//!
+//! Only the arithmetic subset of Calc formulas is supported: `+ - * / ^`, parentheses,
+//! field and range references and the `vsum`, `vmean`, `vmin` and `vmax` functions.
+
+use std::fmt;
+
+/// A single assignment of a `#+TBLFM:` line, such as `$3=$1*$2`.
+///
+/// Multiple formulas in one line are separated by `::`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableFormula<'a> {
+    /// The formula as written
+    pub raw: &'a str,
+    pub target: FormulaTarget,
+    pub expr: FormulaExpr,
+    /// The printf-style format following a `;`, e.g. `%.2f`
+    pub format: Option<&'a str>,
+}
+
+/// The fields a [`TableFormula`] is assigned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormulaTarget {
+    /// `$3`, every data row of a column, excluding the header
+    Column(ColRef),
+    /// `@2$3`
+    Field(RowRef, ColRef),
+    /// `@2$1..@4$3`
+    Range(FieldRef, FieldRef),
+}
+
+/// The row of a reference, counted among data rows: horizontal rules are skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowRef {
+    /// `@2`, `@<` is the first row
+    Absolute(usize),
+    /// `@>`
+    Last,
+    /// `@-1`, relative to the row being computed. `@0` is the current row.
+    Relative(isize),
+    /// `@I`, `@II`, the row after the nth horizontal rule, offset by `@II+1`.
+    Hline(usize, isize),
+}
+
+/// The column of a reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColRef {
+    /// `$2`, `$<` is the first column
+    Absolute(usize),
+    /// `$>`
+    Last,
+    /// `$-1`, relative to the column being computed. `$0` is the current column.
+    Relative(isize),
+}
+
+/// A reference to a field, where either part defaults to the field being computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRef {
+    pub row: Option<RowRef>,
+    pub col: Option<ColRef>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VectorFunc {
+    Sum,
+    Mean,
+    Min,
+    Max,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormulaExpr {
+    Number(f64),
+    Field(FieldRef),
+    /// `@2$1..@-1$1`
+    Range(FieldRef, FieldRef),
+    Neg(Box<FormulaExpr>),
+    Binary(BinaryOp, Box<FormulaExpr>, Box<FormulaExpr>),
+    Call(VectorFunc, Vec<FormulaExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormulaError {
+    /// A formula that couldn't be parsed
+    Syntax(String),
+    /// A reference to a row or column outside of the table
+    Reference(String),
+    DivisionByZero,
+}
+
+impl fmt::Display for FormulaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormulaError::Syntax(formula) => write!(f, "invalid table formula `{formula}`"),
+            FormulaError::Reference(formula) => {
+                write!(f, "table formula `{formula}` refers outside of the table")
+            }
+            FormulaError::DivisionByZero => f.write_str("division by zero in table formula"),
+        }
+    }
+}
+
+impl std::error::Error for FormulaError {}
+
+impl<'a> TableFormula<'a> {
+    /// Parses every formula of a `#+TBLFM:` line, failing on the first invalid one.
+    pub fn parse_line(line: &'a str) -> Result<Vec<Self>, FormulaError> {
+        line.split("::")
+            .map(str::trim)
+            .filter(|formula| !formula.is_empty())
+            .map(|formula| {
+                Self::parse(formula).ok_or_else(|| FormulaError::Syntax(formula.to_owned()))
+            })
+            .collect()
+    }
+
+    fn parse(formula: &'a str) -> Option<Self> {
+        let (assignment, format) = match formula.split_once(';') {
+            Some((assignment, format)) => (assignment, Some(format.trim())),
+            None => (formula, None),
+        };
+        let (target, expr) = assignment.split_once('=')?;
+
+        let mut target_parser = ExprParser::new(target.trim());
+        let start = target_parser.field_ref()?;
+        let target = if target_parser.eat("..") {
+            FormulaTarget::Range(start, target_parser.field_ref()?)
+        } else {
+            match start {
+                FieldRef {
+                    row: None,
+                    col: Some(col),
+                } => FormulaTarget::Column(col),
+                FieldRef {
+                    row: Some(row),
+                    col: Some(col),
+                } => FormulaTarget::Field(row, col),
+                _ => return None,
+            }
+        };
+        // `$0` and `@0` stand for the field being computed, which a target doesn't have
+        if !target_parser.at_end() || target.refers_to_current() {
+            return None;
+        }
+
+        let mut expr_parser = ExprParser::new(expr);
+        let expr = expr_parser.expr()?;
+        if !expr_parser.at_end() {
+            return None;
+        }
+
+        Some(Self {
+            raw: formula,
+            target,
+            expr,
+            format: format.filter(|format| !format.is_empty()),
+        })
+    }
+}
+
+impl FormulaTarget {
+    fn refers_to_current(&self) -> bool {
+        let field_is_current = |field: &FieldRef| {
+            field.row == Some(RowRef::Relative(0)) || field.col == Some(ColRef::Relative(0))
+        };
+        match *self {
+            FormulaTarget::Column(col) => col == ColRef::Relative(0),
+            FormulaTarget::Field(row, col) => {
+                row == RowRef::Relative(0) || col == ColRef::Relative(0)
+            }
+            FormulaTarget::Range(start, end) => field_is_current(&start) || field_is_current(&end),
+        }
+    }
+}
+
+struct ExprParser<'s> {
+    input: &'s [u8],
+    index: usize,
+}
+
+impl<'s> ExprParser<'s> {
+    fn new(input: &'s str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            index: 0,
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while self
+            .input
+            .get(self.index)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.index += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        self.input.get(self.index).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.input[self.index..].starts_with(token.as_bytes()) {
+            self.index += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    fn expr(&mut self) -> Option<FormulaExpr> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(b'+') => BinaryOp::Add,
+                Some(b'-') => BinaryOp::Sub,
+                _ => return Some(lhs),
+            };
+            self.index += 1;
+            lhs = FormulaExpr::Binary(op, Box::new(lhs), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Option<FormulaExpr> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(b'*') => BinaryOp::Mul,
+                Some(b'/') => BinaryOp::Div,
+                _ => return Some(lhs),
+            };
+            self.index += 1;
+            lhs = FormulaExpr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Option<FormulaExpr> {
+        if self.eat("-") {
+            return Some(FormulaExpr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        // right associative
+        if self.eat("^") {
+            return Some(FormulaExpr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(self.unary()?),
+            ));
+        }
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<FormulaExpr> {
+        match self.peek()? {
+            b'(' => {
+                self.index += 1;
+                let inner = self.expr()?;
+                self.eat(")").then_some(inner)
+            }
+            b'@' | b'$' => {
+                let start = self.field_ref()?;
+                if self.eat("..") {
+                    Some(FormulaExpr::Range(start, self.field_ref()?))
+                } else {
+                    Some(FormulaExpr::Field(start))
+                }
+            }
+            b'0'..=b'9' | b'.' => {
+                let start = self.index;
+                while self
+                    .input
+                    .get(self.index)
+                    .is_some_and(|byte| byte.is_ascii_digit() || *byte == b'.')
+                {
+                    self.index += 1;
+                }
+                let num = std::str::from_utf8(&self.input[start..self.index]).ok()?;
+                // numbers too large for a float would otherwise become infinity
+                let num = num.parse::<f64>().ok().filter(|num| num.is_finite())?;
+                Some(FormulaExpr::Number(num))
+            }
+            byte if byte.is_ascii_alphabetic() => {
+                let start = self.index;
+                while self
+                    .input
+                    .get(self.index)
+                    .is_some_and(u8::is_ascii_alphanumeric)
+                {
+                    self.index += 1;
+                }
+                let func = match &self.input[start..self.index] {
+                    b"vsum" => VectorFunc::Sum,
+                    b"vmean" => VectorFunc::Mean,
+                    b"vmin" => VectorFunc::Min,
+                    b"vmax" => VectorFunc::Max,
+                    _ => return None,
+                };
+                if !self.eat("(") {
+                    return None;
+                }
+                let mut args = vec![self.expr()?];
+                while self.eat(",") {
+                    args.push(self.expr()?);
+                }
+                self.eat(")").then_some(FormulaExpr::Call(func, args))
+            }
+            _ => None,
+        }
+    }
+
+    /// `@ROW$COL`, `@ROW` or `$COL`
+    fn field_ref(&mut self) -> Option<FieldRef> {
+        self.skip_ws();
+        let row = if self.eat("@") {
+            Some(self.row_ref()?)
+        } else {
+            None
+        };
+        let col = if self.input.get(self.index) == Some(&b'$') {
+            self.index += 1;
+            Some(self.col_ref()?)
+        } else {
+            None
+        };
+        (row.is_some() || col.is_some()).then_some(FieldRef { row, col })
+    }
+
+    fn row_ref(&mut self) -> Option<RowRef> {
+        match self.input.get(self.index)? {
+            b'<' => {
+                self.index += 1;
+                Some(RowRef::Absolute(1))
+            }
+            b'>' => {
+                self.index += 1;
+                Some(RowRef::Last)
+            }
+            b'I' => {
+                let start = self.index;
+                while self.input.get(self.index) == Some(&b'I') {
+                    self.index += 1;
+                }
+                let hline = self.index - start;
+                let offset = match self.input.get(self.index) {
+                    Some(b'+' | b'-') => self.signed()?,
+                    _ => 0,
+                };
+                Some(RowRef::Hline(hline, offset))
+            }
+            b'+' | b'-' => Some(RowRef::Relative(self.signed()?)),
+            _ => match self.unsigned()? {
+                0 => Some(RowRef::Relative(0)),
+                row => Some(RowRef::Absolute(row)),
+            },
+        }
+    }
+
+    fn col_ref(&mut self) -> Option<ColRef> {
+        match self.input.get(self.index)? {
+            b'<' => {
+                self.index += 1;
+                Some(ColRef::Absolute(1))
+            }
+            b'>' => {
+                self.index += 1;
+                Some(ColRef::Last)
+            }
+            b'+' | b'-' => Some(ColRef::Relative(self.signed()?)),
+            _ => match self.unsigned()? {
+                0 => Some(ColRef::Relative(0)),
+                col => Some(ColRef::Absolute(col)),
+            },
+        }
+    }
+
+    fn unsigned(&mut self) -> Option<usize> {
+        let start = self.index;
+        while self.input.get(self.index).is_some_and(u8::is_ascii_digit) {
+            self.index += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.index])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    fn signed(&mut self) -> Option<isize> {
+        let negative = self.input.get(self.index)? == &b'-';
+        self.index += 1;
+        let num = isize::try_from(self.unsigned()?).ok()?;
+        Some(if negative { -num } else { num })
+    }
+}
+
+/// Recalculates a table in place, applying `formulas` in order.
+///
+/// `rows` holds the cells of every row of the table, with `None` standing in for horizontal
+/// rules. Column formulas skip the header, the rows above the first rule, and don't overwrite
+/// fields that have a formula of their own. Fields that are empty or not numbers count as 0,
+/// but empty fields are left out of ranges.
+///
+/// A formula that can't be evaluated, such as one referring to a row outside the table,
+/// leaves the table as it was and the rest are still applied. The first such error is returned.
+pub fn recalculate(
+    formulas: &[TableFormula],
+    rows: &mut [Option<Vec<String>>],
+) -> Result<(), FormulaError> {
+    let mut table = Grid::new(rows);
+    let cols = table.cols();
+
+    // field formulas take precedence over column formulas
+    let mut fields = Vec::new();
+    for formula in formulas {
+        if let FormulaTarget::Field(row, col) = formula.target {
+            let field = FieldRef {
+                row: Some(row),
+                col: Some(col),
+            };
+            if let Ok(field) = table.resolve(field, (0, 0), formula) {
+                fields.push(field);
+            }
+        }
+    }
+
+    let mut first_error = None;
+    for formula in formulas {
+        let before = table
+            .data
+            .iter()
+            .map(|row| (*row).clone())
+            .collect::<Vec<_>>();
+        if let Err(e) = table.apply(formula, &fields, cols) {
+            for (row, old) in table.data.iter_mut().zip(before) {
+                **row = old;
+            }
+            first_error.get_or_insert(e);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// The row and column of a field, indexed from 0.
+type Field = (usize, usize);
+
+/// The data rows of a table, along with where its horizontal rules fall.
+struct Grid<'r> {
+    data: Vec<&'r mut Vec<String>>,
+    /// The number of data rows above each horizontal rule
+    hlines: Vec<usize>,
+}
+
+impl<'r> Grid<'r> {
+    fn new(rows: &'r mut [Option<Vec<String>>]) -> Self {
+        let mut data = Vec::new();
+        let mut hlines = Vec::new();
+        for row in rows {
+            match row {
+                Some(cells) => data.push(cells),
+                None => hlines.push(data.len()),
+            }
+        }
+        Self { data, hlines }
+    }
+
+    fn cols(&self) -> usize {
+        self.data.iter().map(|row| row.len()).max().unwrap_or(0)
+    }
+
+    /// The number of rows above the first rule, provided data follows it.
+    fn header_rows(&self) -> usize {
+        match self.hlines.first() {
+            Some(&rows) if rows < self.data.len() => rows,
+            _ => 0,
+        }
+    }
+
+    fn get(&self, (row, col): Field) -> &str {
+        self.data[row].get(col).map_or("", |cell| cell.trim())
+    }
+
+    fn set(&mut self, (row, col): Field, value: String) {
+        let row = &mut self.data[row];
+        if row.len() <= col {
+            row.resize(col + 1, String::new());
+        }
+        row[col] = value;
+    }
+
+    /// Applies a formula, leaving out the `fields` that have a formula of their own
+    /// from column formulas. `cols` is the width of the table before any formula.
+    fn apply(
+        &mut self,
+        formula: &TableFormula,
+        fields: &[Field],
+        cols: usize,
+    ) -> Result<(), FormulaError> {
+        let targets = match formula.target {
+            FormulaTarget::Column(col) => {
+                let col = self.resolve_col(Some(col), 0, cols, formula)?;
+                (self.header_rows()..self.data.len())
+                    .map(|row| (row, col))
+                    .filter(|field| !fields.contains(field))
+                    .collect()
+            }
+            FormulaTarget::Field(row, col) => vec![self.resolve(
+                FieldRef {
+                    row: Some(row),
+                    col: Some(col),
+                },
+                (0, 0),
+                formula,
+            )?],
+            FormulaTarget::Range(start, end) => {
+                let (start, end) = self.resolve_range(start, end, (0, 0), formula)?;
+                (start.0..=end.0)
+                    .flat_map(|row| (start.1..=end.1).map(move |col| (row, col)))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        for field in targets {
+            let value = self.eval(&formula.expr, field, formula)?;
+            let formatted = format_value(value, formula.format);
+            self.set(field, formatted);
+        }
+        Ok(())
+    }
+
+    fn resolve_row(
+        &self,
+        row: Option<RowRef>,
+        curr: usize,
+        end: bool,
+        formula: &TableFormula,
+    ) -> Result<usize, FormulaError> {
+        let resolved = match row {
+            None => Some(curr),
+            Some(RowRef::Absolute(row)) => row.checked_sub(1),
+            Some(RowRef::Last) => self.data.len().checked_sub(1),
+            Some(RowRef::Relative(diff)) => curr.checked_add_signed(diff),
+            Some(RowRef::Hline(hline, offset)) => {
+                let after = *self
+                    .hlines
+                    .get(hline - 1)
+                    .ok_or_else(|| out_of_table(formula))?;
+                // as the end of a range, a rule stands for the row above it
+                if end && offset == 0 {
+                    after.checked_sub(1)
+                } else {
+                    after.checked_add_signed(offset)
+                }
+            }
+        };
+        resolved
+            .filter(|row| *row < self.data.len())
+            .ok_or_else(|| out_of_table(formula))
+    }
+
+    fn resolve_col(
+        &self,
+        col: Option<ColRef>,
+        curr: usize,
+        cols: usize,
+        formula: &TableFormula,
+    ) -> Result<usize, FormulaError> {
+        let resolved = match col {
+            None => Some(curr),
+            Some(ColRef::Absolute(col)) => col.checked_sub(1),
+            Some(ColRef::Last) => cols.checked_sub(1),
+            Some(ColRef::Relative(diff)) => curr.checked_add_signed(diff),
+        };
+        resolved
+            .filter(|col| *col < cols)
+            .ok_or_else(|| out_of_table(formula))
+    }
+
+    fn resolve(
+        &self,
+        field: FieldRef,
+        curr: Field,
+        formula: &TableFormula,
+    ) -> Result<Field, FormulaError> {
+        Ok((
+            self.resolve_row(field.row, curr.0, false, formula)?,
+            self.resolve_col(field.col, curr.1, self.cols(), formula)?,
+        ))
+    }
+
+    fn resolve_range(
+        &self,
+        start: FieldRef,
+        end: FieldRef,
+        curr: Field,
+        formula: &TableFormula,
+    ) -> Result<(Field, Field), FormulaError> {
+        let cols = self.cols();
+        let rows = (
+            self.resolve_row(start.row, curr.0, false, formula)?,
+            self.resolve_row(end.row, curr.0, true, formula)?,
+        );
+        let cols = (
+            self.resolve_col(start.col, curr.1, cols, formula)?,
+            self.resolve_col(end.col, curr.1, cols, formula)?,
+        );
+        Ok((
+            (rows.0.min(rows.1), cols.0.min(cols.1)),
+            (rows.0.max(rows.1), cols.0.max(cols.1)),
+        ))
+    }
+
+    fn eval(
+        &self,
+        expr: &FormulaExpr,
+        curr: Field,
+        formula: &TableFormula,
+    ) -> Result<f64, FormulaError> {
+        Ok(match expr {
+            FormulaExpr::Number(num) => *num,
+            FormulaExpr::Field(field) => to_number(self.get(self.resolve(*field, curr, formula)?)),
+            // a range outside of a function call is summed
+            FormulaExpr::Range(..) => self.values(expr, curr, formula)?.iter().sum(),
+            FormulaExpr::Neg(inner) => -self.eval(inner, curr, formula)?,
+            FormulaExpr::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs, curr, formula)?;
+                let rhs = self.eval(rhs, curr, formula)?;
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div if rhs == 0.0 => return Err(FormulaError::DivisionByZero),
+                    BinaryOp::Div => lhs / rhs,
+                    BinaryOp::Pow => lhs.powf(rhs),
+                }
+            }
+            FormulaExpr::Call(func, args) => {
+                let mut values = Vec::new();
+                for arg in args {
+                    values.extend(self.values(arg, curr, formula)?);
+                }
+                match func {
+                    VectorFunc::Sum => values.iter().sum(),
+                    VectorFunc::Mean if values.is_empty() => 0.0,
+                    VectorFunc::Mean => values.iter().sum::<f64>() / values.len() as f64,
+                    VectorFunc::Min => values.into_iter().reduce(f64::min).unwrap_or(0.0),
+                    VectorFunc::Max => values.into_iter().reduce(f64::max).unwrap_or(0.0),
+                }
+            }
+        })
+    }
+
+    /// The values of a function argument, expanding ranges without their empty fields.
+    fn values(
+        &self,
+        expr: &FormulaExpr,
+        curr: Field,
+        formula: &TableFormula,
+    ) -> Result<Vec<f64>, FormulaError> {
+        let FormulaExpr::Range(start, end) = expr else {
+            return Ok(vec![self.eval(expr, curr, formula)?]);
+        };
+        let (start, end) = self.resolve_range(*start, *end, curr, formula)?;
+        let mut ret = Vec::new();
+        for row in start.0..=end.0 {
+            for col in start.1..=end.1 {
+                let cell = self.get((row, col));
+                if !cell.is_empty() {
+                    ret.push(to_number(cell));
+                }
+            }
+        }
+        Ok(ret)
+    }
+}
+
+fn out_of_table(formula: &TableFormula) -> FormulaError {
+    FormulaError::Reference(formula.raw.to_owned())
+}
+
+fn to_number(cell: &str) -> f64 {
+    cell.parse().unwrap_or(0.0)
+}
+
+/// The most digits written after the decimal point by a `%.Nf` format.
+const MAX_PRECISION: usize = 20;
+
+/// Formats a result with a printf-style format such as `%.2f` or `%d`,
+/// otherwise printing integers without a fractional part.
+fn format_value(value: f64, format: Option<&str>) -> String {
+    if let Some(spec) = format.and_then(|format| format.strip_prefix('%')) {
+        if spec == "d" {
+            return format!("{}", value.round());
+        }
+        if let Some(precision) = spec
+            .strip_prefix('.')
+            .and_then(|spec| spec.strip_suffix('f'))
+            .and_then(|precision| precision.parse::<usize>().ok())
+        {
+            let precision = precision.min(MAX_PRECISION);
+            return format!("{value:.precision$}");
+        }
+    }
+
+    // adding zero turns -0 into 0
+    let value = value + 0.0;
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{value}")
+    } else {
+        let ret = format!("{value:.10}");
+        ret.trim_end_matches('0').trim_end_matches('.').to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn table(rows: &[&str]) -> Vec<Option<Vec<String>>> {
+        rows.iter()
+            .map(|row| {
+                (!row.starts_with('-'))
+                    .then(|| row.split('|').map(|cell| cell.trim().to_owned()).collect())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_formulas() {
+        let formulas = TableFormula::parse_line("$3=$1*$2::@>$4=vsum(@2..@-1);%.2f").unwrap();
+
+        assert_eq!(
+            formulas[0],
+            TableFormula {
+                raw: "$3=$1*$2",
+                target: FormulaTarget::Column(ColRef::Absolute(3)),
+                expr: FormulaExpr::Binary(
+                    BinaryOp::Mul,
+                    Box::new(FormulaExpr::Field(FieldRef {
+                        row: None,
+                        col: Some(ColRef::Absolute(1))
+                    })),
+                    Box::new(FormulaExpr::Field(FieldRef {
+                        row: None,
+                        col: Some(ColRef::Absolute(2))
+                    })),
+                ),
+                format: None,
+            }
+        );
+        assert_eq!(
+            formulas[1],
+            TableFormula {
+                raw: "@>$4=vsum(@2..@-1);%.2f",
+                target: FormulaTarget::Field(RowRef::Last, ColRef::Absolute(4)),
+                expr: FormulaExpr::Call(
+                    VectorFunc::Sum,
+                    vec![FormulaExpr::Range(
+                        FieldRef {
+                            row: Some(RowRef::Absolute(2)),
+                            col: None
+                        },
+                        FieldRef {
+                            row: Some(RowRef::Relative(-1)),
+                            col: None
+                        },
+                    )]
+                ),
+                format: Some("%.2f"),
+            }
+        );
+
+        assert_eq!(
+            TableFormula::parse_line("$1=foo($2)"),
+            Err(FormulaError::Syntax("$1=foo($2)".into()))
+        );
+        for formula in [
+            "$0=1",
+            "@0$1=1",
+            "@1$1..@2$0=1",
+            "$1=vsum()",
+            "$1=@>>",
+            "$1=1e400",
+        ] {
+            assert!(TableFormula::parse_line(formula).is_err(), "{formula}");
+        }
+        let huge = format!("$1=1{}", "0".repeat(400));
+        assert!(TableFormula::parse_line(&huge).is_err());
+    }
+
+    #[test]
+    fn column_and_field_formulas() {
+        let mut rows = table(&[
+            "item|qty|price|total",
+            "-",
+            "apples|3|0.5|",
+            "pears|2|1.25|",
+            "-",
+            "total|||",
+        ]);
+        let formulas =
+            TableFormula::parse_line("$4=$2*$3::@>$4=vsum(@I..@II);%.2f::@>$2=vsum(@I..@II)")
+                .unwrap();
+        recalculate(&formulas, &mut rows).unwrap();
+
+        assert_eq!(
+            rows,
+            table(&[
+                "item|qty|price|total",
+                "-",
+                "apples|3|0.5|1.5",
+                "pears|2|1.25|2.5",
+                "-",
+                "total|5||4.00",
+            ])
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut rows = table(&["1|2|", "3|4|", "|6|"]);
+        let formulas =
+            TableFormula::parse_line("$3=-$1 + 2^$2 / (1 + 1)::@1$1..@2$1=vmax($2, 3) - vmin(@<$2..@>$2)::@3$3=vmean(@1..@2)")
+                .unwrap();
+        recalculate(&formulas, &mut rows).unwrap();
+
+        assert_eq!(rows, table(&["1|2|1", "2|4|5", "|6|3"]));
+    }
+
+    #[test]
+    fn formats() {
+        let mut rows = table(&["2|", "1|"]);
+        let formulas = TableFormula::parse_line("$2=$1/3;%.3f::@2$2=$1;%.99999f").unwrap();
+        recalculate(&formulas, &mut rows).unwrap();
+
+        assert_eq!(rows, table(&["2|0.667", "1|1.00000000000000000000"]));
+    }
+
+    #[test]
+    fn formula_errors() {
+        let mut rows = table(&["1|0"]);
+        let formulas = TableFormula::parse_line("$3=$1/$2").unwrap();
+        assert_eq!(
+            recalculate(&formulas, &mut rows),
+            Err(FormulaError::Reference("$3=$1/$2".into()))
+        );
+
+        let formulas = TableFormula::parse_line("$2=$1/$2").unwrap();
+        assert_eq!(
+            recalculate(&formulas, &mut rows),
+            Err(FormulaError::DivisionByZero)
+        );
+        assert_eq!(rows, table(&["1|0"]));
+
+        // only the formula that fails is skipped
+        let mut rows = table(&["1|", "2|", "3|"]);
+        let formulas = TableFormula::parse_line("@4$2=1::$2=$1*2::@3$1=$1/0").unwrap();
+        assert_eq!(
+            recalculate(&formulas, &mut rows),
+            Err(FormulaError::Reference("@4$2=1".into()))
+        );
+        assert_eq!(rows, table(&["1|2", "2|4", "3|6"]));
+    }
+}
@@ -0,0 +1,13 @@
+//! Helpers shared by the tests of several modules.
+
+use crate::node_pool::{make_node_id, NodeID};
+use crate::types::{Expr, Parser};
+
+/// The id of the first heading titled `title`.
+pub(crate) fn heading_id(parser: &Parser, title: &str) -> NodeID {
+    let index = parser.pool.iter().position(|node| {
+        matches!(&node.obj, Expr::Heading(heading)
+            if heading.title.as_ref().is_some_and(|(raw, _)| raw.trim() == title))
+    });
+    make_node_id(index.unwrap() as u32)
+}
@@ -7,8 +7,10 @@ use crate::constants::{
     COLON, EQUAL, NEWLINE, PLUS, RBRACE, RBRACK, SLASH, SPACE, STAR, TILDE, UNDERSCORE, VBAR,
 };
 use crate::element::*;
+use crate::keywords::Keywords;
 use crate::node_pool::{NodeID, NodePool};
 use crate::object::*;
+use crate::settings::{TodoKeywords, TodoState};
 use crate::utils::{bytes_to_str, id_escape, Match};
 use bitflags::bitflags;
 
@@ -43,10 +45,11 @@ pub type NodeCache = HashMap<usize, NodeID>;
 /// For
 ///
 
+/// The attributes are returned in source order.
 pub(crate) fn process_attrs<'a>(
     mut cursor: Cursor<'a>,
-) -> Result<(Cursor, HashMap<&'a str, &'a str>)> {
-    let mut new_attrs: HashMap<&'a str, &'a str> = HashMap::new();
+) -> Result<(Cursor, Vec<(&'a str, &'a str)>)> {
+    let mut new_attrs: Vec<(&'a str, &'a str)> = Vec::new();
     loop {
         match cursor.try_curr()? {
             NEWLINE => break,
@@ -56,7 +59,7 @@ pub(crate) fn process_attrs<'a>(
                 cursor.index = key_match.end;
                 cursor.skip_ws();
                 if NEWLINE == cursor.try_curr()? {
-                    new_attrs.insert(key_match.obj.trim(), "");
+                    new_attrs.push((key_match.obj.trim(), ""));
                     break;
                 }
 
@@ -77,7 +80,7 @@ pub(crate) fn process_attrs<'a>(
                 }
                 let val_obj = cursor.clamp_backwards(val_begin);
 
-                new_attrs.insert(key_match.obj.trim(), val_obj.trim());
+                new_attrs.push((key_match.obj.trim(), val_obj.trim()));
             }
             _ => cursor.next(),
         }
@@ -109,15 +112,23 @@ pub struct Parser<'a> {
     /// A map of macro names to their corresponding [`MacroDef`]s
     pub macros: HashMap<&'a str, MacroDef<'a>>,
 
-    /// A map storing basic the key/val content of [`Keyword`]s.
+    /// The key/val content of every [`Keyword`], looked up case-insensitively.
     ///
     /// Allows for quick access to possible values while exporting/manipulating the AST.
-    // HACK: this feels quite janky, kinda double storing Keywords?
-    pub keywords: HashMap<&'a str, &'a str>,
+    pub keywords: Keywords<'a>,
 
     /// A map of footnote labels to [`FootnoteDef`]s.
     pub footnotes: HashMap<&'a str, NodeID>,
 
+    /// A map of `#+NAME:` values to the elements they name.
+    pub names: HashMap<&'a str, NodeID>,
+
+    /// The TODO keywords recognized in headlines.
+    pub todo_keywords: TodoKeywords<'a>,
+
+    /// The minimum number of stars of an inline task, see [`ParseSettings`](crate::ParseSettings).
+    pub inline_task_min_level: usize,
+
     /// The original source text
     ///
     /// Useful for referencing the captured area of a [`Node`], since it stores spans.
@@ -178,7 +189,13 @@ impl<'a> Parser<'a> {
     /// and in target_occurences.
     /// and we'd like not to triple allocate
     pub(crate) fn generate_target(&mut self, raw_entry: &'a str) -> Rc<str> {
-        let mut id_string = id_escape(raw_entry);
+        self.generate_target_from(raw_entry, raw_entry)
+    }
+
+    /// Like [`Parser::generate_target`], but builds the id from `id_source`
+    /// rather than the raw contents, e.g. to leave out parts of a heading's title.
+    pub(crate) fn generate_target_from(&mut self, raw_entry: &'a str, id_source: &str) -> Rc<str> {
+        let mut id_string = id_escape(id_source);
         // doesn't compile if we're not explicit about the coercion
         let rc_ret: Rc<str>;
         if let Some(counter) = self.target_occurences.get_mut(&id_string as &str) {
@@ -193,6 +210,102 @@ impl<'a> Parser<'a> {
         self.target_occurences.insert(rc_ret.clone(), 0);
         rc_ret
     }
+
+    /// Sums the minutes of every [`Clock`] in the subtree of a node.
+    ///
+    /// Called with the id of a [`Heading`], this includes the clocks of all its child headings.
+    /// Running clocks don't contribute any time.
+    pub fn clocked_minutes(&self, node_id: NodeID) -> u32 {
+        let node = &self.pool[node_id];
+        let mut total = if let Expr::Clock(clock) = &node.obj {
+            clock.minutes().unwrap_or(0)
+        } else {
+            0
+        };
+
+        if let Some(children) = node.obj.children() {
+            for child_id in children {
+                total += self.clocked_minutes(*child_id);
+            }
+        }
+        total
+    }
+
+    /// Recomputes the value of a [`StatisticsCookie`].
+    ///
+    /// Cookies within the title of a [`Heading`] count its direct child headings that have
+    /// a TODO keyword, or when there are none, the checkboxes of the lists in its section.
+    /// Cookies within an [`Item`] count the checkboxes of its direct child items.
+    ///
+    /// Returns `None` if the node isn't a cookie or isn't within a heading title or an item.
+    /// A cookie with nothing to count is `0/0`, or `0%`.
+    pub fn statistics(&self, node_id: NodeID) -> Option<StatisticsCookie> {
+        let Expr::StatisticsCookie(cookie) = &self.pool[node_id].obj else {
+            return None;
+        };
+
+        let mut prev_id = node_id;
+        let mut curr_id = self.pool[node_id].parent?;
+        let (done, total) = loop {
+            match &self.pool[curr_id].obj {
+                Expr::Heading(heading) => {
+                    // cookies in the section of a heading don't count its children
+                    heading
+                        .title
+                        .as_ref()
+                        .filter(|(_, title)| title.contains(&prev_id))?;
+                    let children = heading.children.as_deref().unwrap_or_default();
+                    let keywords = children.iter().filter_map(|id| {
+                        if let Expr::Heading(child) = &self.pool[*id].obj {
+                            child.keyword
+                        } else {
+                            None
+                        }
+                    });
+                    let counts = keywords.fold((0, 0), |(done, total), keyword| {
+                        (
+                            done + u32::from(keyword.state == TodoState::Done),
+                            total + 1,
+                        )
+                    });
+                    // without tasks, the checkboxes in the heading's section are counted
+                    break if counts.1 == 0 {
+                        self.checkbox_counts(children)
+                    } else {
+                        counts
+                    };
+                }
+                Expr::Item(item) => break self.checkbox_counts(&item.children),
+                _ => {
+                    prev_id = curr_id;
+                    curr_id = self.pool[curr_id].parent?;
+                }
+            }
+        };
+
+        Some(cookie.with_counts(done, total))
+    }
+
+    /// The checked and total checkboxes of the items of the lists among `children`.
+    fn checkbox_counts(&self, children: &[NodeID]) -> (u32, u32) {
+        let mut counts = (0, 0);
+        for id in children {
+            let Expr::PlainList(list) = &self.pool[*id].obj else {
+                continue;
+            };
+            for id in &list.children {
+                if let Expr::Item(Item {
+                    check_box: Some(check_box),
+                    ..
+                }) = &self.pool[*id].obj
+                {
+                    counts.0 += u32::from(*check_box == CheckBox::On);
+                    counts.1 += 1;
+                }
+            }
+        }
+        counts
+    }
 }
 
 /// A view into the source text's byte representation.
@@ -434,6 +547,7 @@ pub enum Expr<'a> {
     // Branch
     Root(Vec<NodeID>),
     Heading(Heading<'a>),
+    InlineTask(InlineTask<'a>),
     Block(Block<'a>),
     RegularLink(RegularLink<'a>),
     Paragraph(Paragraph),
@@ -443,13 +557,14 @@ pub enum Expr<'a> {
     Underline(Underline),
     PlainList(PlainList),
     Item(Item<'a>),
-    Table(Table),
+    Table(Table<'a>),
     TableRow(TableRow),
     TableCell(TableCell),
     PlainLink(PlainLink<'a>),
     Superscript(Superscript<'a>),
     Subscript(Subscript<'a>),
     Drawer(Drawer<'a>),
+    DynamicBlock(DynamicBlock<'a>),
     Affiliated(Affiliated<'a>),
     FootnoteDef(FootnoteDef<'a>),
     FootnoteRef(FootnoteRef<'a>),
@@ -464,6 +579,9 @@ pub enum Expr<'a> {
     Verbatim(Verbatim<'a>),
     Code(Code<'a>),
     Comment(Comment<'a>),
+    FixedWidth(FixedWidth<'a>),
+    BabelCall(BabelCall<'a>),
+    InlineBabelCall(InlineBabelCall<'a>),
     InlineSrc(InlineSrc<'a>),
     Keyword(Keyword<'a>),
     LatexEnv(LatexEnv<'a>),
@@ -474,6 +592,14 @@ pub enum Expr<'a> {
     Macro(MacroCall<'a>),
     ExportSnippet(ExportSnippet<'a>),
     MacroDef(MacroDef<'a>),
+    Timestamp(Timestamp<'a>),
+    SexpTimestamp(SexpTimestamp<'a>),
+    DiarySexp(DiarySexp<'a>),
+    Clock(Clock<'a>),
+    RadioTarget(RadioTarget<'a>),
+    RadioLink(RadioLink<'a>),
+    Citation(Citation<'a>),
+    StatisticsCookie(StatisticsCookie),
 }
 
 // TODO: maybe make all fields bitflags for space optimization
@@ -514,6 +640,7 @@ bitflags! {
         const Table         = 1 << 7;
         const SupSub        = 1 << 8;
         const FootnoteRef   = 1 << 9;
+        const Verse         = 1 << 10;
     }
 }
 
@@ -568,10 +695,12 @@ impl<'a> Expr<'a> {
         match self {
             Expr::Root(root) => Some(root),
             Expr::Heading(heading) => heading.children.as_mut(),
+            Expr::InlineTask(task) => task.children.as_mut(),
             Expr::Block(block) => match block {
                 Block::Center { contents, .. }
                 | Block::Quote { contents, .. }
-                | Block::Special { contents, .. } => Some(contents),
+                | Block::Special { contents, .. }
+                | Block::Verse { contents, .. } => Some(contents),
                 _ => None,
             },
 
@@ -598,6 +727,7 @@ impl<'a> Expr<'a> {
                 PlainOrRec::Rec(rec) => Some(rec),
             },
             Expr::Drawer(inner) => Some(&mut inner.children),
+            Expr::DynamicBlock(inner) => Some(&mut inner.children),
             Expr::FootnoteDef(inner) => Some(&mut inner.children),
             Expr::FootnoteRef(inner) => inner.children.as_mut(),
             _ => None,
@@ -608,10 +738,12 @@ impl<'a> Expr<'a> {
         match &self {
             Expr::Root(root) => Some(root),
             Expr::Heading(heading) => heading.children.as_ref(),
+            Expr::InlineTask(task) => task.children.as_ref(),
             Expr::Block(block) => match block {
                 Block::Center { contents, .. }
                 | Block::Quote { contents, .. }
-                | Block::Special { contents, .. } => Some(contents),
+                | Block::Special { contents, .. }
+                | Block::Verse { contents, .. } => Some(contents),
                 _ => None,
             },
 
@@ -638,6 +770,7 @@ impl<'a> Expr<'a> {
                 PlainOrRec::Rec(rec) => Some(rec),
             },
             Expr::Drawer(inner) => Some(&inner.children),
+            Expr::DynamicBlock(inner) => Some(&inner.children),
             Expr::FootnoteDef(inner) => Some(&inner.children),
             Expr::FootnoteRef(inner) => inner.children.as_ref(),
             _ => None,
@@ -662,6 +795,7 @@ impl<'a> Expr<'a> {
                 println!("keyword: {:#?}", inner.keyword);
                 println!("priority: {:#?}", inner.priority);
                 println!("tags: {:#?}", inner.tags);
+                println!("planning: {:#?}", inner.planning);
                 println!("properties: {:#?}", inner.properties);
                 print!("title: ");
                 if let Some(title) = &inner.title {
@@ -686,7 +820,8 @@ impl<'a> Expr<'a> {
                 match inner {
                     Block::Center { contents, .. }
                     | Block::Quote { contents, .. }
-                    | Block::Special { contents, .. } => {
+                    | Block::Special { contents, .. }
+                    | Block::Verse { contents, .. } => {
                         for id in contents {
                             pool[*id].obj.print_tree(pool);
                             print!(",");
@@ -695,8 +830,7 @@ impl<'a> Expr<'a> {
                     Block::Comment { contents, .. }
                     | Block::Example { contents, .. }
                     | Block::Export { contents, .. }
-                    | Block::Src { contents, .. }
-                    | Block::Verse { contents, .. } => {
+                    | Block::Src { contents, .. } => {
                         println!("{contents:#?}");
                     }
                 }
@@ -828,6 +962,19 @@ impl<'a> Expr<'a> {
             Expr::MacroDef(inner) => print!("{inner:#?}"),
             Expr::FootnoteDef(inner) => print!("{inner:#?}"),
             Expr::FootnoteRef(inner) => print!("{inner:#?}"),
+            Expr::Timestamp(inner) => print!("{inner:#?}"),
+            Expr::Clock(inner) => print!("{inner:#?}"),
+            Expr::RadioTarget(inner) => print!("{inner:#?}"),
+            Expr::RadioLink(inner) => print!("{inner:#?}"),
+            Expr::Citation(inner) => print!("{inner:#?}"),
+            Expr::StatisticsCookie(inner) => print!("{inner:#?}"),
+            Expr::InlineTask(inner) => print!("{inner:#?}"),
+            Expr::FixedWidth(inner) => print!("{inner:#?}"),
+            Expr::SexpTimestamp(inner) => print!("{inner:#?}"),
+            Expr::DiarySexp(inner) => print!("{inner:#?}"),
+            Expr::DynamicBlock(inner) => print!("{inner:#?}"),
+            Expr::BabelCall(inner) => print!("{inner:#?}"),
+            Expr::InlineBabelCall(inner) => print!("{inner:#?}"),
         }
     }
 }
@@ -883,6 +1030,19 @@ impl<'a> std::fmt::Debug for Expr<'a> {
                 Expr::MacroDef(inner) => f.write_fmt(format_args!("{inner:#?}")),
                 Expr::FootnoteDef(inner) => f.write_fmt(format_args!("{inner:#?}")),
                 Expr::FootnoteRef(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::Timestamp(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::Clock(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::RadioTarget(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::RadioLink(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::Citation(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::StatisticsCookie(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::InlineTask(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::FixedWidth(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::SexpTimestamp(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::DiarySexp(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::DynamicBlock(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::BabelCall(inner) => f.write_fmt(format_args!("{inner:#?}")),
+                Expr::InlineBabelCall(inner) => f.write_fmt(format_args!("{inner:#?}")),
             }
         } else {
             match self {
@@ -926,6 +1086,19 @@ impl<'a> std::fmt::Debug for Expr<'a> {
                 Expr::ExportSnippet(inner) => f.write_fmt(format_args!("{inner:?}")),
                 Expr::Affiliated(inner) => f.write_fmt(format_args!("{inner:?}")),
                 Expr::MacroDef(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::Timestamp(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::Clock(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::RadioTarget(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::RadioLink(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::Citation(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::StatisticsCookie(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::InlineTask(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::FixedWidth(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::SexpTimestamp(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::DiarySexp(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::DynamicBlock(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::BabelCall(inner) => f.write_fmt(format_args!("{inner:?}")),
+                Expr::InlineBabelCall(inner) => f.write_fmt(format_args!("{inner:?}")),
             }
         }
     }
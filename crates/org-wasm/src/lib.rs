@@ -114,7 +114,7 @@ fn expr_to_kind(val: &Expr) -> i32 {
         Expr::Bold(_) => 2,
         Expr::Entity(_) => 3,
         Expr::Emoji(_) => 4,
-        Expr::Target(_) => 5,
+        Expr::Target(_) | Expr::RadioTarget(_) => 5,
         Expr::Macro(_) => 6,
         Expr::Underline(_) => 7,
         Expr::Verbatim(_) => 8,
@@ -126,7 +126,7 @@ fn expr_to_kind(val: &Expr) -> i32 {
         Expr::ExportSnippet(_) => 14,
         Expr::Keyword(_) | Expr::MacroDef(_) | Expr::Affiliated(_) => 15,
         Expr::Block(_) | Expr::LatexEnv(_) => 16,
-        Expr::RegularLink(_) => 17,
+        Expr::RegularLink(_) | Expr::RadioLink(_) => 17,
         Expr::Table(_) | Expr::TableRow(_) | Expr::TableCell(_) => 18,
         Expr::Paragraph(_) => 19,
         Expr::Plain(_) => 20,